@@ -1,2 +0,0 @@
-pub mod order_model;
-pub mod request_error;
\ No newline at end of file
@@ -1,5 +0,0 @@
-pub mod base_strategy;
-pub mod portfolio;
-pub mod strategy_error;
-pub mod common_module;
-mod order;
\ No newline at end of file
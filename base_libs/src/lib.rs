@@ -1,7 +0,0 @@
-pub mod mongo_engine;
-pub mod market_data_module;
-pub mod base_strategy;
-pub mod kline_basic;
-pub mod tools;
-pub mod base_model;
-pub mod base_enums;
\ No newline at end of file
@@ -1,2 +0,0 @@
-pub mod ws_data;
-pub mod rest_data;
\ No newline at end of file
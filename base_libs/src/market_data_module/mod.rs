@@ -1,3 +0,0 @@
-pub mod binance_data;
-pub mod general_data;
-pub mod general_enum;
\ No newline at end of file
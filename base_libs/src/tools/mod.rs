@@ -1,3 +0,0 @@
-pub mod time_tools;
-pub mod common_tools;
-pub mod parse_data;
\ No newline at end of file
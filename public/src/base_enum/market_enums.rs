@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 pub enum MarketType {
     SPOT,
     FUTURES,
@@ -12,8 +14,18 @@ impl MarketType {
     }
 }
 
-#[derive(PartialEq)]
+/// Price series of a futures kline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceType {
+    Last,
+    Mark,
+    Index,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Interval {
+    Min1,
+    Min3,
     Min5,
     Min10,
     Min15,
@@ -21,20 +33,72 @@ pub enum Interval {
     Hour1,
     Hour2,
     Hour4,
+    Hour6,
+    Hour8,
+    Hour12,
     Day,
 }
 
 impl Interval {
-    pub fn get_divider(&self) -> usize {
+    /// Binance interval string, e.g. "5m". `Min10` is only a resampling target.
+    pub fn get_interval_string(&self) -> String {
         match self {
-            Interval::Min5 => 1,
-            Interval::Min10 => 2,
-            Interval::Min15 => 3,
-            Interval::Min30 => 6,
-            Interval::Hour1 => 12,
-            Interval::Hour2 => 24,
-            Interval::Hour4 => 48,
-            Interval::Day => 12 * 24,
+            Interval::Min1 => "1m".to_string(),
+            Interval::Min3 => "3m".to_string(),
+            Interval::Min5 => "5m".to_string(),
+            Interval::Min10 => "10m".to_string(),
+            Interval::Min15 => "15m".to_string(),
+            Interval::Min30 => "30m".to_string(),
+            Interval::Hour1 => "1h".to_string(),
+            Interval::Hour2 => "2h".to_string(),
+            Interval::Hour4 => "4h".to_string(),
+            Interval::Hour6 => "6h".to_string(),
+            Interval::Hour8 => "8h".to_string(),
+            Interval::Hour12 => "12h".to_string(),
+            Interval::Day => "1d".to_string(),
+        }
+    }
+
+    pub fn get_millis(&self) -> i64 {
+        let minutes = match self {
+            Interval::Min1 => 1,
+            Interval::Min3 => 3,
+            Interval::Min5 => 5,
+            Interval::Min10 => 10,
+            Interval::Min15 => 15,
+            Interval::Min30 => 30,
+            Interval::Hour1 => 60,
+            Interval::Hour2 => 2 * 60,
+            Interval::Hour4 => 4 * 60,
+            Interval::Hour6 => 6 * 60,
+            Interval::Hour8 => 8 * 60,
+            Interval::Hour12 => 12 * 60,
+            Interval::Day => 24 * 60,
+        };
+        minutes * 60 * 1000
+    }
+
+    /// Number of `base_interval` klines combined into one kline of this interval.
+    pub fn get_divider(&self, base_interval: &Interval) -> usize {
+        (self.get_millis() / base_interval.get_millis()).max(1) as usize
+    }
+}
+
+/// Base kline interval per symbol, 5m unless set.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolIntervals {
+    intervals: HashMap<String, Interval>,
+}
+
+impl SymbolIntervals {
+    pub fn set_interval(&mut self, symbol: &str, interval: Interval) {
+        self.intervals.insert(symbol.to_string(), interval);
+    }
+
+    pub fn get_interval(&self, symbol: &str) -> Interval {
+        match self.intervals.get(symbol) {
+            Some(interval) => *interval,
+            None => Interval::Min5,
         }
     }
-}
\ No newline at end of file
+}
@@ -50,16 +50,117 @@ impl OrderType {
     }
 
     pub fn parse_order_type(order_type: &str) -> OrderType {
+        match Self::try_parse_order_type(order_type) {
+            Some(order_type) => order_type,
+            None => panic!("Invalid order type"),
+        }
+    }
+
+    /// Same as `parse_order_type` for values coming from requests.
+    pub fn try_parse_order_type(order_type: &str) -> Option<OrderType> {
         match order_type {
-            "LIMIT" => OrderType::Limit,
-            "MARKET" => OrderType::Market,
-            "TAKE_PROFIT_MARKET" => OrderType::TakeProfitMarket,
-            "STOP_MARKET" => OrderType::StopMarket,
-            "STOP" => OrderType::Stop,
-            "TAKE_PROFIT" => OrderType::TakeProfit,
-            "LIQUIDATION" => OrderType::Liquidation,
-            "TRAILING_STOP_MARKET" => OrderType::TrailingStopMarket,
-            _ => panic!("Invalid order type"),
+            "LIMIT" => Some(OrderType::Limit),
+            "MARKET" => Some(OrderType::Market),
+            "TAKE_PROFIT_MARKET" => Some(OrderType::TakeProfitMarket),
+            "STOP_MARKET" => Some(OrderType::StopMarket),
+            "STOP" => Some(OrderType::Stop),
+            "TAKE_PROFIT" => Some(OrderType::TakeProfit),
+            "LIQUIDATION" => Some(OrderType::Liquidation),
+            "TRAILING_STOP_MARKET" => Some(OrderType::TrailingStopMarket),
+            _ => None,
+        }
+    }
+
+    /// Types triggered by a stop price.
+    pub fn is_conditional(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Stop
+                | OrderType::StopMarket
+                | OrderType::TakeProfit
+                | OrderType::TakeProfitMarket
+                | OrderType::TrailingStopMarket
+        )
+    }
+}
+
+/// GTX is post only: rejected instead of taking liquidity.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    GTC,
+    IOC,
+    FOK,
+    GTX,
+}
+
+impl TimeInForce {
+    pub fn string(&self) -> String {
+        match self {
+            TimeInForce::GTC => "GTC".to_string(),
+            TimeInForce::IOC => "IOC".to_string(),
+            TimeInForce::FOK => "FOK".to_string(),
+            TimeInForce::GTX => "GTX".to_string(),
+        }
+    }
+
+    pub fn try_parse_time_in_force(time_in_force: &str) -> Option<TimeInForce> {
+        match time_in_force {
+            "GTC" => Some(TimeInForce::GTC),
+            "IOC" => Some(TimeInForce::IOC),
+            "FOK" => Some(TimeInForce::FOK),
+            "GTX" => Some(TimeInForce::GTX),
+            _ => None,
+        }
+    }
+}
+
+/// `Both` in one-way mode, `Long` or `Short` in hedge mode.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PositionSide {
+    Both,
+    Long,
+    Short,
+}
+
+impl PositionSide {
+    pub fn string(&self) -> String {
+        match self {
+            PositionSide::Both => "BOTH".to_string(),
+            PositionSide::Long => "LONG".to_string(),
+            PositionSide::Short => "SHORT".to_string(),
+        }
+    }
+
+    pub fn try_parse_position_side(position_side: &str) -> Option<PositionSide> {
+        match position_side {
+            "BOTH" => Some(PositionSide::Both),
+            "LONG" => Some(PositionSide::Long),
+            "SHORT" => Some(PositionSide::Short),
+            _ => None,
+        }
+    }
+}
+
+/// Price that triggers conditional orders.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WorkingType {
+    ContractPrice,
+    MarkPrice,
+}
+
+impl WorkingType {
+    pub fn string(&self) -> String {
+        match self {
+            WorkingType::ContractPrice => "CONTRACT_PRICE".to_string(),
+            WorkingType::MarkPrice => "MARK_PRICE".to_string(),
+        }
+    }
+
+    pub fn try_parse_working_type(working_type: &str) -> Option<WorkingType> {
+        match working_type {
+            "CONTRACT_PRICE" => Some(WorkingType::ContractPrice),
+            "MARK_PRICE" => Some(WorkingType::MarkPrice),
+            _ => None,
         }
     }
 }
@@ -100,4 +201,4 @@ impl OrderStatus {
             OrderStatus::ExpiredInMatch => "EXPIRED_IN_MATCH".to_string(),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,13 +1,22 @@
-use crate::base_model::market_model::{depth_model::Depth, kline_model::Kline};
+use serde::{Deserialize, Serialize};
 
+use crate::base_model::market_model::{
+    book_ticker_model::BookTicker, depth_model::Depth, force_order_model::ForceOrder,
+    kline_model::Kline, mark_price_model::MarkPrice, trade_model::Trade,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MarketDataType {
     Kline(Kline),
     Depth(Depth),
+    Trade(Trade),
+    AggTrade(Trade),
+    BookTicker(BookTicker),
+    MarkPrice(MarkPrice),
+    ForceOrder(ForceOrder),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketData {
     symbol: String,
     data: MarketDataType,
@@ -35,4 +44,37 @@ impl MarketData {
             _ => None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_data(&self) -> &MarketDataType {
+        &self.data
+    }
+
+    /// Raw or aggregate trade.
+    pub fn get_trade(&self) -> Option<Trade> {
+        match &self.data {
+            MarketDataType::Trade(trade) | MarketDataType::AggTrade(trade) => Some(*trade),
+            _ => None,
+        }
+    }
+
+    pub fn get_book_ticker(&self) -> Option<BookTicker> {
+        match &self.data {
+            MarketDataType::BookTicker(book_ticker) => Some(*book_ticker),
+            _ => None,
+        }
+    }
+
+    pub fn get_mark_price(&self) -> Option<MarkPrice> {
+        match &self.data {
+            MarketDataType::MarkPrice(mark_price) => Some(*mark_price),
+            _ => None,
+        }
+    }
+
+    pub fn get_force_order(&self) -> Option<ForceOrder> {
+        match &self.data {
+            MarketDataType::ForceOrder(force_order) => Some(*force_order),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// Best bid and ask, pushed on every change.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BookTicker {
+    update_id: i64,
+    bid_price: f64,
+    bid_qty: f64,
+    ask_price: f64,
+    ask_qty: f64,
+    timestamp: i64,
+}
+
+impl BookTicker {
+    pub fn new(
+        update_id: i64,
+        bid_price: f64,
+        bid_qty: f64,
+        ask_price: f64,
+        ask_qty: f64,
+        timestamp: i64,
+    ) -> Self {
+        BookTicker {
+            update_id,
+            bid_price,
+            bid_qty,
+            ask_price,
+            ask_qty,
+            timestamp,
+        }
+    }
+
+    pub fn get_update_id(&self) -> i64 {
+        self.update_id
+    }
+
+    pub fn get_bid_price(&self) -> f64 {
+        self.bid_price
+    }
+
+    pub fn get_bid_qty(&self) -> f64 {
+        self.bid_qty
+    }
+
+    pub fn get_ask_price(&self) -> f64 {
+        self.ask_price
+    }
+
+    pub fn get_ask_qty(&self) -> f64 {
+        self.ask_qty
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn get_mid_price(&self) -> f64 {
+        (self.bid_price + self.ask_price) / 2.0
+    }
+}
@@ -1,4 +1,7 @@
-#[derive(Debug, Clone, Copy)]
+use crate::base_enum::order_enums::OrderSide;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct PriceLevel {
     price: f64,
     quantity: f64,
@@ -18,7 +21,8 @@ impl PriceLevel {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Book levels from the best price outwards: asks ascending, bids descending.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Depth {
     asks: Vec<PriceLevel>,
     bids: Vec<PriceLevel>,
@@ -38,14 +42,52 @@ impl Depth {
     }
 
     pub fn get_best_bid(&self) -> PriceLevel {
-        self.bids[0].clone()
+        self.bids[0]
     }
 
     pub fn get_best_ask(&self) -> PriceLevel {
-        self.asks.last().unwrap().clone()
+        self.asks[0]
     }
 
     pub fn get_mid_price(&self) -> f64 {
         (self.get_best_bid().get_price() + self.get_best_ask().get_price()) / 2.0
     }
-}
\ No newline at end of file
+
+    /// Quantity quoted at `price` or better, on the bids for `BUY` and the asks for `SELL`.
+    pub fn get_depth_at_price(&self, side: OrderSide, price: f64) -> f64 {
+        match side {
+            OrderSide::BUY => self
+                .bids
+                .iter()
+                .filter(|x| x.get_price() >= price)
+                .map(|x| x.get_quantity())
+                .sum(),
+            OrderSide::SELL => self
+                .asks
+                .iter()
+                .filter(|x| x.get_price() <= price)
+                .map(|x| x.get_quantity())
+                .sum(),
+        }
+    }
+
+    /// (bid qty - ask qty) / (bid qty + ask qty) over the best `levels` levels, in [-1, 1].
+    pub fn get_imbalance(&self, levels: usize) -> f64 {
+        let bid_qty: f64 = self
+            .bids
+            .iter()
+            .take(levels)
+            .map(|x| x.get_quantity())
+            .sum();
+        let ask_qty: f64 = self
+            .asks
+            .iter()
+            .take(levels)
+            .map(|x| x.get_quantity())
+            .sum();
+        if bid_qty + ask_qty == 0.0 {
+            return 0.0;
+        }
+        (bid_qty - ask_qty) / (bid_qty + ask_qty)
+    }
+}
@@ -0,0 +1,65 @@
+use crate::base_enum::order_enums::{OrderSide, OrderStatus};
+use serde::{Deserialize, Serialize};
+
+/// A liquidation order. `side` is the side of the liquidation order, so `SELL`
+/// liquidates a long.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ForceOrder {
+    side: OrderSide,
+    price: f64,
+    avg_price: f64,
+    qty: f64,
+    filled_qty: f64,
+    status: OrderStatus,
+    timestamp: i64,
+}
+
+impl ForceOrder {
+    pub fn new(
+        side: OrderSide,
+        price: f64,
+        avg_price: f64,
+        qty: f64,
+        filled_qty: f64,
+        status: OrderStatus,
+        timestamp: i64,
+    ) -> Self {
+        ForceOrder {
+            side,
+            price,
+            avg_price,
+            qty,
+            filled_qty,
+            status,
+            timestamp,
+        }
+    }
+
+    pub fn get_side(&self) -> OrderSide {
+        self.side
+    }
+
+    pub fn get_price(&self) -> f64 {
+        self.price
+    }
+
+    pub fn get_avg_price(&self) -> f64 {
+        self.avg_price
+    }
+
+    pub fn get_qty(&self) -> f64 {
+        self.qty
+    }
+
+    pub fn get_filled_qty(&self) -> f64 {
+        self.filled_qty
+    }
+
+    pub fn get_status(&self) -> OrderStatus {
+        self.status
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// A settled funding rate of a perpetual contract. Positive rates are paid by longs
+/// to shorts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FundingRate {
+    funding_time: i64,
+    funding_rate: f64,
+    mark_price: f64,
+}
+
+impl FundingRate {
+    pub fn new(funding_time: i64, funding_rate: f64, mark_price: f64) -> Self {
+        FundingRate {
+            funding_time,
+            funding_rate,
+            mark_price,
+        }
+    }
+
+    pub fn get_funding_time(&self) -> i64 {
+        self.funding_time
+    }
+
+    pub fn get_funding_rate(&self) -> f64 {
+        self.funding_rate
+    }
+
+    /// 0 when the exchange did not report it.
+    pub fn get_mark_price(&self) -> f64 {
+        self.mark_price
+    }
+}
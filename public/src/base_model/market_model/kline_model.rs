@@ -1,5 +1,73 @@
+use crate::base_enum::market_enums::Interval;
+use crate::tools::kline_tools;
 use serde::{Deserialize, Serialize};
 
+pub struct CombineKline {
+    klines: Vec<Kline>,
+    base_interval: Interval,
+    interval: Interval,
+}
+
+impl Default for CombineKline {
+    fn default() -> Self {
+        CombineKline {
+            klines: vec![],
+            base_interval: Interval::Min5,
+            interval: Interval::Min5,
+        }
+    }
+}
+
+impl CombineKline {
+    /// Combines 5m klines, see `set_base_interval` for other feeds.
+    pub fn new(klines: Vec<Kline>, interval: Interval) -> Self {
+        CombineKline {
+            klines,
+            base_interval: Interval::Min5,
+            interval,
+        }
+    }
+
+    pub fn set_interval(&mut self, interval: Interval) {
+        self.interval = interval;
+    }
+
+    pub fn set_base_interval(&mut self, base_interval: Interval) {
+        self.base_interval = base_interval;
+    }
+
+    pub fn get_interval(&self) -> Interval {
+        self.interval
+    }
+
+    /// Klines opening on an interval boundary start a new bar, so bars stay aligned
+    /// like `resample_kline_data` and a bar with missing klines is dropped.
+    pub fn add(&mut self, kline: Kline) {
+        if kline_tools::is_interval_start(kline.get_open_time(), &self.interval) {
+            self.klines.clear();
+        }
+        self.klines.push(kline);
+        if self.klines.len() > self.interval.get_divider(&self.base_interval) {
+            self.klines.remove(0);
+        }
+    }
+
+    /// The combined bar once its last kline has been added.
+    pub fn get_kline(&mut self) -> Option<Kline> {
+        if self.klines.len() == self.interval.get_divider(&self.base_interval)
+            && kline_tools::is_interval_start(self.klines[0].get_open_time(), &self.interval)
+        {
+            let mut res = self.klines[0];
+            for i in 1..self.klines.len() {
+                res = res.combine(&self.klines[i]);
+            }
+            self.klines.clear();
+            return Some(res);
+        }
+        None
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct Kline {
     open_time: i64,
@@ -112,4 +180,4 @@ impl Kline {
     pub fn get_close_time(&self) -> i64 {
         self.close_time
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Mark and index price with the current funding rate estimate.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MarkPrice {
+    timestamp: i64,
+    mark_price: f64,
+    index_price: f64,
+    funding_rate: f64,
+    next_funding_time: i64,
+}
+
+impl MarkPrice {
+    pub fn new(
+        timestamp: i64,
+        mark_price: f64,
+        index_price: f64,
+        funding_rate: f64,
+        next_funding_time: i64,
+    ) -> Self {
+        MarkPrice {
+            timestamp,
+            mark_price,
+            index_price,
+            funding_rate,
+            next_funding_time,
+        }
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn get_mark_price(&self) -> f64 {
+        self.mark_price
+    }
+
+    pub fn get_index_price(&self) -> f64 {
+        self.index_price
+    }
+
+    pub fn get_funding_rate(&self) -> f64 {
+        self.funding_rate
+    }
+
+    pub fn get_next_funding_time(&self) -> i64 {
+        self.next_funding_time
+    }
+}
@@ -1,2 +1,9 @@
+pub mod book_ticker_model;
+pub mod depth_model;
+pub mod force_order_model;
+pub mod funding_rate_model;
 pub mod kline_model;
-pub mod depth_model;
\ No newline at end of file
+pub mod mark_price_model;
+pub mod open_interest_model;
+pub mod order_book_model;
+pub mod trade_model;
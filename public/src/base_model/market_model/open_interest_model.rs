@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// Open interest of a symbol summed over the statistics period ending at `timestamp`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct OpenInterest {
+    timestamp: i64,
+    sum_open_interest: f64,
+    sum_open_interest_value: f64,
+}
+
+impl OpenInterest {
+    pub fn new(timestamp: i64, sum_open_interest: f64, sum_open_interest_value: f64) -> Self {
+        OpenInterest {
+            timestamp,
+            sum_open_interest,
+            sum_open_interest_value,
+        }
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// In contracts.
+    pub fn get_sum_open_interest(&self) -> f64 {
+        self.sum_open_interest
+    }
+
+    /// In quote asset.
+    pub fn get_sum_open_interest_value(&self) -> f64 {
+        self.sum_open_interest_value
+    }
+}
@@ -0,0 +1,291 @@
+use super::depth_model::{Depth, PriceLevel};
+use crate::base_enum::order_enums::OrderSide;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// One diff depth event. Levels with a zero quantity are removed from the book.
+#[derive(Debug, Clone)]
+pub struct DepthUpdate {
+    event_time: i64,
+    first_update_id: i64,
+    final_update_id: i64,
+    prev_final_update_id: i64,
+    bids: Vec<PriceLevel>,
+    asks: Vec<PriceLevel>,
+}
+
+impl DepthUpdate {
+    pub fn new(
+        event_time: i64,
+        first_update_id: i64,
+        final_update_id: i64,
+        prev_final_update_id: i64,
+        bids: Vec<PriceLevel>,
+        asks: Vec<PriceLevel>,
+    ) -> Self {
+        DepthUpdate {
+            event_time,
+            first_update_id,
+            final_update_id,
+            prev_final_update_id,
+            bids,
+            asks,
+        }
+    }
+
+    pub fn get_event_time(&self) -> i64 {
+        self.event_time
+    }
+
+    /// Binance `U`.
+    pub fn get_first_update_id(&self) -> i64 {
+        self.first_update_id
+    }
+
+    /// Binance `u`.
+    pub fn get_final_update_id(&self) -> i64 {
+        self.final_update_id
+    }
+
+    /// Binance `pu`, the `u` of the previous event.
+    pub fn get_prev_final_update_id(&self) -> i64 {
+        self.prev_final_update_id
+    }
+
+    pub fn get_bids(&self) -> &Vec<PriceLevel> {
+        &self.bids
+    }
+
+    pub fn get_asks(&self) -> &Vec<PriceLevel> {
+        &self.asks
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderBookUpdate {
+    Applied,
+    /// Already contained in the snapshot.
+    Skipped,
+    /// No snapshot yet or an update was missed, the book needs a new snapshot.
+    OutOfSync,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BookPrice(f64);
+
+impl Eq for BookPrice {}
+
+impl PartialOrd for BookPrice {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BookPrice {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Local L2 book kept in sync from a REST snapshot and diff depth events, following
+/// the Binance futures procedure: events older than the snapshot are skipped, the
+/// first applied event must straddle the snapshot id and every later event's `pu`
+/// must match the previous `u`.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    last_update_id: Option<i64>,
+    is_first_update: bool,
+    update_time: i64,
+    bids: BTreeMap<BookPrice, f64>,
+    asks: BTreeMap<BookPrice, f64>,
+}
+
+impl OrderBook {
+    pub fn apply_snapshot(
+        &mut self,
+        last_update_id: i64,
+        bids: &[PriceLevel],
+        asks: &[PriceLevel],
+    ) {
+        self.bids.clear();
+        self.asks.clear();
+        Self::update_levels(&mut self.bids, bids);
+        Self::update_levels(&mut self.asks, asks);
+        self.last_update_id = Some(last_update_id);
+        self.is_first_update = true;
+    }
+
+    pub fn apply_update(&mut self, update: &DepthUpdate) -> OrderBookUpdate {
+        let Some(last_update_id) = self.last_update_id else {
+            return OrderBookUpdate::OutOfSync;
+        };
+        if update.get_final_update_id() < last_update_id {
+            return OrderBookUpdate::Skipped;
+        }
+        let in_sequence = if self.is_first_update {
+            update.get_first_update_id() <= last_update_id
+        } else {
+            update.get_prev_final_update_id() == last_update_id
+        };
+        if !in_sequence {
+            self.reset();
+            return OrderBookUpdate::OutOfSync;
+        }
+        Self::update_levels(&mut self.bids, update.get_bids());
+        Self::update_levels(&mut self.asks, update.get_asks());
+        self.last_update_id = Some(update.get_final_update_id());
+        self.is_first_update = false;
+        self.update_time = update.get_event_time();
+        OrderBookUpdate::Applied
+    }
+
+    fn update_levels(book: &mut BTreeMap<BookPrice, f64>, levels: &[PriceLevel]) {
+        for level in levels {
+            if level.get_quantity() == 0.0 {
+                book.remove(&BookPrice(level.get_price()));
+            } else {
+                book.insert(BookPrice(level.get_price()), level.get_quantity());
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = OrderBook::default();
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.last_update_id.is_some()
+    }
+
+    pub fn get_last_update_id(&self) -> Option<i64> {
+        self.last_update_id
+    }
+
+    /// Event time of the last applied update.
+    pub fn get_update_time(&self) -> i64 {
+        self.update_time
+    }
+
+    pub fn get_best_bid(&self) -> Option<PriceLevel> {
+        self.bids
+            .iter()
+            .next_back()
+            .map(|(price, qty)| PriceLevel::new(price.0, *qty))
+    }
+
+    pub fn get_best_ask(&self) -> Option<PriceLevel> {
+        self.asks
+            .iter()
+            .next()
+            .map(|(price, qty)| PriceLevel::new(price.0, *qty))
+    }
+
+    pub fn get_mid_price(&self) -> Option<f64> {
+        match (self.get_best_bid(), self.get_best_ask()) {
+            (Some(bid), Some(ask)) => Some((bid.get_price() + ask.get_price()) / 2.0),
+            _ => None,
+        }
+    }
+
+    /// Quantity quoted at `price` or better, on the bids for `BUY` and the asks for `SELL`.
+    pub fn get_depth_at_price(&self, side: OrderSide, price: f64) -> f64 {
+        match side {
+            OrderSide::BUY => self
+                .bids
+                .range(BookPrice(price)..)
+                .map(|(_, qty)| qty)
+                .sum(),
+            OrderSide::SELL => self
+                .asks
+                .range(..=BookPrice(price))
+                .map(|(_, qty)| qty)
+                .sum(),
+        }
+    }
+
+    /// (bid qty - ask qty) / (bid qty + ask qty) over the best `levels` levels, in [-1, 1].
+    pub fn get_imbalance(&self, levels: usize) -> f64 {
+        self.get_depth(levels).get_imbalance(levels)
+    }
+
+    /// The best `levels` levels of each side.
+    pub fn get_depth(&self, levels: usize) -> Depth {
+        let asks = self
+            .asks
+            .iter()
+            .take(levels)
+            .map(|(price, qty)| PriceLevel::new(price.0, *qty))
+            .collect();
+        let bids = self
+            .bids
+            .iter()
+            .rev()
+            .take(levels)
+            .map(|(price, qty)| PriceLevel::new(price.0, *qty))
+            .collect();
+        Depth::new(asks, bids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[(f64, f64)]) -> Vec<PriceLevel> {
+        levels
+            .iter()
+            .map(|(p, q)| PriceLevel::new(*p, *q))
+            .collect()
+    }
+
+    fn test_book() -> OrderBook {
+        let mut book = OrderBook::default();
+        book.apply_snapshot(
+            100,
+            &levels(&[(99.0, 1.0), (98.0, 2.0)]),
+            &levels(&[(101.0, 1.0), (102.0, 3.0)]),
+        );
+        book
+    }
+
+    #[test]
+    fn test_order_book_sequence() {
+        let mut book = test_book();
+        let stale = DepthUpdate::new(0, 90, 99, 89, vec![], vec![]);
+        assert_eq!(book.apply_update(&stale), OrderBookUpdate::Skipped);
+
+        let first = DepthUpdate::new(
+            1,
+            95,
+            105,
+            94,
+            levels(&[(99.0, 0.0), (99.5, 4.0)]),
+            levels(&[(100.5, 1.0)]),
+        );
+        assert_eq!(book.apply_update(&first), OrderBookUpdate::Applied);
+        assert_eq!(book.get_best_bid().unwrap().get_price(), 99.5);
+        assert_eq!(book.get_best_ask().unwrap().get_price(), 100.5);
+        assert_eq!(book.get_mid_price(), Some(100.0));
+
+        let next = DepthUpdate::new(2, 106, 110, 105, vec![], levels(&[(100.5, 0.0)]));
+        assert_eq!(book.apply_update(&next), OrderBookUpdate::Applied);
+        assert_eq!(book.get_best_ask().unwrap().get_price(), 101.0);
+
+        let gap = DepthUpdate::new(3, 115, 120, 112, vec![], vec![]);
+        assert_eq!(book.apply_update(&gap), OrderBookUpdate::OutOfSync);
+        assert!(!book.is_synced());
+    }
+
+    #[test]
+    fn test_order_book_depth() {
+        let book = test_book();
+        assert_eq!(book.get_depth_at_price(OrderSide::BUY, 98.0), 3.0);
+        assert_eq!(book.get_depth_at_price(OrderSide::SELL, 101.5), 1.0);
+        assert!((book.get_imbalance(2) - (3.0 - 4.0) / 7.0).abs() < 1e-12);
+
+        let depth = book.get_depth(1);
+        assert_eq!(depth.get_best_bid().get_price(), 99.0);
+        assert_eq!(depth.get_best_ask().get_price(), 101.0);
+        assert_eq!(depth.get_depth_at_price(OrderSide::SELL, 105.0), 1.0);
+    }
+}
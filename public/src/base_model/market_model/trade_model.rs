@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// A public trade, or an aggregate of fills of one taker order at one price where
+/// `trade_id` is the aggregate id.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Trade {
+    trade_id: i64,
+    price: f64,
+    qty: f64,
+    timestamp: i64,
+    is_buyer_maker: bool,
+}
+
+impl Trade {
+    pub fn new(trade_id: i64, price: f64, qty: f64, timestamp: i64, is_buyer_maker: bool) -> Self {
+        Trade {
+            trade_id,
+            price,
+            qty,
+            timestamp,
+            is_buyer_maker,
+        }
+    }
+
+    pub fn get_trade_id(&self) -> i64 {
+        self.trade_id
+    }
+
+    pub fn get_price(&self) -> f64 {
+        self.price
+    }
+
+    pub fn get_qty(&self) -> f64 {
+        self.qty
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// True when the taker sold.
+    pub fn is_buyer_maker(&self) -> bool {
+        self.is_buyer_maker
+    }
+}
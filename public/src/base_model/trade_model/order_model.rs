@@ -12,6 +12,8 @@ pub struct Order {
     avg_price: f64,
     filled_qty: f64,
     fee: f64,
+    #[serde(default)]
+    realized_pnl: f64,
     order_type: OrderType,
     cid: String,
     oid: String,
@@ -29,6 +31,7 @@ impl Default for Order {
             avg_price: 0.0,
             filled_qty: 0.0,
             fee: 0.0,
+            realized_pnl: 0.0,
             order_type: OrderType::Limit,
             cid: "".to_string(),
             oid: "".to_string(),
@@ -60,6 +63,7 @@ impl Order {
             avg_price,
             filled_qty,
             fee: 0.0,
+            realized_pnl: 0.0,
             order_type,
             cid: cid.to_string(),
             oid: oid.to_string(),
@@ -124,6 +128,10 @@ impl Order {
         self.qty = qty;
     }
 
+    pub fn set_order_type(&mut self, order_type: OrderType) {
+        self.order_type = order_type;
+    }
+
     pub fn set_side(&mut self, side: OrderSide) {
         self.side = side;
     }
@@ -148,10 +156,23 @@ impl Order {
         self.fee
     }
 
+    /// Realized profit of the trade an order update reports, like its fee.
+    pub fn set_realized_pnl(&mut self, realized_pnl: f64) {
+        self.realized_pnl = realized_pnl;
+    }
+
+    pub fn get_realized_pnl(&self) -> f64 {
+        self.realized_pnl
+    }
+
     pub fn set_status(&mut self, status: OrderStatus) {
         self.status = status;
     }
 
+    pub fn set_cid(&mut self, cid: &str) {
+        self.cid = cid.to_string();
+    }
+
     pub fn get_strategy_name(&self) -> String {
         let cid_parts: Vec<&str> = self.cid.split("_").collect();
         cid_parts[0].to_string()
@@ -210,8 +231,8 @@ impl OrderResponse {
             self.quantity.parse().unwrap(),
             OrderSide::parse_order_side(&self.side),
             OrderType::parse_order_type(&self.order_type),
-            0.0,
-            0.0,
+            self.avg_price.parse().unwrap_or(0.0),
+            self.filled_qty.parse().unwrap_or(0.0),
             &self.cid,
             &self.order_id.to_string(),
             OrderStatus::parse_order_status(&self.status),
@@ -73,10 +73,26 @@ impl Position {
         self.price
     }
 
+    /// Quantity with sign, positive for long and negative for short.
+    pub fn get_signed_quantity(&self) -> f64 {
+        match self.side {
+            OrderSide::BUY => self.quantity,
+            OrderSide::SELL => -self.quantity,
+        }
+    }
+
     pub fn get_break_even_price(&self) -> f64 {
         self.break_even_price
     }
 
+    /// Notional value at the last market price.
+    pub fn get_notional(&self) -> f64 {
+        match self.side {
+            OrderSide::BUY => self.quantity * self.price + self.unrealized_pnl,
+            OrderSide::SELL => self.quantity * self.price - self.unrealized_pnl,
+        }
+    }
+
     pub fn get_unrealized_pnl(&self) -> f64 {
         self.unrealized_pnl
     }
@@ -89,6 +105,10 @@ impl Position {
         self.margin
     }
 
+    pub fn get_leverage(&self) -> f64 {
+        self.leverage
+    }
+
     pub fn get_timestamp(&self) -> i64 {
         self.timestamp
     }
@@ -132,8 +152,9 @@ impl Position {
                     }
                 } else if remain_qty < 0.0 {
                     let delta_amt = (order.get_avg_price() - self.price) * order.get_filled_qty();
-                    self.quantity = remain_qty;
-                    self.margin -= tmp_margin;
+                    // release margin in proportion to the closed quantity at entry price
+                    self.margin -= self.margin * order.get_filled_qty() / self.quantity;
+                    self.quantity = -remain_qty;
                     if order.get_side() == OrderSide::BUY {
                         self.realized_pnl -= delta_amt;
                         return -delta_amt;
@@ -179,3 +200,36 @@ impl Position {
         self.timestamp = position.timestamp;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_enum::order_enums::{OrderStatus, OrderType};
+
+    fn filled_order(side: OrderSide, price: f64, qty: f64) -> Order {
+        Order::new(
+            "btcusdt",
+            price,
+            qty,
+            side,
+            OrderType::Limit,
+            price,
+            qty,
+            "",
+            "",
+            OrderStatus::Filled,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_partial_close_position() {
+        let mut pos = Position::default();
+        pos.update_order(&filled_order(OrderSide::BUY, 100.0, 2.0));
+        let pnl = pos.update_order(&filled_order(OrderSide::SELL, 110.0, 0.5));
+        assert_eq!(pnl, 5.0);
+        assert_eq!(pos.get_quantity(), 1.5);
+        assert_eq!(pos.get_side(), OrderSide::BUY);
+        assert!((pos.get_margin() - 1.5).abs() < 1e-9);
+    }
+}
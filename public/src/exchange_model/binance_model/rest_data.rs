@@ -1,7 +1,14 @@
 use crate::base_enum::order_enums::{OrderSide, OrderStatus, OrderType};
+use crate::base_model::market_model::depth_model::PriceLevel;
+use crate::base_model::market_model::funding_rate_model::FundingRate;
+use crate::base_model::market_model::open_interest_model::OpenInterest;
+use crate::base_model::market_model::order_book_model::OrderBook;
 use crate::base_model::trade_model::{order_model::Order, position_model::Position};
+use crate::strategy_model::margin_model::{MarginBracket, MarginBrackets};
+use crate::strategy_model::strategy_portfolio::Balance;
 use serde::Deserialize;
 use serde_json::Value;
+use std::num::ParseFloatError;
 
 #[derive(Debug, Deserialize)]
 pub struct ExchangeInfo {
@@ -42,8 +49,8 @@ impl OrderResponse {
             self.quantity.parse().unwrap(),
             OrderSide::parse_order_side(&self.side),
             OrderType::parse_order_type(&self.order_type),
-            0.0,
-            0.0,
+            self.avg_price.parse().unwrap(),
+            self.filled_qty.parse().unwrap(),
             &self.cid,
             &self.order_id.to_string(),
             OrderStatus::parse_order_status(&self.status),
@@ -76,20 +83,21 @@ pub struct PositionResponse {
 }
 
 impl PositionResponse {
+    /// `positionAmt` is negative for shorts, stored as a side and an absolute
+    /// quantity like the user data stream positions.
     pub fn convert_into_position(&self) -> Position {
-        let mut side = OrderSide::BUY;
-        let market_price: f64 = self.market_price.parse().unwrap();
         let price: f64 = self.price.parse().unwrap();
         let unrealized_pnl: f64 = self.unrealized_pnl.parse().unwrap();
-        if market_price < price && unrealized_pnl > 0.0 {
-            side = OrderSide::SELL;
-        } else if market_price > price && unrealized_pnl < 0.0 {
-            side = OrderSide::BUY;
-        }
+        let quantity: f64 = self.quantity.parse().unwrap();
+        let side = if quantity < 0.0 {
+            OrderSide::SELL
+        } else {
+            OrderSide::BUY
+        };
         Position::new(
             &self.symbol.to_lowercase(),
             price,
-            self.quantity.parse().unwrap(),
+            quantity.abs(),
             side,
             self.break_even_price.parse().unwrap(),
             self.leverage.parse().unwrap(),
@@ -100,3 +108,254 @@ impl PositionResponse {
         )
     }
 }
+
+/// One fill of `/fapi/v1/userTrades`.
+#[derive(Debug, Deserialize)]
+pub struct TradeResponse {
+    pub symbol: String,
+    pub id: i64,
+    #[serde(rename = "orderId")]
+    pub order_id: i64,
+    pub side: String,
+    pub price: String,
+    pub qty: String,
+    pub commission: String,
+    pub time: i64,
+}
+
+impl TradeResponse {
+    /// A filled order of the trade's price and quantity, `cid` and `order_type`
+    /// come from the order the trade belongs to.
+    pub fn convert_into_order(&self, cid: &str, order_type: OrderType) -> Order {
+        let price: f64 = self.price.parse().unwrap();
+        let qty: f64 = self.qty.parse().unwrap();
+        let mut order = Order::new(
+            &self.symbol.to_lowercase(),
+            price,
+            qty,
+            OrderSide::parse_order_side(&self.side),
+            order_type,
+            price,
+            qty,
+            cid,
+            &self.order_id.to_string(),
+            OrderStatus::Filled,
+            self.time,
+        );
+        order.set_fee(self.commission.parse().unwrap());
+        order
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BracketResponse {
+    #[serde(rename = "notionalCap")]
+    pub notional_cap: f64,
+    #[serde(rename = "notionalFloor")]
+    pub notional_floor: f64,
+    #[serde(rename = "maintMarginRatio")]
+    pub maint_margin_ratio: f64,
+    pub cum: f64,
+}
+
+/// Maintenance margin brackets of a symbol from `/fapi/v1/leverageBracket`.
+#[derive(Debug, Deserialize)]
+pub struct LeverageBracketResponse {
+    pub symbol: String,
+    pub brackets: Vec<BracketResponse>,
+}
+
+impl LeverageBracketResponse {
+    pub fn convert_into_margin_brackets(&self) -> MarginBrackets {
+        let mut brackets: Vec<MarginBracket> = self
+            .brackets
+            .iter()
+            .map(|b| {
+                MarginBracket::new(
+                    b.notional_floor,
+                    b.notional_cap,
+                    b.maint_margin_ratio,
+                    b.cum,
+                )
+            })
+            .collect();
+        brackets.sort_by(|a, b| a.get_notional_floor().total_cmp(&b.get_notional_floor()));
+        MarginBrackets::new(brackets)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BalanceResponse {
+    pub asset: String,
+    pub balance: String,
+    #[serde(rename = "updateTime")]
+    pub timestamp: i64,
+}
+
+impl BalanceResponse {
+    pub fn convert_into_balance(&self) -> Balance {
+        let mut balance = Balance::default();
+        balance.set_balance(self.balance.parse().unwrap());
+        balance.set_update_time(self.timestamp);
+        balance
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FundingRateResponse {
+    pub symbol: String,
+    #[serde(rename = "fundingTime")]
+    pub funding_time: i64,
+    #[serde(rename = "fundingRate")]
+    pub funding_rate: String,
+    #[serde(rename = "markPrice")]
+    pub mark_price: String,
+}
+
+impl FundingRateResponse {
+    /// Early records have an empty mark price, kept as 0.
+    pub fn convert_into_funding_rate(&self) -> Result<FundingRate, ParseFloatError> {
+        let mark_price = match self.mark_price.as_str() {
+            "" => 0.0,
+            mark_price => mark_price.parse()?,
+        };
+        Ok(FundingRate::new(
+            self.funding_time,
+            self.funding_rate.parse()?,
+            mark_price,
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenInterestResponse {
+    pub symbol: String,
+    #[serde(rename = "sumOpenInterest")]
+    pub sum_open_interest: String,
+    #[serde(rename = "sumOpenInterestValue")]
+    pub sum_open_interest_value: String,
+    pub timestamp: i64,
+}
+
+impl OpenInterestResponse {
+    pub fn convert_into_open_interest(&self) -> Result<OpenInterest, ParseFloatError> {
+        Ok(OpenInterest::new(
+            self.timestamp,
+            self.sum_open_interest.parse()?,
+            self.sum_open_interest_value.parse()?,
+        ))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DepthResponse {
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: i64,
+    pub bids: Vec<Vec<String>>,
+    pub asks: Vec<Vec<String>>,
+}
+
+impl DepthResponse {
+    fn parse_price_levels(levels: &[Vec<String>]) -> Vec<PriceLevel> {
+        levels
+            .iter()
+            .map(|x| PriceLevel::new(x[0].parse().unwrap(), x[1].parse().unwrap()))
+            .collect()
+    }
+
+    pub fn convert_into_order_book(&self) -> OrderBook {
+        let mut order_book = OrderBook::default();
+        order_book.apply_snapshot(
+            self.last_update_id,
+            &Self::parse_price_levels(&self.bids),
+            &Self::parse_price_levels(&self.asks),
+        );
+        order_book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_into_funding_rate() {
+        let data = r#"[
+            {"symbol":"BTCUSDT","fundingTime":1577836800000,"fundingRate":"0.00010000",
+             "markPrice":""},
+            {"symbol":"BTCUSDT","fundingTime":1712534400000,"fundingRate":"-0.00002151",
+             "markPrice":"69305.10000000"}]"#;
+        let funding_rates: Vec<FundingRateResponse> = serde_json::from_str(data).unwrap();
+        let funding_rate = funding_rates[0].convert_into_funding_rate().unwrap();
+        assert_eq!(funding_rate.get_funding_rate(), 0.0001);
+        assert_eq!(funding_rate.get_mark_price(), 0.0);
+        let funding_rate = funding_rates[1].convert_into_funding_rate().unwrap();
+        assert_eq!(funding_rate.get_funding_time(), 1712534400000);
+        assert_eq!(funding_rate.get_funding_rate(), -0.00002151);
+        assert_eq!(funding_rate.get_mark_price(), 69305.1);
+
+        let invalid = FundingRateResponse {
+            funding_rate: "".to_string(),
+            ..funding_rates.into_iter().next().unwrap()
+        };
+        assert!(invalid.convert_into_funding_rate().is_err());
+    }
+
+    #[test]
+    fn test_convert_into_open_interest() {
+        let data = r#"[{"symbol":"BTCUSDT","sumOpenInterest":"80265.53300000",
+            "sumOpenInterestValue":"5563079017.04840000","timestamp":1712534400000}]"#;
+        let open_interest: Vec<OpenInterestResponse> = serde_json::from_str(data).unwrap();
+        let res = open_interest[0].convert_into_open_interest().unwrap();
+        assert_eq!(res.get_timestamp(), 1712534400000);
+        assert_eq!(res.get_sum_open_interest(), 80265.533);
+        assert_eq!(res.get_sum_open_interest_value(), 5563079017.0484);
+
+        let invalid = OpenInterestResponse {
+            sum_open_interest: "n/a".to_string(),
+            ..open_interest.into_iter().next().unwrap()
+        };
+        assert!(invalid.convert_into_open_interest().is_err());
+    }
+
+    #[test]
+    fn test_convert_into_position() {
+        let data = r#"[{"symbol":"BTCUSDT","positionAmt":"-0.010","entryPrice":"60000.0","breakEvenPrice":"60012.0","markPrice":"59000.0","unRealizedProfit":"10.00000000","liquidationPrice":"0","leverage":"10","maxNotionalValue":"40000000","marginType":"cross","isolatedMargin":"0.00000000","isAutoAddMargin":"false","positionSide":"BOTH","notional":"-590.00000000","isolatedWallet":"0","updateTime":1700000000000}]"#;
+        let positions: Vec<PositionResponse> = serde_json::from_str(data).unwrap();
+        let position = positions[0].convert_into_position();
+        assert_eq!(position.get_symbol(), "btcusdt");
+        assert_eq!(position.get_side(), OrderSide::SELL);
+        assert_eq!(position.get_quantity(), 0.01);
+        assert_eq!(position.get_price(), 60000.0);
+    }
+
+    #[test]
+    fn test_convert_into_margin_brackets() {
+        let data = r#"[{"symbol":"ETHUSDT","notionalCoef":1.50,"brackets":[
+            {"bracket":2,"initialLeverage":50,"notionalCap":50000,"notionalFloor":10000,
+             "maintMarginRatio":0.01,"cum":35},
+            {"bracket":1,"initialLeverage":75,"notionalCap":10000,"notionalFloor":0,
+             "maintMarginRatio":0.0065,"cum":0}]}]"#;
+        let brackets: Vec<LeverageBracketResponse> = serde_json::from_str(data).unwrap();
+        let margin_brackets = brackets[0].convert_into_margin_brackets();
+        assert_eq!(
+            margin_brackets.get_bracket(5000.0).get_maint_margin_ratio(),
+            0.0065
+        );
+        assert_eq!(margin_brackets.get_bracket(20000.0).get_cum(), 35.0);
+    }
+
+    #[test]
+    fn test_convert_trade_into_order() {
+        let data = r#"[{"buyer":false,"commission":"-0.07819010","commissionAsset":"USDT","id":698759,"maker":false,"orderId":25851813,"price":"7819.01","qty":"0.002","quoteQty":"15.63802","realizedPnl":"-0.91539999","side":"SELL","positionSide":"SHORT","symbol":"BTCUSDT","time":1569514978020}]"#;
+        let trades: Vec<TradeResponse> = serde_json::from_str(data).unwrap();
+        let order = trades[0].convert_into_order("test_BTCUSDT_1", OrderType::Limit);
+        assert_eq!(order.get_symbol(), "btcusdt");
+        assert_eq!(order.get_side(), OrderSide::SELL);
+        assert_eq!(order.get_status(), OrderStatus::Filled);
+        assert_eq!(order.get_filled_qty(), 0.002);
+        assert_eq!(order.get_avg_price(), 7819.01);
+        assert_eq!(order.get_fee(), -0.0781901);
+        assert_eq!(order.get_oid(), "25851813");
+    }
+}
@@ -1,6 +1,11 @@
 use crate::base_enum::order_enums::*;
+use crate::base_model::market_model::book_ticker_model::BookTicker;
 use crate::base_model::market_model::depth_model::{Depth, PriceLevel};
+use crate::base_model::market_model::force_order_model::ForceOrder;
 use crate::base_model::market_model::kline_model::Kline;
+use crate::base_model::market_model::mark_price_model::MarkPrice;
+use crate::base_model::market_model::order_book_model::DepthUpdate;
+use crate::base_model::market_model::trade_model::Trade;
 use crate::base_model::trade_model::order_model::Order;
 use crate::base_model::trade_model::position_model::Position;
 
@@ -70,6 +75,187 @@ impl WsDepth {
     }
 }
 
+fn parse_price_levels(levels: &[Vec<String>]) -> Vec<PriceLevel> {
+    levels
+        .iter()
+        .map(|x| PriceLevel::new(x[0].parse::<f64>().unwrap(), x[1].parse::<f64>().unwrap()))
+        .collect()
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct WsDepthUpdate {
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "U")]
+    first_update_id: i64,
+    #[serde(rename = "u")]
+    final_update_id: i64,
+    #[serde(rename = "pu")]
+    prev_final_update_id: i64,
+    #[serde(rename = "b")]
+    bids: Vec<Vec<String>>,
+    #[serde(rename = "a")]
+    asks: Vec<Vec<String>>,
+}
+
+impl WsDepthUpdate {
+    pub fn convert_to_depth_update(self) -> DepthUpdate {
+        DepthUpdate::new(
+            self.event_time,
+            self.first_update_id,
+            self.final_update_id,
+            self.prev_final_update_id,
+            parse_price_levels(&self.bids),
+            parse_price_levels(&self.asks),
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsTrade {
+    #[serde(rename = "t")]
+    trade_id: i64,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "q")]
+    qty: String,
+    #[serde(rename = "T")]
+    timestamp: i64,
+    #[serde(rename = "m")]
+    is_buyer_maker: bool,
+}
+
+impl WsTrade {
+    pub fn convert_to_standard_trade(&self) -> Trade {
+        Trade::new(
+            self.trade_id,
+            self.price.parse().unwrap(),
+            self.qty.parse().unwrap(),
+            self.timestamp,
+            self.is_buyer_maker,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsAggTrade {
+    #[serde(rename = "a")]
+    agg_trade_id: i64,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "q")]
+    qty: String,
+    #[serde(rename = "T")]
+    timestamp: i64,
+    #[serde(rename = "m")]
+    is_buyer_maker: bool,
+}
+
+impl WsAggTrade {
+    pub fn convert_to_standard_trade(&self) -> Trade {
+        Trade::new(
+            self.agg_trade_id,
+            self.price.parse().unwrap(),
+            self.qty.parse().unwrap(),
+            self.timestamp,
+            self.is_buyer_maker,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsBookTicker {
+    #[serde(rename = "u")]
+    update_id: i64,
+    #[serde(rename = "b")]
+    bid_price: String,
+    #[serde(rename = "B")]
+    bid_qty: String,
+    #[serde(rename = "a")]
+    ask_price: String,
+    #[serde(rename = "A")]
+    ask_qty: String,
+    #[serde(rename = "T")]
+    timestamp: i64,
+}
+
+impl WsBookTicker {
+    pub fn convert_to_standard_book_ticker(&self) -> BookTicker {
+        BookTicker::new(
+            self.update_id,
+            self.bid_price.parse().unwrap(),
+            self.bid_qty.parse().unwrap(),
+            self.ask_price.parse().unwrap(),
+            self.ask_qty.parse().unwrap(),
+            self.timestamp,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsMarkPrice {
+    #[serde(rename = "E")]
+    timestamp: i64,
+    #[serde(rename = "p")]
+    mark_price: String,
+    #[serde(rename = "i")]
+    index_price: String,
+    #[serde(rename = "r")]
+    funding_rate: String,
+    #[serde(rename = "T")]
+    next_funding_time: i64,
+}
+
+impl WsMarkPrice {
+    /// Delivery contracts have no funding, their rate is empty.
+    pub fn convert_to_standard_mark_price(&self) -> MarkPrice {
+        MarkPrice::new(
+            self.timestamp,
+            self.mark_price.parse().unwrap(),
+            self.index_price.parse().unwrap(),
+            self.funding_rate.parse().unwrap_or(0.0),
+            self.next_funding_time,
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsForceOrderDetail {
+    #[serde(rename = "S")]
+    side: String,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "ap")]
+    avg_price: String,
+    #[serde(rename = "q")]
+    qty: String,
+    #[serde(rename = "z")]
+    filled_qty: String,
+    #[serde(rename = "X")]
+    status: String,
+    #[serde(rename = "T")]
+    timestamp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsForceOrder {
+    o: WsForceOrderDetail,
+}
+
+impl WsForceOrder {
+    pub fn convert_to_standard_force_order(&self) -> ForceOrder {
+        ForceOrder::new(
+            OrderSide::parse_order_side(&self.o.side),
+            self.o.price.parse().unwrap(),
+            self.o.avg_price.parse().unwrap(),
+            self.o.qty.parse().unwrap(),
+            self.o.filled_qty.parse().unwrap(),
+            OrderStatus::parse_order_status(&self.o.status),
+            self.o.timestamp,
+        )
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct WsEvent {
     pub stream: String,
@@ -106,6 +292,8 @@ pub struct WsOrder {
     status: String,
     #[serde(rename = "n")]
     fee: String,
+    #[serde(rename = "rp")]
+    realized_pnl: String,
 }
 
 impl WsOrder {
@@ -124,6 +312,7 @@ impl WsOrder {
             self.timestamp,
         );
         ord.set_fee(self.fee.parse().unwrap());
+        ord.set_realized_pnl(self.realized_pnl.parse().unwrap());
         ord
     }
 }
@@ -0,0 +1,624 @@
+use crate::base_enum::order_enums::OrderSide;
+use crate::base_model::trade_model::order_model::Order;
+use crate::strategy_model::strategy_portfolio::{PnlRecord, StrategyPortfolio};
+use crate::tools::time_tools;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use tracing::info;
+
+const MILLIS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0 * 1000.0;
+const QTY_EPSILON: f64 = 1e-9;
+
+/// A position opened from flat and closed back to flat (or flipped) on one symbol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundTrip {
+    symbol: String,
+    side: OrderSide,
+    entry_time: i64,
+    exit_time: i64,
+    entry_price: f64,
+    exit_price: f64,
+    quantity: f64,
+    fee: f64,
+    pnl: f64,
+}
+
+impl RoundTrip {
+    pub fn get_symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn get_side(&self) -> OrderSide {
+        self.side
+    }
+
+    pub fn get_entry_time(&self) -> i64 {
+        self.entry_time
+    }
+
+    pub fn get_exit_time(&self) -> i64 {
+        self.exit_time
+    }
+
+    pub fn get_entry_price(&self) -> f64 {
+        self.entry_price
+    }
+
+    pub fn get_exit_price(&self) -> f64 {
+        self.exit_price
+    }
+
+    pub fn get_quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    pub fn get_fee(&self) -> f64 {
+        self.fee
+    }
+
+    /// Net of fees.
+    pub fn get_pnl(&self) -> f64 {
+        self.pnl
+    }
+
+    pub fn get_holding_period(&self) -> i64 {
+        self.exit_time - self.entry_time
+    }
+}
+
+struct OpenTrip {
+    side: OrderSide,
+    entry_time: i64,
+    entry_notional: f64,
+    entry_qty: f64,
+    exit_notional: f64,
+    exit_qty: f64,
+    fee: f64,
+}
+
+impl OpenTrip {
+    fn new(side: OrderSide, entry_time: i64) -> Self {
+        Self {
+            side,
+            entry_time,
+            entry_notional: 0.0,
+            entry_qty: 0.0,
+            exit_notional: 0.0,
+            exit_qty: 0.0,
+            fee: 0.0,
+        }
+    }
+
+    fn remain_qty(&self) -> f64 {
+        self.entry_qty - self.exit_qty
+    }
+
+    fn close(self, symbol: &str, exit_time: i64) -> RoundTrip {
+        let entry_price = self.entry_notional / self.entry_qty;
+        let exit_price = self.exit_notional / self.exit_qty;
+        let gross_pnl = match self.side {
+            OrderSide::BUY => self.exit_notional - self.entry_notional,
+            OrderSide::SELL => self.entry_notional - self.exit_notional,
+        };
+        RoundTrip {
+            symbol: symbol.to_string(),
+            side: self.side,
+            entry_time: self.entry_time,
+            exit_time,
+            entry_price,
+            exit_price,
+            quantity: self.entry_qty,
+            fee: self.fee,
+            pnl: gross_pnl - self.fee,
+        }
+    }
+}
+
+/// Split the filled orders of one symbol into round trips. A fill that flips the
+/// position closes the current trip and opens a new one with the remaining quantity,
+/// with its fee split in proportion.
+pub fn parse_round_trips(symbol: &str, orders: &[Order]) -> Vec<RoundTrip> {
+    let mut res = Vec::new();
+    let mut open_trip: Option<OpenTrip> = None;
+    for order in orders {
+        let (price, mut qty) = (order.get_avg_price(), order.get_filled_qty());
+        if qty <= 0.0 {
+            continue;
+        }
+        let fee_per_qty = order.get_fee() / qty;
+        while qty > QTY_EPSILON {
+            let trip = open_trip
+                .get_or_insert_with(|| OpenTrip::new(order.get_side(), order.get_timestamp()));
+            if trip.side == order.get_side() {
+                trip.entry_notional += price * qty;
+                trip.entry_qty += qty;
+                trip.fee += fee_per_qty * qty;
+                qty = 0.0;
+            } else {
+                let close_qty = qty.min(trip.remain_qty());
+                trip.exit_notional += price * close_qty;
+                trip.exit_qty += close_qty;
+                trip.fee += fee_per_qty * close_qty;
+                qty -= close_qty;
+                if trip.remain_qty() <= QTY_EPSILON {
+                    res.push(
+                        open_trip
+                            .take()
+                            .unwrap()
+                            .close(symbol, order.get_timestamp()),
+                    );
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Serializable summary of a backtest, built from the portfolio once it has finished.
+/// Ratios are annualised from the sampling frequency of the equity curve and are 0
+/// when undefined (e.g. no trades or no volatility).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    strategy_name: String,
+    start_time: i64,
+    end_time: i64,
+    starting_cash: f64,
+    final_value: f64,
+    total_return: f64,
+    annual_return: f64,
+    sharpe_ratio: f64,
+    sortino_ratio: f64,
+    calmar_ratio: f64,
+    max_drawdown: f64,
+    max_drawdown_duration: i64,
+    exposure: f64,
+    time_in_market: f64,
+    turnover: f64,
+    fees: f64,
+    funding: f64,
+    trade_count: usize,
+    win_rate: f64,
+    profit_factor: f64,
+    equity_curve: Vec<PnlRecord>,
+    round_trips: Vec<RoundTrip>,
+}
+
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() {
+        value
+    } else {
+        0.0
+    }
+}
+
+impl BacktestReport {
+    pub fn new(strategy_name: &str, portfolio: &StrategyPortfolio) -> Self {
+        let mut round_trips = Vec::new();
+        let mut traded_notional = 0.0;
+        let mut symbols = portfolio.get_orders().keys().collect::<Vec<&String>>();
+        symbols.sort();
+        for symbol in symbols {
+            let orders = portfolio.get_orders().get(symbol).unwrap();
+            traded_notional += orders
+                .iter()
+                .map(|o| o.get_avg_price() * o.get_filled_qty())
+                .sum::<f64>();
+            round_trips.extend(parse_round_trips(symbol, orders));
+        }
+        round_trips.sort_by_key(|t| t.get_exit_time());
+        Self::from_records(
+            strategy_name,
+            portfolio.get_starting_cash(),
+            portfolio.get_pnl_records().clone(),
+            round_trips,
+            traded_notional,
+            portfolio.get_fee(),
+        )
+    }
+
+    pub fn from_records(
+        strategy_name: &str,
+        starting_cash: f64,
+        equity_curve: Vec<PnlRecord>,
+        round_trips: Vec<RoundTrip>,
+        traded_notional: f64,
+        fees: f64,
+    ) -> Self {
+        let (start_time, end_time) = match (equity_curve.first(), equity_curve.last()) {
+            (Some(first), Some(last)) => (first.get_timestamp(), last.get_timestamp()),
+            _ => (0, 0),
+        };
+        let final_value = equity_curve
+            .last()
+            .map(|r| r.get_net_value())
+            .unwrap_or(starting_cash);
+        let total_return = final_value / starting_cash - 1.0;
+
+        let returns = equity_curve
+            .windows(2)
+            .map(|w| w[1].get_net_value() / w[0].get_net_value() - 1.0)
+            .collect::<Vec<f64>>();
+        let periods_per_year = Self::periods_per_year(&equity_curve);
+        let years = (end_time - start_time) as f64 / MILLIS_PER_YEAR;
+        let annual_return = if years > 0.0 && final_value > 0.0 {
+            (final_value / starting_cash).powf(1.0 / years) - 1.0
+        } else {
+            0.0
+        };
+
+        let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
+        let std_dev = (returns
+            .iter()
+            .map(|r| (r - mean_return).powi(2))
+            .sum::<f64>()
+            / returns.len() as f64)
+            .sqrt();
+        let downside_dev =
+            (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / returns.len() as f64).sqrt();
+        let sharpe_ratio = mean_return / std_dev * periods_per_year.sqrt();
+        let sortino_ratio = mean_return / downside_dev * periods_per_year.sqrt();
+
+        let (max_drawdown, max_drawdown_duration) = Self::drawdown_statistic(&equity_curve);
+        let calmar_ratio = annual_return / max_drawdown;
+
+        let average_value =
+            equity_curve.iter().map(|r| r.get_net_value()).sum::<f64>() / equity_curve.len() as f64;
+        let exposure = equity_curve
+            .iter()
+            .map(|r| r.get_exposure() / r.get_net_value())
+            .sum::<f64>()
+            / equity_curve.len() as f64;
+        let time_in_market = equity_curve
+            .iter()
+            .filter(|r| r.get_exposure() > 0.0)
+            .count() as f64
+            / equity_curve.len() as f64;
+
+        let gross_profit = round_trips
+            .iter()
+            .filter(|t| t.get_pnl() > 0.0)
+            .map(|t| t.get_pnl())
+            .sum::<f64>();
+        let gross_loss = round_trips
+            .iter()
+            .filter(|t| t.get_pnl() < 0.0)
+            .map(|t| t.get_pnl())
+            .sum::<f64>();
+        let win_count = round_trips.iter().filter(|t| t.get_pnl() > 0.0).count();
+
+        Self {
+            strategy_name: strategy_name.to_string(),
+            start_time,
+            end_time,
+            starting_cash,
+            final_value,
+            total_return,
+            annual_return,
+            sharpe_ratio: finite_or_zero(sharpe_ratio),
+            sortino_ratio: finite_or_zero(sortino_ratio),
+            calmar_ratio: finite_or_zero(calmar_ratio),
+            max_drawdown,
+            max_drawdown_duration,
+            exposure: finite_or_zero(exposure),
+            time_in_market: finite_or_zero(time_in_market),
+            turnover: finite_or_zero(traded_notional / average_value),
+            fees,
+            funding: equity_curve.last().map(|r| r.get_funding()).unwrap_or(0.0),
+            trade_count: round_trips.len(),
+            win_rate: finite_or_zero(win_count as f64 / round_trips.len() as f64),
+            profit_factor: finite_or_zero(gross_profit / gross_loss.abs()),
+            equity_curve,
+            round_trips,
+        }
+    }
+
+    /// Median sampling interval of the equity curve, expressed as periods per year.
+    fn periods_per_year(equity_curve: &[PnlRecord]) -> f64 {
+        let mut deltas = equity_curve
+            .windows(2)
+            .map(|w| w[1].get_timestamp() - w[0].get_timestamp())
+            .filter(|d| *d > 0)
+            .collect::<Vec<i64>>();
+        if deltas.is_empty() {
+            return 0.0;
+        }
+        deltas.sort();
+        MILLIS_PER_YEAR / deltas[deltas.len() / 2] as f64
+    }
+
+    /// Returns the max drawdown as a fraction of the peak and the longest time spent
+    /// below a previous peak, in milliseconds.
+    fn drawdown_statistic(equity_curve: &[PnlRecord]) -> (f64, i64) {
+        let mut max_drawdown = 0.0;
+        let mut max_duration = 0;
+        let mut peak_value = f64::MIN;
+        let mut peak_time = 0;
+        for record in equity_curve {
+            if record.get_net_value() >= peak_value {
+                peak_value = record.get_net_value();
+                peak_time = record.get_timestamp();
+            } else {
+                let drawdown = (peak_value - record.get_net_value()) / peak_value;
+                if drawdown > max_drawdown {
+                    max_drawdown = drawdown;
+                }
+            }
+            max_duration = max_duration.max(record.get_timestamp() - peak_time);
+        }
+        (max_drawdown, max_duration)
+    }
+
+    pub fn get_strategy_name(&self) -> &str {
+        &self.strategy_name
+    }
+
+    pub fn get_start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    pub fn get_end_time(&self) -> i64 {
+        self.end_time
+    }
+
+    pub fn get_starting_cash(&self) -> f64 {
+        self.starting_cash
+    }
+
+    pub fn get_final_value(&self) -> f64 {
+        self.final_value
+    }
+
+    pub fn get_total_return(&self) -> f64 {
+        self.total_return
+    }
+
+    pub fn get_annual_return(&self) -> f64 {
+        self.annual_return
+    }
+
+    pub fn get_sharpe_ratio(&self) -> f64 {
+        self.sharpe_ratio
+    }
+
+    pub fn get_sortino_ratio(&self) -> f64 {
+        self.sortino_ratio
+    }
+
+    pub fn get_calmar_ratio(&self) -> f64 {
+        self.calmar_ratio
+    }
+
+    pub fn get_max_drawdown(&self) -> f64 {
+        self.max_drawdown
+    }
+
+    pub fn get_max_drawdown_duration(&self) -> i64 {
+        self.max_drawdown_duration
+    }
+
+    /// Average gross position notional over net value.
+    pub fn get_exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    /// Fraction of records with an open position.
+    pub fn get_time_in_market(&self) -> f64 {
+        self.time_in_market
+    }
+
+    /// Traded notional over average net value.
+    pub fn get_turnover(&self) -> f64 {
+        self.turnover
+    }
+
+    pub fn get_fees(&self) -> f64 {
+        self.fees
+    }
+
+    /// Net funding received, negative when paid.
+    pub fn get_funding(&self) -> f64 {
+        self.funding
+    }
+
+    pub fn get_trade_count(&self) -> usize {
+        self.trade_count
+    }
+
+    pub fn get_win_rate(&self) -> f64 {
+        self.win_rate
+    }
+
+    pub fn get_profit_factor(&self) -> f64 {
+        self.profit_factor
+    }
+
+    pub fn get_equity_curve(&self) -> &Vec<PnlRecord> {
+        &self.equity_curve
+    }
+
+    pub fn get_round_trips(&self) -> &Vec<RoundTrip> {
+        &self.round_trips
+    }
+
+    pub fn show_summary(&self) {
+        info!(
+            "{} | {} ~ {} | final value: {:.2} | total return: {:.4} | annual return: {:.4}",
+            self.strategy_name,
+            time_tools::get_datetime_from_timestamp(self.start_time),
+            time_tools::get_datetime_from_timestamp(self.end_time),
+            self.final_value,
+            self.total_return,
+            self.annual_return
+        );
+        info!(
+            "Sharpe Ratio: {:.4} | Sortino Ratio: {:.4} | Calmar Ratio: {:.4} | max drawdown: {:.4} | max drawdown duration: {}h",
+            self.sharpe_ratio,
+            self.sortino_ratio,
+            self.calmar_ratio,
+            self.max_drawdown,
+            self.max_drawdown_duration / 3_600_000
+        );
+        info!(
+            "trades: {} | win rate: {:.4} | profit factor: {:.4} | exposure: {:.4} | time in market: {:.4} | turnover: {:.2} | fees: {:.2} | funding: {:.2}",
+            self.trade_count,
+            self.win_rate,
+            self.profit_factor,
+            self.exposure,
+            self.time_in_market,
+            self.turnover,
+            self.fees,
+            self.funding
+        );
+    }
+
+    pub fn summary_csv_header() -> String {
+        "strategy_name,start_time,end_time,starting_cash,final_value,total_return,annual_return,sharpe_ratio,sortino_ratio,calmar_ratio,max_drawdown,max_drawdown_duration,exposure,time_in_market,turnover,fees,funding,trade_count,win_rate,profit_factor".to_string()
+    }
+
+    /// One CSV row of scalar metrics matching `summary_csv_header`.
+    pub fn summary_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.strategy_name,
+            self.start_time,
+            self.end_time,
+            self.starting_cash,
+            self.final_value,
+            self.total_return,
+            self.annual_return,
+            self.sharpe_ratio,
+            self.sortino_ratio,
+            self.calmar_ratio,
+            self.max_drawdown,
+            self.max_drawdown_duration,
+            self.exposure,
+            self.time_in_market,
+            self.turnover,
+            self.fees,
+            self.funding,
+            self.trade_count,
+            self.win_rate,
+            self.profit_factor
+        )
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn write_json(&self, path: &str) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_json()?.as_bytes())
+    }
+
+    /// Writes `{prefix}_summary.csv`, `{prefix}_equity.csv` and `{prefix}_trades.csv`.
+    pub fn write_csv(&self, prefix: &str) -> std::io::Result<()> {
+        let mut file = File::create(format!("{}_summary.csv", prefix))?;
+        writeln!(file, "{}", Self::summary_csv_header())?;
+        writeln!(file, "{}", self.summary_csv_row())?;
+
+        let mut file = File::create(format!("{}_equity.csv", prefix))?;
+        writeln!(file, "timestamp,pnl,net_value,exposure,funding")?;
+        for record in &self.equity_curve {
+            writeln!(
+                file,
+                "{},{},{},{},{}",
+                record.get_timestamp(),
+                record.get_pnl(),
+                record.get_net_value(),
+                record.get_exposure(),
+                record.get_funding()
+            )?;
+        }
+
+        let mut file = File::create(format!("{}_trades.csv", prefix))?;
+        writeln!(
+            file,
+            "symbol,side,entry_time,exit_time,entry_price,exit_price,quantity,fee,pnl"
+        )?;
+        for trip in &self.round_trips {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{},{}",
+                trip.symbol,
+                trip.side.string(),
+                trip.entry_time,
+                trip.exit_time,
+                trip.entry_price,
+                trip.exit_price,
+                trip.quantity,
+                trip.fee,
+                trip.pnl
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_enum::order_enums::{OrderStatus, OrderType};
+
+    fn filled_order(side: OrderSide, price: f64, qty: f64, timestamp: i64) -> Order {
+        let mut order = Order::new(
+            "btcusdt",
+            price,
+            qty,
+            side,
+            OrderType::Limit,
+            price,
+            qty,
+            "",
+            "",
+            OrderStatus::Filled,
+            timestamp,
+        );
+        order.set_fee(qty);
+        order
+    }
+
+    #[test]
+    fn test_parse_round_trips() {
+        let orders = vec![
+            filled_order(OrderSide::BUY, 100.0, 1.0, 1),
+            filled_order(OrderSide::BUY, 110.0, 1.0, 2),
+            // closes the long and opens a short of 1
+            filled_order(OrderSide::SELL, 120.0, 3.0, 3),
+            filled_order(OrderSide::BUY, 100.0, 1.0, 4),
+        ];
+        let trips = parse_round_trips("btcusdt", &orders);
+        assert_eq!(trips.len(), 2);
+        assert_eq!(trips[0].get_side(), OrderSide::BUY);
+        assert_eq!(trips[0].get_entry_price(), 105.0);
+        assert_eq!(trips[0].get_quantity(), 2.0);
+        assert!((trips[0].get_pnl() - (30.0 - 4.0)).abs() < 1e-9);
+        assert_eq!(trips[1].get_side(), OrderSide::SELL);
+        assert_eq!(trips[1].get_holding_period(), 1);
+        assert!((trips[1].get_pnl() - (20.0 - 2.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_statistic() {
+        let hour = 3_600_000;
+        let values = [100.0, 110.0, 99.0, 104.5, 121.0];
+        let equity_curve = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| PnlRecord::new(i as i64 * hour, v - 100.0, *v, 50.0, 0.0))
+            .collect::<Vec<PnlRecord>>();
+        let report = BacktestReport::from_records("test", 100.0, equity_curve, vec![], 200.0, 1.0);
+
+        assert!((report.get_total_return() - 0.21).abs() < 1e-9);
+        assert!((report.get_max_drawdown() - 0.1).abs() < 1e-9);
+        assert_eq!(report.get_max_drawdown_duration(), 2 * hour);
+        assert_eq!(report.get_time_in_market(), 1.0);
+        assert!(report.get_sharpe_ratio() > 0.0);
+        assert_eq!(report.get_win_rate(), 0.0);
+
+        let json = report.to_json().unwrap();
+        let parsed = serde_json::from_str::<BacktestReport>(&json).unwrap();
+        assert_eq!(parsed.get_equity_curve().len(), 5);
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+/// Binance USDⓈ-M futures fee tiers, without the BNB discount.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VipTier {
+    Regular,
+    Vip1,
+    Vip2,
+    Vip3,
+    Vip4,
+    Vip5,
+    Vip6,
+    Vip7,
+    Vip8,
+    Vip9,
+}
+
+impl VipTier {
+    pub fn get_fee_rate(&self) -> FeeRate {
+        match self {
+            VipTier::Regular => FeeRate::new(0.0002, 0.0005),
+            VipTier::Vip1 => FeeRate::new(0.00016, 0.0004),
+            VipTier::Vip2 => FeeRate::new(0.00014, 0.00035),
+            VipTier::Vip3 => FeeRate::new(0.00012, 0.00032),
+            VipTier::Vip4 => FeeRate::new(0.0001, 0.0003),
+            VipTier::Vip5 => FeeRate::new(0.00008, 0.00027),
+            VipTier::Vip6 => FeeRate::new(0.00006, 0.00025),
+            VipTier::Vip7 => FeeRate::new(0.00004, 0.00022),
+            VipTier::Vip8 => FeeRate::new(0.00002, 0.0002),
+            VipTier::Vip9 => FeeRate::new(0.0, 0.00017),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeRate {
+    maker: f64,
+    taker: f64,
+}
+
+impl FeeRate {
+    pub fn new(maker: f64, taker: f64) -> Self {
+        FeeRate { maker, taker }
+    }
+
+    pub fn get_maker(&self) -> f64 {
+        self.maker
+    }
+
+    pub fn get_taker(&self) -> f64 {
+        self.taker
+    }
+}
+
+/// Maker/taker fee rates with per-symbol overrides on top of a default tier.
+#[derive(Debug, Clone)]
+pub struct FeeSchedule {
+    default_rate: FeeRate,
+    symbol_rates: HashMap<String, FeeRate>,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        FeeSchedule::from_vip_tier(VipTier::Regular)
+    }
+}
+
+impl FeeSchedule {
+    pub fn new(maker: f64, taker: f64) -> Self {
+        FeeSchedule {
+            default_rate: FeeRate::new(maker, taker),
+            symbol_rates: HashMap::new(),
+        }
+    }
+
+    pub fn from_vip_tier(tier: VipTier) -> Self {
+        FeeSchedule {
+            default_rate: tier.get_fee_rate(),
+            symbol_rates: HashMap::new(),
+        }
+    }
+
+    pub fn set_symbol_fee_rate(&mut self, symbol: &str, maker: f64, taker: f64) {
+        self.symbol_rates
+            .insert(symbol.to_string(), FeeRate::new(maker, taker));
+    }
+
+    pub fn get_fee_rate(&self, symbol: &str, is_maker: bool) -> f64 {
+        let rate = self.symbol_rates.get(symbol).unwrap_or(&self.default_rate);
+        if is_maker {
+            rate.get_maker()
+        } else {
+            rate.get_taker()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_schedule() {
+        let mut schedule = FeeSchedule::from_vip_tier(VipTier::Vip3);
+        schedule.set_symbol_fee_rate("btcusdt", 0.0, 0.0001);
+        assert_eq!(schedule.get_fee_rate("ethusdt", true), 0.00012);
+        assert_eq!(schedule.get_fee_rate("ethusdt", false), 0.00032);
+        assert_eq!(schedule.get_fee_rate("btcusdt", true), 0.0);
+        assert_eq!(schedule.get_fee_rate("btcusdt", false), 0.0001);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::base_enum::order_enums::OrderSide;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarginMode {
+    /// Each position only risks the margin posted for it.
+    Isolated,
+    /// All positions share the wallet balance.
+    Cross,
+}
+
+/// One notional tier of a symbol's leverage brackets, as returned by Binance
+/// `/fapi/v1/leverageBracket`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MarginBracket {
+    notional_floor: f64,
+    notional_cap: f64,
+    maint_margin_ratio: f64,
+    cum: f64,
+}
+
+impl MarginBracket {
+    pub fn new(notional_floor: f64, notional_cap: f64, maint_margin_ratio: f64, cum: f64) -> Self {
+        MarginBracket {
+            notional_floor,
+            notional_cap,
+            maint_margin_ratio,
+            cum,
+        }
+    }
+
+    pub fn get_notional_floor(&self) -> f64 {
+        self.notional_floor
+    }
+
+    pub fn get_notional_cap(&self) -> f64 {
+        self.notional_cap
+    }
+
+    pub fn get_maint_margin_ratio(&self) -> f64 {
+        self.maint_margin_ratio
+    }
+
+    /// Maintenance amount deducted so the margin stays continuous across tiers.
+    pub fn get_cum(&self) -> f64 {
+        self.cum
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarginBrackets {
+    brackets: Vec<MarginBracket>,
+}
+
+/// BTCUSDT brackets.
+impl Default for MarginBrackets {
+    fn default() -> Self {
+        MarginBrackets::from_ratios(vec![
+            (0.0, 50_000.0, 0.004),
+            (50_000.0, 250_000.0, 0.005),
+            (250_000.0, 3_000_000.0, 0.01),
+            (3_000_000.0, 15_000_000.0, 0.025),
+            (15_000_000.0, 30_000_000.0, 0.05),
+            (30_000_000.0, 80_000_000.0, 0.1),
+            (80_000_000.0, 150_000_000.0, 0.125),
+            (150_000_000.0, 300_000_000.0, 0.15),
+            (300_000_000.0, 500_000_000.0, 0.25),
+            (500_000_000.0, f64::MAX, 0.5),
+        ])
+    }
+}
+
+impl MarginBrackets {
+    pub fn new(brackets: Vec<MarginBracket>) -> Self {
+        MarginBrackets { brackets }
+    }
+
+    /// Builds brackets from `(notional_floor, notional_cap, maint_margin_ratio)` tiers in
+    /// ascending order, deriving the maintenance amounts.
+    pub fn from_ratios(tiers: Vec<(f64, f64, f64)>) -> Self {
+        let mut brackets = Vec::new();
+        let (mut cum, mut prev_ratio) = (0.0, 0.0);
+        for (floor, cap, ratio) in tiers {
+            cum += floor * (ratio - prev_ratio);
+            prev_ratio = ratio;
+            brackets.push(MarginBracket::new(floor, cap, ratio, cum));
+        }
+        MarginBrackets { brackets }
+    }
+
+    pub fn get_bracket(&self, notional: f64) -> MarginBracket {
+        for bracket in &self.brackets {
+            if notional < bracket.get_notional_cap() {
+                return *bracket;
+            }
+        }
+        *self.brackets.last().unwrap()
+    }
+
+    pub fn get_maintenance_margin(&self, notional: f64) -> f64 {
+        let bracket = self.get_bracket(notional);
+        notional * bracket.get_maint_margin_ratio() - bracket.get_cum()
+    }
+}
+
+/// Binance one-way mode liquidation price of a position of `qty` entered at
+/// `entry_price`. `wallet_balance` is the isolated margin of the position in isolated
+/// mode, or the cross wallet balance with the maintenance margin and unrealized pnl
+/// of the other cross positions in cross mode.
+pub fn get_liquidation_price(
+    side: OrderSide,
+    qty: f64,
+    entry_price: f64,
+    wallet_balance: f64,
+    other_maintenance_margin: f64,
+    other_unrealized_pnl: f64,
+    bracket: &MarginBracket,
+) -> f64 {
+    let side = match side {
+        OrderSide::BUY => 1.0,
+        OrderSide::SELL => -1.0,
+    };
+    let price =
+        (wallet_balance - other_maintenance_margin + other_unrealized_pnl + bracket.get_cum()
+            - side * qty * entry_price)
+            / (qty * bracket.get_maint_margin_ratio() - side * qty);
+    price.max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_margin_brackets() {
+        let brackets = MarginBrackets::default();
+        assert_eq!(
+            brackets.get_bracket(10_000.0).get_maint_margin_ratio(),
+            0.004
+        );
+        assert_eq!(brackets.get_bracket(100_000.0).get_cum(), 50.0);
+        assert!((brackets.get_bracket(1_000_000.0).get_cum() - 1_300.0).abs() < 1e-6);
+        assert!((brackets.get_bracket(20_000_000.0).get_cum() - 421_300.0).abs() < 1e-6);
+        assert!((brackets.get_maintenance_margin(100_000.0) - 450.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_liquidation_price() {
+        let bracket = MarginBracket::new(0.0, 50_000.0, 0.004, 0.0);
+        // 1 btc long at 20000 with 1000 isolated margin (20x)
+        let liq_price =
+            get_liquidation_price(OrderSide::BUY, 1.0, 20000.0, 1000.0, 0.0, 0.0, &bracket);
+        assert!((liq_price - 19000.0 / 0.996).abs() < 1e-6);
+        // maintenance margin equals the remaining margin at the liquidation price
+        let remain = 1000.0 + (liq_price - 20000.0);
+        assert!((remain - liq_price * 0.004).abs() < 1e-6);
+
+        let liq_price =
+            get_liquidation_price(OrderSide::SELL, 1.0, 20000.0, 1000.0, 0.0, 0.0, &bracket);
+        assert!((liq_price - 21000.0 / 1.004).abs() < 1e-6);
+    }
+}
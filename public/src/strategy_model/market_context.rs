@@ -0,0 +1,149 @@
+use crate::base_model::market_model::kline_model::Kline;
+use std::collections::{HashMap, VecDeque};
+
+/// Rolling window of the last `history_size` closed klines of each symbol, kept by
+/// `StrategyEngine` so strategies don't buffer history themselves. Series are
+/// returned oldest first and hold fewer values until the window has filled.
+pub struct MarketContext {
+    history_size: usize,
+    klines: HashMap<String, VecDeque<Kline>>,
+}
+
+impl Default for MarketContext {
+    fn default() -> Self {
+        MarketContext::new(0)
+    }
+}
+
+impl MarketContext {
+    pub fn new(history_size: usize) -> Self {
+        MarketContext {
+            history_size,
+            klines: HashMap::new(),
+        }
+    }
+
+    pub fn get_history_size(&self) -> usize {
+        self.history_size
+    }
+
+    /// Klines not newer than the last one of the symbol are ignored.
+    pub fn add_kline(&mut self, symbol: &str, kline: Kline) {
+        if self.history_size == 0 {
+            return;
+        }
+        let klines = self.klines.entry(symbol.to_string()).or_default();
+        if klines
+            .back()
+            .is_some_and(|k| k.get_open_time() >= kline.get_open_time())
+        {
+            return;
+        }
+        klines.push_back(kline);
+        if klines.len() > self.history_size {
+            klines.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.klines.clear();
+    }
+
+    pub fn get_len(&self, symbol: &str) -> usize {
+        match self.klines.get(symbol) {
+            Some(klines) => klines.len(),
+            None => 0,
+        }
+    }
+
+    pub fn get_last_kline(&self, symbol: &str) -> Option<Kline> {
+        self.klines.get(symbol)?.back().copied()
+    }
+
+    /// The last `n` klines.
+    pub fn get_klines(&self, symbol: &str, n: usize) -> Vec<Kline> {
+        match self.klines.get(symbol) {
+            Some(klines) => klines
+                .iter()
+                .skip(klines.len().saturating_sub(n))
+                .copied()
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn get_series(&self, symbol: &str, n: usize, f: fn(&Kline) -> f64) -> Vec<f64> {
+        self.get_klines(symbol, n).iter().map(f).collect()
+    }
+
+    pub fn get_opens(&self, symbol: &str, n: usize) -> Vec<f64> {
+        self.get_series(symbol, n, Kline::get_open)
+    }
+
+    pub fn get_highs(&self, symbol: &str, n: usize) -> Vec<f64> {
+        self.get_series(symbol, n, Kline::get_high)
+    }
+
+    pub fn get_lows(&self, symbol: &str, n: usize) -> Vec<f64> {
+        self.get_series(symbol, n, Kline::get_low)
+    }
+
+    pub fn get_closes(&self, symbol: &str, n: usize) -> Vec<f64> {
+        self.get_series(symbol, n, Kline::get_close)
+    }
+
+    pub fn get_volumes(&self, symbol: &str, n: usize) -> Vec<f64> {
+        self.get_series(symbol, n, Kline::get_volume)
+    }
+
+    /// The last `n` simple close-to-close returns, which needs `n + 1` klines.
+    pub fn get_returns(&self, symbol: &str, n: usize) -> Vec<f64> {
+        self.get_closes(symbol, n + 1)
+            .windows(2)
+            .map(|w| w[1] / w[0] - 1.0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(open_time: i64, close: f64) -> Kline {
+        Kline::new(
+            open_time,
+            open_time + 299999,
+            close,
+            close + 1.0,
+            close - 1.0,
+            close,
+            1.0,
+            1,
+            0.0,
+            0.0,
+        )
+    }
+
+    #[test]
+    fn test_market_context() {
+        let mut context = MarketContext::new(3);
+        assert!(context.get_closes("btcusdt", 2).is_empty());
+        for (i, close) in [100.0, 110.0, 99.0, 108.9].iter().enumerate() {
+            context.add_kline("btcusdt", kline(i as i64 * 300000, *close));
+        }
+        // duplicates of the last kline are ignored
+        context.add_kline("btcusdt", kline(900000, 1.0));
+
+        assert_eq!(context.get_len("btcusdt"), 3);
+        assert_eq!(context.get_closes("btcusdt", 5), vec![110.0, 99.0, 108.9]);
+        assert_eq!(context.get_highs("btcusdt", 1), vec![109.9]);
+        let returns = context.get_returns("btcusdt", 2);
+        assert_eq!(returns.len(), 2);
+        assert!((returns[0] + 0.1).abs() < 1e-9);
+        assert!((returns[1] - 0.1).abs() < 1e-9);
+        assert_eq!(
+            context.get_last_kline("btcusdt").unwrap().get_open_time(),
+            900000
+        );
+    }
+}
@@ -1 +1,6 @@
-pub mod strategy_portfolio;
\ No newline at end of file
+pub mod backtest_report;
+pub mod fee_schedule;
+pub mod margin_model;
+pub mod market_context;
+pub mod strategy_portfolio;
+pub mod target_position;
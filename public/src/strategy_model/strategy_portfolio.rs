@@ -1,7 +1,11 @@
+use crate::base_enum::order_enums::{OrderSide, OrderStatus, OrderType};
+use crate::base_model::market_model::funding_rate_model::FundingRate;
 use crate::base_model::market_model::kline_model::Kline;
 use crate::base_model::trade_model::order_model::Order;
 use crate::base_model::trade_model::position_model::Position;
 use crate::base_model::{error_model::StrategyError, info_model::SymbolInfo};
+use crate::strategy_model::fee_schedule::FeeSchedule;
+use crate::strategy_model::margin_model::{get_liquidation_price, MarginBrackets, MarginMode};
 use crate::tools::time_tools;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -40,19 +44,23 @@ impl Balance {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct PnlRecord {
     timestamp: i64,
     pnl: f64,
     net_value: f64,
+    exposure: f64,
+    funding: f64,
 }
 
 impl PnlRecord {
-    pub fn new(timestamp: i64, pnl: f64, net_value: f64) -> Self {
+    pub fn new(timestamp: i64, pnl: f64, net_value: f64, exposure: f64, funding: f64) -> Self {
         Self {
             timestamp,
             pnl,
             net_value,
+            exposure,
+            funding,
         }
     }
 
@@ -67,6 +75,16 @@ impl PnlRecord {
     pub fn get_net_value(&self) -> f64 {
         self.net_value
     }
+
+    /// Gross notional of open positions at the record time.
+    pub fn get_exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    /// Cumulative funding received, negative when paid. Already included in `pnl`.
+    pub fn get_funding(&self) -> f64 {
+        self.funding
+    }
 }
 
 pub struct StrategyPortfolio {
@@ -75,12 +93,17 @@ pub struct StrategyPortfolio {
     freezed_cash: f64,
     total_value: f64,
     leverage_rate: f64,
-    fee_rate: f64,
+    fee_schedule: FeeSchedule,
     fee: f64,
+    funding: f64,
+    margin_mode: MarginMode,
+    margin_brackets: HashMap<String, MarginBrackets>,
+    default_margin_brackets: MarginBrackets,
     unrealized_pnl: f64,
     realized_pnl: f64,
     positions: HashMap<String, Position>,
     orders: HashMap<String, Vec<Order>>,
+    live_orders: HashMap<String, Order>,
     symbol_infos: HashMap<String, SymbolInfo>,
     total_value_records: Vec<f64>,
     pnl_records: Vec<PnlRecord>,
@@ -94,8 +117,12 @@ impl StrategyPortfolio {
             freezed_cash: 0.0,
             leverage_rate: leverage_rate,
             total_value: starting_cash,
-            fee_rate: 0.0005,
+            fee_schedule: FeeSchedule::default(),
             fee: 0.0,
+            funding: 0.0,
+            margin_mode: MarginMode::Cross,
+            margin_brackets: HashMap::new(),
+            default_margin_brackets: MarginBrackets::default(),
             unrealized_pnl: 0.0,
             realized_pnl: 0.0,
             positions: symbols
@@ -103,6 +130,7 @@ impl StrategyPortfolio {
                 .map(|s| (s, Position::default()))
                 .collect::<HashMap<String, Position>>(),
             orders: HashMap::new(),
+            live_orders: HashMap::new(),
             symbol_infos: HashMap::new(),
             total_value_records: Vec::new(),
             pnl_records: Vec::new(),
@@ -120,6 +148,58 @@ impl StrategyPortfolio {
     pub fn get_leverage_rate(&self) -> f64 {
         self.leverage_rate
     }
+
+    pub fn get_starting_cash(&self) -> f64 {
+        self.starting_cash
+    }
+
+    pub fn get_total_value(&self) -> f64 {
+        self.total_value
+    }
+
+    pub fn get_fee(&self) -> f64 {
+        self.fee
+    }
+
+    pub fn get_funding(&self) -> f64 {
+        self.funding
+    }
+
+    pub fn set_fee_schedule(&mut self, fee_schedule: FeeSchedule) {
+        self.fee_schedule = fee_schedule;
+    }
+
+    pub fn get_fee_schedule(&self) -> &FeeSchedule {
+        &self.fee_schedule
+    }
+
+    pub fn set_margin_mode(&mut self, margin_mode: MarginMode) {
+        self.margin_mode = margin_mode;
+    }
+
+    pub fn get_margin_mode(&self) -> MarginMode {
+        self.margin_mode
+    }
+
+    /// Symbols without brackets use the BTCUSDT ones.
+    pub fn set_margin_brackets(&mut self, symbol: &str, margin_brackets: MarginBrackets) {
+        self.margin_brackets
+            .insert(symbol.to_string(), margin_brackets);
+    }
+
+    pub fn get_margin_brackets(&self, symbol: &str) -> &MarginBrackets {
+        self.margin_brackets
+            .get(symbol)
+            .unwrap_or(&self.default_margin_brackets)
+    }
+
+    pub fn get_orders(&self) -> &HashMap<String, Vec<Order>> {
+        &self.orders
+    }
+
+    pub fn get_pnl_records(&self) -> &Vec<PnlRecord> {
+        &self.pnl_records
+    }
     pub fn set_symbol_infos(&mut self, symbol_infos: HashMap<String, SymbolInfo>) {
         self.symbol_infos = symbol_infos;
         for (s, info) in &self.symbol_infos {
@@ -169,7 +249,7 @@ impl StrategyPortfolio {
         Ok(())
     }
 
-    fn check_back_test_order(&mut self, symbol: &str, order: &Order) -> Result<(), StrategyError> {
+    fn check_order(&self, symbol: &str, order: &Order) -> Result<(), StrategyError> {
         match self.check_insufficient_cash(symbol, order) {
             Ok(_) => {}
             Err(e) => {
@@ -200,19 +280,13 @@ impl StrategyPortfolio {
             );
             return Err(StrategyError::OrderQuantityError(msg));
         }
-        info!(
-            "Place order: {} | px: {} | qty: {} | side: {}",
-            order.get_symbol(),
-            order.get_price(),
-            order.get_qty(),
-            order.get_side().string()
-        );
+        Ok(())
+    }
 
+    fn apply_fill(&mut self, symbol: &str, order: &Order, fee: f64) {
         if let Some(cur_pos) = self.positions.get_mut(symbol) {
             let prev_margin = cur_pos.get_margin();
             let cur_realized_pnl = cur_pos.update_order(order);
-            let amt = order.get_price() * order.get_qty();
-            let fee = amt * self.fee_rate;
             let delta_margin = cur_pos.get_margin() - prev_margin;
 
             self.fee += fee;
@@ -221,16 +295,143 @@ impl StrategyPortfolio {
             self.realized_pnl += cur_realized_pnl;
             self.available_cash += cur_realized_pnl;
         }
+    }
 
+    fn record_order(&mut self, symbol: &str, order: &Order) {
         if let Some(cur_orders) = self.orders.get_mut(symbol) {
             cur_orders.push(order.clone());
         } else {
             self.orders.insert(symbol.to_string(), vec![order.clone()]);
         }
+    }
 
+    fn check_back_test_order(&mut self, symbol: &str, order: &Order) -> Result<(), StrategyError> {
+        match self.check_order(symbol, order) {
+            Ok(_) => {}
+            Err(e) => {
+                return Err(e);
+            }
+        }
+        info!(
+            "Place order: {} | px: {} | qty: {} | side: {}",
+            order.get_symbol(),
+            order.get_price(),
+            order.get_qty(),
+            order.get_side().string()
+        );
+        self.fill_back_test_order(symbol, order, false);
         Ok(())
     }
 
+    /// Applies a simulated fill of `filled_qty` at `avg_price`, charged at the maker or
+    /// taker rate of the fee schedule. The order is expected to have been validated with
+    /// `check_live_order` when it was submitted.
+    pub fn fill_back_test_order(&mut self, symbol: &str, order: &Order, is_maker: bool) {
+        let fee = order.get_avg_price()
+            * order.get_filled_qty()
+            * self.fee_schedule.get_fee_rate(symbol, is_maker);
+        self.apply_fill(symbol, order, fee);
+        let mut order = order.clone();
+        order.set_fee(fee);
+        self.record_order(symbol, &order);
+    }
+
+    pub fn check_live_order(&self, symbol: &str, order: &Order) -> Result<(), StrategyError> {
+        self.check_order(symbol, order)
+    }
+
+    pub fn add_live_order(&mut self, order: Order) {
+        info!(
+            "Live order submitted: {} | cid: {}",
+            order.string_order(),
+            order.get_cid()
+        );
+        self.live_orders.insert(order.get_cid().to_string(), order);
+    }
+
+    pub fn get_live_orders(&self, symbol: &str) -> Vec<&Order> {
+        self.live_orders
+            .values()
+            .filter(|o| o.get_symbol() == symbol)
+            .collect()
+    }
+
+    /// Reconcile an order update pushed by the exchange with the tracked live order.
+    /// Exchange updates carry the cumulative filled qty and average price, so only the
+    /// incremental fill since the previous update is applied to the position.
+    /// Returns false if the order was not placed through this portfolio.
+    pub fn update_live_order(&mut self, order: &Order) -> bool {
+        let prev_order = match self.live_orders.get(order.get_cid()) {
+            Some(prev_order) => prev_order.clone(),
+            None => return false,
+        };
+        let symbol = prev_order.get_symbol().to_string();
+
+        let delta_qty = order.get_filled_qty() - prev_order.get_filled_qty();
+        if delta_qty > 0.0 {
+            let delta_px = (order.get_avg_price() * order.get_filled_qty()
+                - prev_order.get_avg_price() * prev_order.get_filled_qty())
+                / delta_qty;
+            let mut fill = prev_order.clone();
+            fill.set_avg_price(delta_px);
+            fill.set_filled_qty(delta_qty);
+            fill.set_timestamp(order.get_timestamp());
+            self.apply_fill(&symbol, &fill, order.get_fee());
+            info!(
+                "Live order filled: {} | px: {} | qty: {} | side: {}",
+                symbol,
+                delta_px,
+                delta_qty,
+                fill.get_side().string()
+            );
+        }
+
+        let mut cur_order = prev_order;
+        cur_order.set_avg_price(order.get_avg_price());
+        cur_order.set_filled_qty(order.get_filled_qty());
+        cur_order.set_fee(cur_order.get_fee() + order.get_fee());
+        cur_order.set_timestamp(order.get_timestamp());
+        cur_order.set_status(order.get_status());
+        match order.get_status() {
+            OrderStatus::New | OrderStatus::PartiallyFilled => {
+                self.live_orders
+                    .insert(cur_order.get_cid().to_string(), cur_order);
+            }
+            _ => {
+                self.live_orders.remove(cur_order.get_cid());
+                self.record_order(&symbol, &cur_order);
+            }
+        }
+        true
+    }
+
+    /// Seeds a live session with the account balance and open positions, e.g. after a
+    /// restart. The balance becomes the starting cash, and positions of symbols the
+    /// strategy does not trade are ignored.
+    pub fn sync_live_account(&mut self, balance: f64, positions: Vec<Position>) {
+        for position in positions {
+            if let Some(cur_pos) = self.positions.get_mut(position.get_symbol()) {
+                info!(
+                    "Live position synced: {} | px: {} | qty: {} | side: {}",
+                    position.get_symbol(),
+                    position.get_price(),
+                    position.get_quantity(),
+                    position.get_side().string()
+                );
+                *cur_pos = position;
+            }
+        }
+        self.freezed_cash = self.positions.values().map(|p| p.get_margin()).sum();
+        self.unrealized_pnl = self
+            .positions
+            .values()
+            .map(|p| p.get_unrealized_pnl())
+            .sum();
+        self.starting_cash = balance;
+        self.available_cash = balance - self.freezed_cash;
+        self.total_value = balance + self.unrealized_pnl;
+    }
+
     pub fn make_back_test_order(
         &mut self,
         orders: HashMap<String, Order>,
@@ -254,6 +455,140 @@ impl StrategyPortfolio {
         }
     }
 
+    /// Settles a funding payment on the open position of `symbol` at `mark_price`.
+    /// Longs pay shorts when the rate is positive.
+    pub fn apply_funding(&mut self, symbol: &str, funding_rate: &FundingRate, mark_price: f64) {
+        let Some(cur_pos) = self.positions.get(symbol) else {
+            return;
+        };
+        if cur_pos.get_quantity() == 0.0 {
+            return;
+        }
+        let payment = -cur_pos.get_signed_quantity() * mark_price * funding_rate.get_funding_rate();
+        self.funding += payment;
+        self.available_cash += payment;
+        info!(
+            "Funding: {} | rate: {} | payment: {}",
+            symbol,
+            funding_rate.get_funding_rate(),
+            payment
+        );
+    }
+
+    /// Liquidation price of the open position of `symbol` at the given mark price,
+    /// which selects the maintenance margin bracket.
+    pub fn get_liquidation_price(&self, symbol: &str, mark_price: f64) -> Option<f64> {
+        let cur_pos = self.positions.get(symbol)?;
+        if cur_pos.get_quantity() == 0.0 {
+            return None;
+        }
+        let bracket = self
+            .get_margin_brackets(symbol)
+            .get_bracket(cur_pos.get_quantity() * mark_price);
+        let (wallet_balance, other_maintenance_margin, other_unrealized_pnl) =
+            match self.margin_mode {
+                MarginMode::Isolated => (
+                    cur_pos.get_quantity() * cur_pos.get_price() / self.leverage_rate,
+                    0.0,
+                    0.0,
+                ),
+                MarginMode::Cross => {
+                    let others = self
+                        .positions
+                        .iter()
+                        .filter(|(s, p)| s.as_str() != symbol && p.get_quantity() != 0.0);
+                    let mut maintenance_margin = 0.0;
+                    let mut unrealized_pnl = 0.0;
+                    for (s, p) in others {
+                        maintenance_margin += self
+                            .get_margin_brackets(s)
+                            .get_maintenance_margin(p.get_notional());
+                        unrealized_pnl += p.get_unrealized_pnl();
+                    }
+                    (
+                        self.starting_cash + self.realized_pnl - self.fee + self.funding,
+                        maintenance_margin,
+                        unrealized_pnl,
+                    )
+                }
+            };
+        Some(get_liquidation_price(
+            cur_pos.get_side(),
+            cur_pos.get_quantity(),
+            cur_pos.get_price(),
+            wallet_balance,
+            other_maintenance_margin,
+            other_unrealized_pnl,
+            &bracket,
+        ))
+    }
+
+    /// Closes the position of `symbol` at its liquidation price if `mark_price` reached
+    /// it, charging the taker fee, and returns the liquidation order.
+    pub fn check_liquidation(
+        &mut self,
+        symbol: &str,
+        mark_price: f64,
+        timestamp: i64,
+    ) -> Option<Order> {
+        let liq_price = self.get_liquidation_price(symbol, mark_price)?;
+        let cur_pos = self.positions.get(symbol)?;
+        let (is_liquidated, side) = match cur_pos.get_side() {
+            OrderSide::BUY => (mark_price <= liq_price, OrderSide::SELL),
+            OrderSide::SELL => (mark_price >= liq_price, OrderSide::BUY),
+        };
+        if !is_liquidated {
+            return None;
+        }
+        let order = Order::new(
+            symbol,
+            liq_price,
+            cur_pos.get_quantity(),
+            side,
+            OrderType::Liquidation,
+            liq_price,
+            cur_pos.get_quantity(),
+            "",
+            "",
+            OrderStatus::Filled,
+            timestamp,
+        );
+        warn!(
+            "Liquidation: {} | mark price: {} | liquidation price: {} | qty: {}",
+            symbol,
+            mark_price,
+            liq_price,
+            order.get_qty()
+        );
+        self.fill_back_test_order(symbol, &order, false);
+        Some(order)
+    }
+
+    /// Checks every position against the worst mark price of its kline, the low for
+    /// longs and the high for shorts. Symbols without a mark price kline fall back to
+    /// the trade kline.
+    pub fn check_back_test_liquidation(
+        &mut self,
+        klines: &HashMap<String, Kline>,
+        mark_price_klines: &HashMap<String, Kline>,
+    ) -> Vec<Order> {
+        let mut res = Vec::new();
+        for (symbol, kline) in klines {
+            let mark_kline = mark_price_klines.get(symbol).unwrap_or(kline);
+            let mark_price = match self.positions.get(symbol) {
+                Some(cur_pos) if cur_pos.get_quantity() != 0.0 => match cur_pos.get_side() {
+                    OrderSide::BUY => mark_kline.get_low(),
+                    OrderSide::SELL => mark_kline.get_high(),
+                },
+                _ => continue,
+            };
+            if let Some(order) = self.check_liquidation(symbol, mark_price, kline.get_open_time()) {
+                res.push(order);
+            }
+        }
+        res
+    }
+
     pub fn update_back_test_value(&mut self) -> Result<(), StrategyError> {
         let mut unrealized_pnl = 0.0;
         for (_, pos) in &self.positions {
@@ -262,7 +597,7 @@ impl StrategyPortfolio {
         self.unrealized_pnl = unrealized_pnl;
 
         let tmp_total_value =
-            self.starting_cash + self.unrealized_pnl + self.realized_pnl - self.fee;
+            self.starting_cash + self.unrealized_pnl + self.realized_pnl - self.fee + self.funding;
         let tmp_total_value_check = self.available_cash + self.freezed_cash + self.unrealized_pnl;
         if tmp_total_value - tmp_total_value_check < 0.0001 {
             self.total_value = tmp_total_value;
@@ -281,7 +616,8 @@ impl StrategyPortfolio {
 
     pub fn update_pnl_records(&mut self, timestamp: i64) {
         let pnl = self.total_value - self.starting_cash;
-        let cur_pnl = PnlRecord::new(timestamp, pnl, self.total_value);
+        let exposure = self.positions.values().map(|p| p.get_notional()).sum();
+        let cur_pnl = PnlRecord::new(timestamp, pnl, self.total_value, exposure, self.funding);
         if self.pnl_records.len() == 0 {
             info!(
                 "Pnl record: timestamp: {} | pnl: {} | net value: {}",
@@ -302,46 +638,148 @@ impl StrategyPortfolio {
         }
         self.pnl_records.push(cur_pnl);
     }
+}
 
-    pub fn show_summary(&self) -> Result<(), Box<dyn std::error::Error>> {
-        self.ratio_statistic();
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live_order(status: OrderStatus, avg_price: f64, filled_qty: f64, fee: f64) -> Order {
+        let mut order = Order::new(
+            "btcusdt",
+            100.0,
+            2.0,
+            OrderSide::BUY,
+            OrderType::Limit,
+            avg_price,
+            filled_qty,
+            "test_btcusdt_1",
+            "1",
+            status,
+            0,
+        );
+        order.set_fee(fee);
+        order
     }
 
-    fn ratio_statistic(&self) {
-        let mut returns: Vec<f64> = Vec::new();
-        for idx in 1..self.pnl_records.len() {
-            let pnl = (self.pnl_records[idx].get_net_value()
-                - self.pnl_records[idx - 1].get_net_value())
-                / self.pnl_records[idx - 1].get_net_value();
-            returns.push(pnl);
-        }
+    #[test]
+    fn test_update_live_order() {
+        let mut portfolio = StrategyPortfolio::new(1000.0, 10.0, vec!["btcusdt".to_string()]);
+        portfolio.add_live_order(live_order(OrderStatus::New, 0.0, 0.0, 0.0));
+
+        assert!(portfolio.update_live_order(&live_order(
+            OrderStatus::PartiallyFilled,
+            100.0,
+            1.0,
+            0.1
+        )));
+        assert!(portfolio.update_live_order(&live_order(OrderStatus::Filled, 101.0, 2.0, 0.1)));
+
+        let pos = portfolio.get_position("btcusdt").unwrap();
+        assert_eq!(pos.get_quantity(), 2.0);
+        assert!((pos.get_price() - 101.0).abs() < 1e-9);
+        assert!(portfolio.get_live_orders("btcusdt").is_empty());
+        assert!((portfolio.get_available_cash() - (1000.0 - 202.0 / 100.0 - 0.2)).abs() < 1e-9);
+
+        assert!(!portfolio.update_live_order(&Order::default()));
+    }
 
-        let mean_return = returns.iter().sum::<f64>() / returns.len() as f64;
-        let mut squared_diff_sum = 0.0;
-        for ret in &returns {
-            squared_diff_sum += (ret - mean_return).powi(2);
-        }
-        let std_dev = (squared_diff_sum / returns.len() as f64).sqrt();
-        let risk_free_rate = 0.0; // Set the risk-free rate here
-        let sharpe_ratio = (mean_return - risk_free_rate) / std_dev;
-
-        let downside_returns: Vec<f64> = returns
-            .iter()
-            .filter(|&ret| *ret < risk_free_rate)
-            .map(|&ret| ret)
-            .collect();
-        let mean_downside_return =
-            downside_returns.iter().sum::<f64>() / downside_returns.len() as f64;
-        let mut squared_downside_diff_sum = 0.0;
-        for ret in &downside_returns {
-            squared_downside_diff_sum += (ret - mean_downside_return).powi(2);
-        }
-        let downside_std_dev = (squared_downside_diff_sum / downside_returns.len() as f64).sqrt();
-        let sortino_ratio = (mean_return - risk_free_rate) / downside_std_dev;
-        let sqrt_ratio = 288.0_f64.sqrt();
+    #[test]
+    fn test_sync_live_account() {
+        let mut portfolio = StrategyPortfolio::new(1000.0, 10.0, vec!["btcusdt".to_string()]);
+        let position = |symbol: &str| {
+            Position::new(
+                symbol,
+                100.0,
+                2.0,
+                OrderSide::SELL,
+                100.0,
+                10.0,
+                -4.0,
+                0.0,
+                20.0,
+                0,
+            )
+        };
+        portfolio.sync_live_account(500.0, vec![position("btcusdt"), position("ethusdt")]);
+
+        let pos = portfolio.get_position("btcusdt").unwrap();
+        assert_eq!(pos.get_signed_quantity(), -2.0);
+        assert!(portfolio.get_position("ethusdt").is_none());
+        assert_eq!(portfolio.get_starting_cash(), 500.0);
+        assert_eq!(portfolio.get_available_cash(), 480.0);
+        assert_eq!(portfolio.get_total_value(), 496.0);
+    }
+
+    #[test]
+    fn test_fee_and_funding() {
+        let mut portfolio = StrategyPortfolio::new(1000.0, 10.0, vec!["btcusdt".to_string()]);
+        portfolio.fill_back_test_order(
+            "btcusdt",
+            &live_order(OrderStatus::Filled, 100.0, 2.0, 0.0),
+            true,
+        );
+        assert!((portfolio.get_fee() - 200.0 * 0.0002).abs() < 1e-9);
+
+        portfolio.apply_funding("btcusdt", &FundingRate::new(0, 0.0001, 100.0), 100.0);
+        assert!((portfolio.get_funding() + 0.02).abs() < 1e-9);
+
+        portfolio.update_back_test_market_price(&HashMap::from([(
+            "btcusdt".to_string(),
+            Kline::new(0, 299999, 100.0, 100.0, 100.0, 100.0, 1.0, 1, 0.0, 0.0),
+        )]));
+        assert!(portfolio.update_back_test_value().is_ok());
+        portfolio.update_pnl_records(0);
+        let record = portfolio.get_pnl_records().last().unwrap();
+        assert!((record.get_net_value() - (1000.0 - 0.04 - 0.02)).abs() < 1e-9);
+        assert_eq!(record.get_funding(), portfolio.get_funding());
+    }
+
+    #[test]
+    fn test_check_liquidation() {
+        let mut portfolio = StrategyPortfolio::new(1000.0, 20.0, vec!["btcusdt".to_string()]);
+        portfolio.set_margin_mode(MarginMode::Isolated);
+        portfolio.fill_back_test_order(
+            "btcusdt",
+            &Order::new(
+                "btcusdt",
+                20000.0,
+                1.0,
+                OrderSide::BUY,
+                OrderType::Limit,
+                20000.0,
+                1.0,
+                "",
+                "",
+                OrderStatus::Filled,
+                0,
+            ),
+            false,
+        );
+        let liq_price = portfolio.get_liquidation_price("btcusdt", 20000.0).unwrap();
+        assert!((liq_price - 19000.0 / 0.996).abs() < 1e-6);
+        assert!(portfolio.check_liquidation("btcusdt", 19200.0, 1).is_none());
 
-        info!("Sharpe Ratio: {}", sharpe_ratio * sqrt_ratio);
-        info!("Sortino Ratio: {}", sortino_ratio * sqrt_ratio);
+        // a last price wick through the liquidation price does not move the mark price
+        let kline = Kline::new(
+            0, 299999, 19500.0, 19600.0, 18900.0, 19500.0, 1.0, 1, 0.0, 0.0,
+        );
+        let mark_kline = Kline::new(
+            0, 299999, 19500.0, 19550.0, 19300.0, 19500.0, 0.0, 0, 0.0, 0.0,
+        );
+        let klines = HashMap::from([("btcusdt".to_string(), kline)]);
+        let mark_price_klines = HashMap::from([("btcusdt".to_string(), mark_kline)]);
+        assert!(portfolio
+            .check_back_test_liquidation(&klines, &mark_price_klines)
+            .is_empty());
+
+        let order = portfolio.check_liquidation("btcusdt", 19000.0, 2).unwrap();
+        assert!(matches!(order.get_order_type(), OrderType::Liquidation));
+        assert_eq!(order.get_side(), OrderSide::SELL);
+        assert_eq!(
+            portfolio.get_position("btcusdt").unwrap().get_quantity(),
+            0.0
+        );
+        assert_eq!(portfolio.get_orders().get("btcusdt").unwrap().len(), 2);
     }
 }
@@ -0,0 +1,11 @@
+#[derive(Debug, Clone, Copy)]
+pub struct TargetPosition(f64);
+impl TargetPosition {
+    pub fn new(qty: f64) -> Self {
+        Self(qty)
+    }
+
+    pub fn get_position(&self) -> f64 {
+        self.0
+    }
+}
@@ -2,48 +2,73 @@ use hmac::{Hmac, Mac};
 use sha2::Sha256;
 
 use crate::base_model::api_model::{MarketData, MarketDataType};
+use crate::base_model::market_model::order_book_model::DepthUpdate;
 use crate::base_model::trade_model::order_model::Order;
 use crate::base_model::trade_model::position_model::Position;
 use crate::exchange_model::binance_model::ws_data::{self, WsOrderEvent};
 use crate::strategy_model::strategy_portfolio::Balance;
 
-fn get_kline_topic(symbol: &str) -> String {
-    format!("{}@kline_5m", symbol)
+fn is_kline_topic(topic: &str) -> bool {
+    topic.starts_with("kline_")
 }
 
-fn get_depth_topic(symbol: &str) -> String {
-    format!("{}@depth5", symbol)
+pub fn get_depth_update_topic(symbol: &str) -> String {
+    format!("{}@depth@100ms", symbol)
+}
+
+/// Diff depth events feed a local order book instead of being broadcast directly.
+pub fn parse_depth_update(data: &str) -> Option<(String, DepthUpdate)> {
+    let event = serde_json::from_str::<ws_data::WsEvent>(data).ok()?;
+    let symbol = event.stream.split('@').next()?.to_string();
+    if event.stream != get_depth_update_topic(&symbol) {
+        return None;
+    }
+    let ws_depth_update = serde_json::from_value::<ws_data::WsDepthUpdate>(event.data).ok()?;
+    Some((symbol, ws_depth_update.convert_to_depth_update()))
 }
 
 pub fn parse_market_data(data: &str) -> Option<MarketData> {
-    if let Ok(event) = serde_json::from_str::<ws_data::WsEvent>(&data) {
-        let (stream, data) = (event.stream, event.data);
-        let stream_parts: Vec<&str> = stream.split('@').collect();
-        if stream_parts.len() == 2 {
-            let symbol = stream_parts[0];
-            if stream == get_kline_topic(symbol) {
-                if let Ok(ws_event_kline) = serde_json::from_value::<ws_data::WsEventKline>(data) {
-                    let ws_kline = ws_event_kline.k;
-                    if ws_kline.is_final() {
-                        let kline = ws_kline.convert_to_standard_kline();
-                        return Some(MarketData::new(
-                            symbol.to_string(),
-                            MarketDataType::Kline(kline),
-                        ));
-                    }
-                }
-            } else if stream == get_depth_topic(symbol) {
-                if let Ok(ws_depth) = serde_json::from_value::<ws_data::WsDepth>(data) {
-                    let depth = ws_depth.convert_to_standard_depth();
-                    return Some(MarketData::new(
-                        symbol.to_string(),
-                        MarketDataType::Depth(depth),
-                    ));
-                }
+    let event = serde_json::from_str::<ws_data::WsEvent>(data).ok()?;
+    let (symbol, topic) = event.stream.split_once('@')?;
+    let data = event.data;
+    let market_data = match topic {
+        "depth5" => {
+            let ws_depth = serde_json::from_value::<ws_data::WsDepth>(data).ok()?;
+            MarketDataType::Depth(ws_depth.convert_to_standard_depth())
+        }
+        "trade" => {
+            let ws_trade = serde_json::from_value::<ws_data::WsTrade>(data).ok()?;
+            MarketDataType::Trade(ws_trade.convert_to_standard_trade())
+        }
+        "aggTrade" => {
+            let ws_trade = serde_json::from_value::<ws_data::WsAggTrade>(data).ok()?;
+            MarketDataType::AggTrade(ws_trade.convert_to_standard_trade())
+        }
+        "bookTicker" => {
+            let ws_book_ticker = serde_json::from_value::<ws_data::WsBookTicker>(data).ok()?;
+            MarketDataType::BookTicker(ws_book_ticker.convert_to_standard_book_ticker())
+        }
+        "markPrice" | "markPrice@1s" => {
+            let ws_mark_price = serde_json::from_value::<ws_data::WsMarkPrice>(data).ok()?;
+            MarketDataType::MarkPrice(ws_mark_price.convert_to_standard_mark_price())
+        }
+        "forceOrder" => {
+            let ws_force_order = serde_json::from_value::<ws_data::WsForceOrder>(data).ok()?;
+            MarketDataType::ForceOrder(ws_force_order.convert_to_standard_force_order())
+        }
+        topic if is_kline_topic(topic) => {
+            let ws_kline = serde_json::from_value::<ws_data::WsEventKline>(data)
+                .ok()?
+                .k;
+            // only closed klines are sent
+            if !ws_kline.is_final() {
+                return None;
             }
+            MarketDataType::Kline(ws_kline.convert_to_standard_kline())
         }
-    }
-    None
+        _ => return None,
+    };
+    Some(MarketData::new(symbol.to_string(), market_data))
 }
 
 pub fn parse_ws_order(data: &str) -> Option<Order> {
@@ -55,16 +80,19 @@ pub fn parse_ws_order(data: &str) -> Option<Order> {
     None
 }
 
+/// The balance is the USDT wallet balance, None if the event carries no USDT entry.
 pub fn parse_ws_account(data: &str) -> (Option<Balance>, Option<Vec<Position>>) {
     if let Ok(account_data) = serde_json::from_str::<ws_data::WsAccountEvent>(&data) {
         let update_time = account_data.get_update_time();
         let data = account_data.get_data();
         let balance_data = data.get_balance();
-        let mut balance_res = Balance::default();
+        let mut balance_res = None;
         let mut pos_res: Vec<Position> = Vec::new();
-        if balance_data.len() > 0 {
-            balance_res.set_balance(balance_data[0].get_balance());
-            balance_res.set_update_time(update_time);
+        if let Some(usdt) = balance_data.iter().find(|b| b.get_base() == "USDT") {
+            let mut balance = Balance::default();
+            balance.set_balance(usdt.get_balance());
+            balance.set_update_time(update_time);
+            balance_res = Some(balance);
         }
         let pos_data = data.get_position();
 
@@ -74,11 +102,24 @@ pub fn parse_ws_account(data: &str) -> (Option<Balance>, Option<Vec<Position>>)
                 pos_res.push(cur_standard_pos);
             }
         }
-        return (Some(balance_res), Some(pos_res));
+        return (balance_res, Some(pos_res));
     }
     (None, None)
 }
 
+/// Percent-encodes everything but unreserved characters, e.g. JSON query values.
+pub fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
 pub fn get_signature(secret_key: &str, params: &str) -> String {
     let mut mac = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())
         .expect("HMAC can take key of any size");
@@ -90,6 +131,15 @@ pub fn get_signature(secret_key: &str, params: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::base_enum::order_enums::{OrderSide, OrderStatus};
+
+    #[test]
+    fn test_url_encode() {
+        assert_eq!(
+            url_encode("[{\"symbol\":\"BTCUSDT\"}]"),
+            "%5B%7B%22symbol%22%3A%22BTCUSDT%22%7D%5D"
+        );
+    }
 
     #[test]
     fn test_create_signature() {
@@ -115,9 +165,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_depth_update() {
+        let data = "{\"stream\":\"btcusdt@depth@100ms\",\"data\":{\"e\":\"depthUpdate\",\"E\":1571889248277,\"T\":1571889248276,\"s\":\"BTCUSDT\",\"U\":390497796,\"u\":390497878,\"pu\":390497794,\"b\":[[\"7403.89\",\"0.002\"]],\"a\":[[\"7405.96\",\"3.340\"],[\"7406.63\",\"0\"]]}}";
+        let (symbol, update) = parse_depth_update(data).unwrap();
+        assert_eq!(symbol, "btcusdt");
+        assert_eq!(update.get_first_update_id(), 390497796);
+        assert_eq!(update.get_prev_final_update_id(), 390497794);
+        assert_eq!(update.get_asks()[1].get_quantity(), 0.0);
+        assert!(parse_market_data(data).is_none());
+    }
+
+    #[test]
+    fn test_parse_market_data() {
+        let data = "{\"stream\":\"btcusdt@aggTrade\",\"data\":{\"e\":\"aggTrade\",\"E\":123456789,\"s\":\"BTCUSDT\",\"a\":5933014,\"p\":\"0.001\",\"q\":\"100\",\"f\":100,\"l\":105,\"T\":123456785,\"m\":true}}";
+        let trade = parse_market_data(data).unwrap().get_trade().unwrap();
+        assert_eq!(trade.get_trade_id(), 5933014);
+        assert_eq!(trade.get_qty(), 100.0);
+        assert!(trade.is_buyer_maker());
+
+        let data = "{\"stream\":\"btcusdt@markPrice@1s\",\"data\":{\"e\":\"markPriceUpdate\",\"E\":1562305380000,\"s\":\"BTCUSDT\",\"p\":\"11794.15000000\",\"i\":\"11784.62659091\",\"P\":\"11784.25641265\",\"r\":\"0.00038167\",\"T\":1562306400000}}";
+        let mark_price = parse_market_data(data).unwrap().get_mark_price().unwrap();
+        assert_eq!(mark_price.get_funding_rate(), 0.00038167);
+        assert_eq!(mark_price.get_next_funding_time(), 1562306400000);
+
+        let data = "{\"stream\":\"btcusdt@forceOrder\",\"data\":{\"e\":\"forceOrder\",\"E\":1568014460893,\"o\":{\"s\":\"BTCUSDT\",\"S\":\"SELL\",\"o\":\"LIMIT\",\"f\":\"IOC\",\"q\":\"0.014\",\"p\":\"9910\",\"ap\":\"9910\",\"X\":\"FILLED\",\"l\":\"0.014\",\"z\":\"0.014\",\"T\":1568014460893}}}";
+        let force_order = parse_market_data(data).unwrap().get_force_order().unwrap();
+        assert_eq!(force_order.get_side(), OrderSide::SELL);
+        assert_eq!(force_order.get_status(), OrderStatus::Filled);
+
+        let data = "{\"stream\":\"btcusdt@bookTicker\",\"data\":{\"e\":\"bookTicker\",\"u\":400900217,\"E\":1568014460893,\"T\":1568014460891,\"s\":\"BNBUSDT\",\"b\":\"25.35190000\",\"B\":\"31.21000000\",\"a\":\"25.36520000\",\"A\":\"40.66000000\"}}";
+        let book_ticker = parse_market_data(data).unwrap().get_book_ticker().unwrap();
+        assert_eq!(book_ticker.get_ask_qty(), 40.66);
+    }
+
     #[test]
     fn test_parse_ws_account() {
         let data = "{\"e\":\"ACCOUNT_UPDATE\",\"T\":1721976305145,\"E\":1721976305145,\"a\":{\"B\":[{\"a\":\"USDT\",\"wb\":\"2102.58528451\",\"cw\":\"2102.58528451\",\"bc\":\"0\"}],\"P\":[{\"s\":\"BTCUSDT\",\"pa\":\"0\",\"ep\":\"0\",\"cr\":\"140.70390000\",\"up\":\"0\",\"mt\":\"cross\",\"iw\":\"0\",\"ps\":\"BOTH\",\"ma\":\"USDT\",\"bep\":\"0\"}],\"m\":\"ORDER\"}}";
-        parse_ws_account(data);
+        let (balance, positions) = parse_ws_account(data);
+        assert_eq!(balance.unwrap().get_balance(), 2102.58528451);
+        assert_eq!(positions.unwrap().len(), 1);
+
+        // a zero USDT balance is still a balance
+        let data = data.replace("2102.58528451", "0");
+        let (balance, _) = parse_ws_account(&data);
+        assert_eq!(balance.unwrap().get_balance(), 0.0);
+
+        let data = "{\"e\":\"ACCOUNT_UPDATE\",\"T\":1721976305145,\"E\":1721976305145,\"a\":{\"B\":[{\"a\":\"BNB\",\"wb\":\"1.5\",\"cw\":\"1.5\",\"bc\":\"0\"}],\"P\":[],\"m\":\"ORDER\"}}";
+        let (balance, positions) = parse_ws_account(data);
+        assert!(balance.is_none());
+        assert!(positions.unwrap().is_empty());
     }
 }
@@ -0,0 +1,122 @@
+use crate::base_enum::market_enums::Interval;
+use crate::base_model::market_model::kline_model::Kline;
+
+/// Combines `base_interval` klines into `interval` klines, dropping incomplete ones.
+pub fn resample_kline_data(
+    klines: Vec<Kline>,
+    base_interval: &Interval,
+    interval: &Interval,
+) -> Vec<Kline> {
+    let klines = cut_off_kline_data(klines, interval);
+    if interval == base_interval {
+        return klines;
+    }
+    let divider = interval.get_divider(base_interval);
+    klines
+        .chunks(divider)
+        .filter(|chunk| chunk.len() == divider)
+        .map(|chunk| {
+            chunk[1..]
+                .iter()
+                .fold(chunk[0], |acc, kline| acc.combine(kline))
+        })
+        .collect()
+}
+
+/// Intervals are aligned to the unix epoch, which starts on a UTC day boundary.
+pub fn is_interval_start(open_time: i64, interval: &Interval) -> bool {
+    open_time % interval.get_millis() == 0
+}
+
+/// Drop the leading klines until the first one aligned with the interval boundary.
+pub fn cut_off_kline_data(klines: Vec<Kline>, interval: &Interval) -> Vec<Kline> {
+    match klines
+        .iter()
+        .position(|k| is_interval_start(k.get_open_time(), interval))
+    {
+        Some(idx) => klines[idx..].to_vec(),
+        None => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_model::market_model::kline_model::CombineKline;
+
+    fn min5_klines(start: i64, len: usize) -> Vec<Kline> {
+        base_klines(start, len, &Interval::Min5)
+    }
+
+    fn base_klines(start: i64, len: usize, base_interval: &Interval) -> Vec<Kline> {
+        let millis = base_interval.get_millis();
+        (0..len)
+            .map(|i| {
+                let open_time = start + i as i64 * millis;
+                let px = 100.0 + i as f64;
+                Kline::new(
+                    open_time,
+                    open_time + millis - 1,
+                    px,
+                    px + 1.0,
+                    px - 1.0,
+                    px + 0.5,
+                    1.0,
+                    1,
+                    0.0,
+                    0.0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resample_kline_data() {
+        // 2024-04-07 00:05:00 UTC, one bar after the 15m boundary
+        let klines = min5_klines(1712448300000, 8);
+        let res = resample_kline_data(klines, &Interval::Min5, &Interval::Min15);
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].get_open_time(), 1712448900000);
+        assert_eq!(res[0].get_close_time(), 1712448900000 + 900_000 - 1);
+        assert_eq!(res[0].get_open(), 102.0);
+        assert_eq!(res[0].get_high(), 105.0);
+        assert_eq!(res[0].get_low(), 101.0);
+        assert_eq!(res[0].get_close(), 104.5);
+        assert_eq!(res[0].get_volume(), 3.0);
+    }
+
+    #[test]
+    fn test_resample_min1_kline_data() {
+        // 2024-04-07 00:00:00 UTC
+        let klines = base_klines(1712448000000, 125, &Interval::Min1);
+        let res = resample_kline_data(klines.clone(), &Interval::Min1, &Interval::Hour1);
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[1].get_open_time(), 1712448000000 + 3_600_000);
+        assert_eq!(res[1].get_volume(), 60.0);
+
+        let res = resample_kline_data(klines, &Interval::Min1, &Interval::Min5);
+        assert_eq!(res.len(), 25);
+        assert_eq!(res[0].get_close(), 104.5);
+        assert_eq!(Interval::Hour6.get_divider(&Interval::Min3), 120);
+    }
+
+    #[test]
+    fn test_combine_kline() {
+        // 2024-04-07 00:05:00 UTC, one bar after the 15m boundary
+        let klines = min5_klines(1712448300000, 8);
+        let mut combine_kline = CombineKline::new(vec![], Interval::Min15);
+        let mut res = vec![];
+        for kline in klines.iter() {
+            combine_kline.add(*kline);
+            if let Some(kline) = combine_kline.get_kline() {
+                res.push(kline);
+            }
+        }
+        let expected = resample_kline_data(klines, &Interval::Min5, &Interval::Min15);
+        assert_eq!(res.len(), expected.len());
+        for (a, b) in res.iter().zip(expected.iter()) {
+            assert_eq!(a.get_open_time(), b.get_open_time());
+            assert_eq!(a.get_close(), b.get_close());
+        }
+    }
+}
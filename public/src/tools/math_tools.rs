@@ -1,4 +1,20 @@
 pub fn round_to_precision(value: f64, precision: i64) -> f64 {
     let multiplier = 10f64.powi(precision as i32);
     (value * multiplier).round() / multiplier
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_to_precision() {
+        let value = 123.456789;
+        let precision = 2;
+        let expected_result = 123.46;
+
+        let result = round_to_precision(value, precision);
+
+        assert_eq!(result, expected_result);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod time_tools;
 pub mod settings_tools;
 pub mod api_tools;
-pub mod math_tools;
\ No newline at end of file
+pub mod math_tools;
+pub mod kline_tools;
\ No newline at end of file
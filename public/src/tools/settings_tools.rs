@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
 
@@ -6,6 +7,92 @@ use std::io::Read;
 pub struct Settings {
     api_key: String,
     secret_key: String,
+    #[serde(default)]
+    risk: RiskSettings,
+}
+
+/// Pre-trade limits of one symbol, a zero limit is disabled.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SymbolRiskLimits {
+    max_notional: f64,
+    max_position: f64,
+    max_orders_per_minute: usize,
+    /// Max relative distance of the order price from the last trade, e.g. 0.05.
+    price_band: f64,
+    max_leverage: f64,
+}
+
+impl SymbolRiskLimits {
+    pub fn new(
+        max_notional: f64,
+        max_position: f64,
+        max_orders_per_minute: usize,
+        price_band: f64,
+        max_leverage: f64,
+    ) -> Self {
+        Self {
+            max_notional,
+            max_position,
+            max_orders_per_minute,
+            price_band,
+            max_leverage,
+        }
+    }
+
+    pub fn get_max_notional(&self) -> f64 {
+        self.max_notional
+    }
+
+    pub fn get_max_position(&self) -> f64 {
+        self.max_position
+    }
+
+    pub fn get_max_orders_per_minute(&self) -> usize {
+        self.max_orders_per_minute
+    }
+
+    pub fn get_price_band(&self) -> f64 {
+        self.price_band
+    }
+
+    pub fn get_max_leverage(&self) -> f64 {
+        self.max_leverage
+    }
+}
+
+/// Risk limits of the order service. Symbols without an entry use `default_limits`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RiskSettings {
+    default_limits: SymbolRiskLimits,
+    symbols: HashMap<String, SymbolRiskLimits>,
+    /// Realized loss per strategy and UTC day after which new exposure is rejected.
+    max_daily_loss: f64,
+}
+
+impl RiskSettings {
+    pub fn new(
+        default_limits: SymbolRiskLimits,
+        symbols: HashMap<String, SymbolRiskLimits>,
+        max_daily_loss: f64,
+    ) -> Self {
+        Self {
+            default_limits,
+            symbols,
+            max_daily_loss,
+        }
+    }
+
+    pub fn get_symbol_limits(&self, symbol: &str) -> &SymbolRiskLimits {
+        self.symbols
+            .get(&symbol.to_uppercase())
+            .unwrap_or(&self.default_limits)
+    }
+
+    pub fn get_max_daily_loss(&self) -> f64 {
+        self.max_daily_loss
+    }
 }
 
 impl Settings {
@@ -16,6 +103,10 @@ impl Settings {
     pub fn get_secret_key(&self) -> String {
         self.secret_key.clone()
     }
+
+    pub fn get_risk_settings(&self) -> RiskSettings {
+        self.risk.clone()
+    }
 }
 
 pub fn load_settings(path: &str) -> Settings {
@@ -1,4 +1,4 @@
-use base_libs::market_data_module::general_data::Kline;
+use public::base_model::market_model::kline_model::Kline;
 
 pub struct SuperTrend {
     period: usize,
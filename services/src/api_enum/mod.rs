@@ -1,5 +1,10 @@
 pub enum FuturesApi {
     ExchangeInfo,
     Klines,
+    Depth,
+    FundingRate,
+    MarkPriceKlines,
+    IndexPriceKlines,
+    OpenInterestHist,
+    LeverageBracket,
 }
-
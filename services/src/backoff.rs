@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+/// Doubling reconnect or retry delay capped at `max_delay`, reset after a success.
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    initial_delay: Duration,
+    max_delay: Duration,
+    cur_delay: Duration,
+}
+
+impl ReconnectBackoff {
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            max_delay,
+            cur_delay: initial_delay,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.cur_delay;
+        self.cur_delay = (self.cur_delay * 2).min(self.max_delay);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.cur_delay = self.initial_delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+}
@@ -2,3 +2,4 @@ pub mod market_data_engine;
 pub mod api_enum;
 pub mod order_manager;
 pub mod mongo_engine;
+pub mod backoff;
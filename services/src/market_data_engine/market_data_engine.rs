@@ -1,5 +1,6 @@
 use super::rest_data_engine::RestDataEngine;
-use super::ws_data_engine::WsDataEngine;
+use super::ws_data_engine::{MarketStream, WsDataEngine};
+use public::base_enum::market_enums::Interval;
 use public::base_model::api_model::MarketData;
 use tokio::sync::broadcast::Sender;
 
@@ -26,9 +27,58 @@ impl MarketDataEngine {
         self.ws_data_engine.subscribe_symbols(symbols);
     }
 
+    /// Base kline interval downloaded and streamed for the symbol, 5m unless set.
+    pub fn set_symbol_interval(&mut self, symbol: &str, interval: Interval) {
+        self.rest_data_engine.set_symbol_interval(symbol, interval);
+        self.ws_data_engine.set_symbol_interval(symbol, interval);
+    }
+
+    /// Keys used to download margin brackets, see `RestDataEngine::set_api_keys`.
+    pub fn set_api_keys(&mut self, api_key: &str, secret_key: &str) {
+        self.rest_data_engine.set_api_keys(api_key, secret_key);
+    }
+
+    /// Streams subscribed for every symbol, klines and order books by default.
+    pub fn set_streams(&mut self, streams: Vec<MarketStream>) {
+        self.ws_data_engine.set_streams(streams);
+    }
+
     pub async fn start(&mut self, tx: Sender<MarketData>) {
         self.rest_data_engine.update_exchange_info().await;
+        self.ws_data_engine
+            .set_request_weight_limit(self.rest_data_engine.get_request_weight_limit());
+        self.rest_data_engine.update_margin_brackets().await;
         self.rest_data_engine.start().await;
         self.ws_data_engine.start_watch_send(tx).await;
     }
+
+    /// Subscribes symbols on the running stream and backfills their history in the
+    /// background, after refreshing the exchange info and margin brackets for them.
+    /// Set non-default base intervals with `set_symbol_interval` first.
+    pub fn add_symbols(&mut self, symbols: &[String]) {
+        let new_symbols: Vec<String> = symbols
+            .iter()
+            .filter(|s| !self.symbols.contains(s))
+            .cloned()
+            .collect();
+        if new_symbols.is_empty() {
+            return;
+        }
+        self.symbols.extend(new_symbols.iter().cloned());
+        self.ws_data_engine.add_symbols(&new_symbols);
+
+        let mut rest_data_engine = self.rest_data_engine.clone();
+        rest_data_engine.subscribe_symbols(&self.symbols);
+        tokio::spawn(async move {
+            rest_data_engine.pure_update_exchange_info("update").await;
+            rest_data_engine.update_margin_brackets().await;
+            rest_data_engine.subscribe_symbols(&new_symbols);
+            rest_data_engine.start().await;
+        });
+    }
+
+    pub fn remove_symbols(&mut self, symbols: &[String]) {
+        self.symbols.retain(|s| !symbols.contains(s));
+        self.ws_data_engine.remove_symbols(symbols);
+    }
 }
@@ -1,3 +1,5 @@
 pub mod market_data_engine;
 pub mod rest_data_engine;
 pub mod ws_data_engine;
+pub mod recorder;
+pub mod replayer;
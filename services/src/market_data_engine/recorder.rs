@@ -0,0 +1,147 @@
+use chrono::DateTime;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use public::base_model::api_model::MarketData;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+const DAY_MILLIS: i64 = 24 * 3600 * 1000;
+/// Events queued for the writer thread before receiving waits.
+const WRITE_BUFFER_RECORDS: usize = 4096;
+
+/// A `MarketData` event with the local time it was received, in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketDataRecord {
+    receive_time: i64,
+    market_data: MarketData,
+}
+
+impl MarketDataRecord {
+    pub fn new(receive_time: i64, market_data: MarketData) -> Self {
+        Self {
+            receive_time,
+            market_data,
+        }
+    }
+
+    pub fn get_receive_time(&self) -> i64 {
+        self.receive_time
+    }
+
+    pub fn get_market_data(&self) -> &MarketData {
+        &self.market_data
+    }
+}
+
+/// Persists market data events as gzip compressed JSON lines, one
+/// `market_data_YYYYMMDD.jsonl.gz` file per UTC day in `dir`. Reopening a day appends
+/// a new gzip member, which `MarketDataReplayer` reads through.
+pub struct MarketDataRecorder {
+    dir: PathBuf,
+    flush_interval: usize,
+    encoder: Option<GzEncoder<File>>,
+    cur_day: i64,
+    unflushed: usize,
+}
+
+impl MarketDataRecorder {
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+            flush_interval: 1000,
+            encoder: None,
+            cur_day: -1,
+            unflushed: 0,
+        }
+    }
+
+    /// Events buffered before the file is flushed, so a crash loses at most as many.
+    pub fn set_flush_interval(&mut self, flush_interval: usize) {
+        self.flush_interval = flush_interval.max(1);
+    }
+
+    pub fn get_file_path(&self, receive_time: i64) -> PathBuf {
+        let date = DateTime::from_timestamp_millis(receive_time).unwrap();
+        self.dir
+            .join(format!("market_data_{}.jsonl.gz", date.format("%Y%m%d")))
+    }
+
+    /// Records every event from `rx` until the channel closes. Files are written on a
+    /// blocking thread, so compression and disk writes never stall the runtime.
+    pub async fn start_record(mut self, mut rx: Receiver<MarketData>) {
+        info!("MarketDataRecorder Start...");
+        let (record_tx, mut record_rx) = mpsc::channel::<MarketDataRecord>(WRITE_BUFFER_RECORDS);
+        let writer = tokio::task::spawn_blocking(move || {
+            while let Some(record) = record_rx.blocking_recv() {
+                if let Err(e) = self.write_record(record) {
+                    error!("MarketDataRecorder Write Error: {}", e);
+                }
+            }
+            if let Err(e) = self.close() {
+                error!("MarketDataRecorder Close Error: {}", e);
+            }
+        });
+        loop {
+            match rx.recv().await {
+                Ok(market_data) => {
+                    let receive_time = chrono::Utc::now().timestamp_millis();
+                    let record = MarketDataRecord::new(receive_time, market_data);
+                    if record_tx.send(record).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(count)) => {
+                    warn!("MarketDataRecorder lagged, {} events lost", count);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+        drop(record_tx);
+        if let Err(e) = writer.await {
+            error!("MarketDataRecorder Writer Error: {}", e);
+        }
+    }
+
+    pub fn write(&mut self, receive_time: i64, market_data: MarketData) -> std::io::Result<()> {
+        self.write_record(MarketDataRecord::new(receive_time, market_data))
+    }
+
+    fn write_record(&mut self, record: MarketDataRecord) -> std::io::Result<()> {
+        let receive_time = record.get_receive_time();
+        let day = receive_time.div_euclid(DAY_MILLIS);
+        if self.encoder.is_none() || day != self.cur_day {
+            self.close()?;
+            std::fs::create_dir_all(&self.dir)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.get_file_path(receive_time))?;
+            self.encoder = Some(GzEncoder::new(file, Compression::default()));
+            self.cur_day = day;
+        }
+        let line = serde_json::to_string(&record)?;
+        let encoder = self.encoder.as_mut().unwrap();
+        encoder.write_all(line.as_bytes())?;
+        encoder.write_all(b"\n")?;
+        self.unflushed += 1;
+        if self.unflushed >= self.flush_interval {
+            encoder.flush()?;
+            self.unflushed = 0;
+        }
+        Ok(())
+    }
+
+    /// Finishes the current file.
+    pub fn close(&mut self) -> std::io::Result<()> {
+        if let Some(encoder) = self.encoder.take() {
+            encoder.finish()?;
+        }
+        self.unflushed = 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+use super::recorder::MarketDataRecord;
+use flate2::read::MultiGzDecoder;
+use public::base_model::api_model::MarketData;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::broadcast::Sender;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// Records decoded ahead of the replay.
+const READ_AHEAD_RECORDS: usize = 1024;
+
+/// Feeds files written by `MarketDataRecorder` back into a market data channel,
+/// keeping the recorded gaps between events divided by `speed`. Files are decoded
+/// line by line on a blocking thread, and sending waits while receivers are
+/// `max_pending` events behind, so a fast replay does not make them lag.
+pub struct MarketDataReplayer {
+    paths: Vec<PathBuf>,
+    speed: f64,
+    max_pending: usize,
+}
+
+impl MarketDataReplayer {
+    /// Files are replayed in the given order.
+    pub fn new(paths: Vec<String>) -> Self {
+        Self {
+            paths: paths.into_iter().map(PathBuf::from).collect(),
+            speed: 1.0,
+            max_pending: 256,
+        }
+    }
+
+    /// All recordings in `dir`, oldest first.
+    pub fn from_dir(dir: &str) -> std::io::Result<Self> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.ends_with(".jsonl.gz"))
+            })
+            .collect();
+        paths.sort();
+        Ok(Self {
+            paths,
+            speed: 1.0,
+            max_pending: 256,
+        })
+    }
+
+    /// 1.0 replays in real time, 10.0 ten times faster and 0.0 without waiting.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Events the slowest receiver may be behind before sending waits. Must stay below
+    /// the channel capacity, 256 by default.
+    pub fn set_max_pending(&mut self, max_pending: usize) {
+        self.max_pending = max_pending.max(1);
+    }
+
+    /// Sends the records of the recordings to `tx` one by one, blocking while it is
+    /// full. A truncated tail, e.g. after a crash, ends the file.
+    fn read_records(paths: &[PathBuf], tx: mpsc::Sender<MarketDataRecord>) {
+        for path in paths {
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(e) => {
+                    error!("MarketDataReplayer failed to read {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            for line in BufReader::new(MultiGzDecoder::new(file)).lines() {
+                match line {
+                    Ok(line) => match serde_json::from_str::<MarketDataRecord>(&line) {
+                        Ok(record) => {
+                            if tx.blocking_send(record).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            warn!("MarketDataReplayer skip line in {:?}: {}", path, e);
+                        }
+                    },
+                    Err(e) => {
+                        warn!("MarketDataReplayer stop reading {:?}: {}", path, e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends every recorded event to `tx` and returns how many were sent.
+    pub async fn replay(&self, tx: Sender<MarketData>) -> usize {
+        info!("MarketDataReplayer Start...");
+        let (record_tx, mut record_rx) = mpsc::channel(READ_AHEAD_RECORDS);
+        let paths = self.paths.clone();
+        let reader = tokio::task::spawn_blocking(move || Self::read_records(&paths, record_tx));
+        let mut count = 0;
+        let mut last_receive_time: Option<i64> = None;
+        while let Some(record) = record_rx.recv().await {
+            if let Some(last_receive_time) = last_receive_time {
+                let gap = (record.get_receive_time() - last_receive_time).max(0);
+                if self.speed > 0.0 && gap > 0 {
+                    let wait = Duration::from_secs_f64(gap as f64 / 1000.0 / self.speed);
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            last_receive_time = Some(record.get_receive_time());
+            while tx.len() >= self.max_pending {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            match tx.send(record.get_market_data().clone()) {
+                Ok(_) => count += 1,
+                Err(e) => {
+                    error!("MarketDataReplayer Send Error: {}", e);
+                }
+            }
+        }
+        if let Err(e) = reader.await {
+            error!("MarketDataReplayer Read Error: {}", e);
+        }
+        info!("MarketDataReplayer replayed {} events", count);
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market_data_engine::recorder::MarketDataRecorder;
+    use public::base_model::api_model::MarketDataType;
+    use public::base_model::market_model::kline_model::Kline;
+    use public::base_model::market_model::trade_model::Trade;
+    use tokio::sync::broadcast::error::RecvError;
+
+    #[tokio::test]
+    async fn test_record_replay() {
+        let dir = std::env::temp_dir().join(format!("market_data_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut recorder = MarketDataRecorder::new(dir.to_str().unwrap());
+        recorder.set_flush_interval(1);
+        let kline = Kline::new(0, 299_999, 1.0, 2.0, 0.5, 1.5, 10.0, 3, 0.0, 0.0);
+        let trade = Trade::new(7, 1.5, 0.1, 1_000, true);
+        // 2024-04-07 23:59:59 and 2024-04-08 00:00:01 UTC, two files
+        recorder
+            .write(
+                1712534399000,
+                MarketData::new("btcusdt".to_string(), MarketDataType::Kline(kline)),
+            )
+            .unwrap();
+        recorder
+            .write(
+                1712534401000,
+                MarketData::new("btcusdt".to_string(), MarketDataType::AggTrade(trade)),
+            )
+            .unwrap();
+        recorder.close().unwrap();
+
+        let mut replayer = MarketDataReplayer::from_dir(dir.to_str().unwrap()).unwrap();
+        assert_eq!(replayer.paths.len(), 2);
+        replayer.set_speed(0.0);
+        let (tx, mut rx) = tokio::sync::broadcast::channel::<MarketData>(16);
+        assert_eq!(replayer.replay(tx).await, 2);
+        assert_eq!(
+            rx.recv().await.unwrap().get_kline().unwrap().get_close(),
+            1.5
+        );
+        assert_eq!(rx.recv().await.unwrap().get_trade(), Some(trade));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_backpressure() {
+        let dir = std::env::temp_dir().join(format!("market_replay_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut recorder = MarketDataRecorder::new(dir.to_str().unwrap());
+        for i in 0..200 {
+            let trade = Trade::new(i, 1.5, 0.1, 1_000 + i, true);
+            recorder
+                .write(
+                    1712534399000,
+                    MarketData::new("btcusdt".to_string(), MarketDataType::AggTrade(trade)),
+                )
+                .unwrap();
+        }
+        recorder.close().unwrap();
+
+        let mut replayer = MarketDataReplayer::from_dir(dir.to_str().unwrap()).unwrap();
+        replayer.set_speed(0.0);
+        replayer.set_max_pending(8);
+        // far more events than the channel holds, read by a slow receiver
+        let (tx, mut rx) = tokio::sync::broadcast::channel::<MarketData>(16);
+        let consumer = tokio::spawn(async move {
+            let mut ids = Vec::new();
+            loop {
+                match rx.recv().await {
+                    Ok(market_data) => ids.push(market_data.get_trade().unwrap().get_trade_id()),
+                    Err(RecvError::Lagged(count)) => panic!("Lagged by {} events", count),
+                    Err(RecvError::Closed) => break,
+                }
+                tokio::time::sleep(Duration::from_micros(100)).await;
+            }
+            ids
+        });
+        assert_eq!(replayer.replay(tx).await, 200);
+        assert_eq!(consumer.await.unwrap(), (0..200).collect::<Vec<i64>>());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
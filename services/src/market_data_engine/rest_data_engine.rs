@@ -1,22 +1,41 @@
 use crate::api_enum::FuturesApi;
-use public::exchange_model::binance_model::rest_data;
-use public::base_model::info_model::{SymbolInfo, ExchangeInfo};
-use public::base_model::market_model::kline_model::Kline;
-use public::base_enum::market_enums::MarketType;
 use crate::mongo_engine::MongoEngine;
-
-
+use public::base_enum::market_enums::{Interval, MarketType, PriceType, SymbolIntervals};
+use public::base_model::info_model::{ExchangeInfo, SymbolInfo};
+use public::base_model::market_model::funding_rate_model::FundingRate;
+use public::base_model::market_model::kline_model::Kline;
+use public::base_model::market_model::open_interest_model::OpenInterest;
+use public::base_model::market_model::order_book_model::OrderBook;
+use public::exchange_model::binance_model::rest_data;
+use public::tools::{api_tools, time_tools};
 
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
 use serde_json::Value;
 use tokio;
 use tracing::{error, info};
 
+const OPEN_INTEREST_HISTORY_MILLIS: i64 = 29 * 24 * 3600 * 1000;
+const OPEN_INTEREST_BATCH_MILLIS: i64 = 500 * 5 * 60 * 1000;
+
+/// Historical series backfilled into Mongo per symbol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryData {
+    Klines,
+    FundingRate,
+    MarkPriceKlines,
+    IndexPriceKlines,
+    OpenInterest,
+}
+
 #[derive(Clone)]
 pub struct RestDataEngine {
     url: String,
+    api_key: String,
+    secret_key: String,
     request_weight_limit: i64,
     symbols: Vec<String>,
+    symbol_intervals: SymbolIntervals,
+    history_data: Vec<HistoryData>,
     mongo_engine: MongoEngine,
 }
 
@@ -24,8 +43,12 @@ impl Default for RestDataEngine {
     fn default() -> Self {
         Self {
             url: "https://fapi.binance.com".to_string(),
+            api_key: "".to_string(),
+            secret_key: "".to_string(),
             request_weight_limit: 0,
             symbols: vec![],
+            symbol_intervals: SymbolIntervals::default(),
+            history_data: vec![HistoryData::Klines],
             mongo_engine: MongoEngine::default(),
         }
     }
@@ -36,6 +59,12 @@ impl RestDataEngine {
         let api_url = match item {
             FuturesApi::ExchangeInfo => "/fapi/v1/exchangeInfo".to_string(),
             FuturesApi::Klines => "/fapi/v1/klines".to_string(),
+            FuturesApi::Depth => "/fapi/v1/depth".to_string(),
+            FuturesApi::FundingRate => "/fapi/v1/fundingRate".to_string(),
+            FuturesApi::MarkPriceKlines => "/fapi/v1/markPriceKlines".to_string(),
+            FuturesApi::IndexPriceKlines => "/fapi/v1/indexPriceKlines".to_string(),
+            FuturesApi::OpenInterestHist => "/futures/data/openInterestHist".to_string(),
+            FuturesApi::LeverageBracket => "/fapi/v1/leverageBracket".to_string(),
         };
         format!("{}{}", self.url, api_url)
     }
@@ -126,6 +155,21 @@ impl RestDataEngine {
         self.symbols = symbols.to_vec();
     }
 
+    /// Keys for the signed margin bracket request, the only one needing an account.
+    pub fn set_api_keys(&mut self, api_key: &str, secret_key: &str) {
+        self.api_key = api_key.to_string();
+        self.secret_key = secret_key.to_string();
+    }
+
+    /// Base kline interval downloaded for the symbol, 5m unless set.
+    pub fn set_symbol_interval(&mut self, symbol: &str, interval: Interval) {
+        self.symbol_intervals.set_interval(symbol, interval);
+    }
+
+    pub fn get_symbol_interval(&self, symbol: &str) -> Interval {
+        self.symbol_intervals.get_interval(symbol)
+    }
+
     pub async fn fetch_exchange_info(&self) -> Option<ExchangeInfo> {
         match reqwest::get(self.get_api(FuturesApi::ExchangeInfo)).await {
             Ok(res) => match res.text().await {
@@ -169,7 +213,9 @@ impl RestDataEngine {
         }
     }
 
-    async fn pure_update_exchange_info(&mut self, msg: &str) {
+    /// Fetches and stores the exchange info of the subscribed symbols, even if the
+    /// stored one is recent.
+    pub async fn pure_update_exchange_info(&mut self, msg: &str) {
         if let Some(exchange_info) = self.fetch_exchange_info().await {
             println!("{:?}", exchange_info);
             self.request_weight_limit = exchange_info.get_rest_limit_rate();
@@ -200,23 +246,29 @@ impl RestDataEngine {
         }
     }
 
-    pub async fn fetch_single_batch_his_kline(
-        &self,
-        symbol: &str,
-        start_time: i64,
-    ) -> Option<Vec<Kline>> {
-        let api_url = self.get_api(FuturesApi::Klines);
-        let request_url = format!(
-            "{}?symbol={}&interval=5m&startTime={}&limit=1000",
-            api_url, symbol, start_time
-        );
-        match reqwest::get(request_url.clone()).await {
+    /// Request weight per minute from the exchange info, 0 until it is loaded.
+    pub fn get_request_weight_limit(&self) -> i64 {
+        self.request_weight_limit
+    }
+
+    pub fn set_request_weight_limit(&mut self, request_weight_limit: i64) {
+        self.request_weight_limit = request_weight_limit;
+    }
+
+    /// Whether the used weight nears the limit, never without a known limit.
+    fn is_weight_exhausted(&self, used_weight: i64) -> bool {
+        self.request_weight_limit > 0 && used_weight >= self.request_weight_limit * 8 / 10
+    }
+
+    /// GET `request_url` and back off when the used weight nears the limit.
+    async fn request(&self, request_url: &str) -> Option<String> {
+        match reqwest::get(request_url).await {
             Ok(res) => {
                 let headers = res.headers();
                 for (name, value) in headers {
-                    if name.to_string() == "x-mbx-used-weight-1m".to_string() {
+                    if name == "x-mbx-used-weight-1m" {
                         let cur_limit = value.to_str().unwrap().parse::<i64>().unwrap();
-                        if cur_limit >= self.request_weight_limit * 8 / 10 {
+                        if self.is_weight_exhausted(cur_limit) {
                             // sleep 10 seconds
                             info!("Request weight limit reached, sleep 10 seconds");
                             tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
@@ -224,31 +276,7 @@ impl RestDataEngine {
                     }
                 }
                 match res.text().await {
-                    Ok(data) => {
-                        if let Ok(klines) = serde_json::from_str::<Vec<Vec<Value>>>(&data) {
-                            let format_kline: Vec<Kline> = klines
-                                .iter()
-                                .map(|x| {
-                                    Kline::new(
-                                        x[0].as_i64().unwrap(),
-                                        x[6].as_i64().unwrap(),
-                                        x[1].as_str().unwrap().parse::<f64>().unwrap(),
-                                        x[2].as_str().unwrap().parse::<f64>().unwrap(),
-                                        x[3].as_str().unwrap().parse::<f64>().unwrap(),
-                                        x[4].as_str().unwrap().parse::<f64>().unwrap(),
-                                        x[5].as_str().unwrap().parse::<f64>().unwrap(),
-                                        x[8].as_i64().unwrap(),
-                                        x[9].as_str().unwrap().parse::<f64>().unwrap(),
-                                        x[10].as_str().unwrap().parse::<f64>().unwrap(),
-                                    )
-                                })
-                                .collect();
-                            Some(format_kline)
-                        } else {
-                            error!("Failed to parse kline data");
-                            None
-                        }
-                    }
+                    Ok(data) => Some(data),
                     Err(e) => {
                         error!("{}", e);
                         None
@@ -262,12 +290,237 @@ impl RestDataEngine {
         }
     }
 
-    pub async fn fetch_his_kline(&self, symbol: &str) {
+    async fn fetch_margin_brackets(&self) -> Option<Vec<rest_data::LeverageBracketResponse>> {
+        let params = format!(
+            "timestamp={}&recvWindow=5000",
+            time_tools::get_now_timestamp()
+        );
+        let signature = api_tools::get_signature(&self.secret_key, &params);
+        let request_url = format!(
+            "{}?{}&signature={}",
+            self.get_api(FuturesApi::LeverageBracket),
+            params,
+            signature
+        );
+        match reqwest::Client::new()
+            .get(&request_url)
+            .header("X-MBX-APIKEY", self.api_key.clone())
+            .send()
+            .await
+        {
+            Ok(res) => match res.text().await {
+                Ok(data) => match serde_json::from_str(&data) {
+                    Ok(brackets) => Some(brackets),
+                    Err(e) => {
+                        error!("Failed to parse margin brackets {} {}", e, data);
+                        None
+                    }
+                },
+                Err(e) => {
+                    error!("{}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                error!("Failed to connect url {} {}", request_url, e);
+                None
+            }
+        }
+    }
+
+    /// Stores the maintenance margin brackets of the subscribed symbols for
+    /// backtest liquidations. Skipped without api keys.
+    pub async fn update_margin_brackets(&self) {
+        if self.api_key.is_empty() {
+            info!("No api key set, skip updating margin brackets");
+            return;
+        }
+        if let Some(brackets) = self.fetch_margin_brackets().await {
+            for bracket in brackets {
+                let symbol = bracket.symbol.to_lowercase();
+                if !self.symbols.contains(&symbol) {
+                    continue;
+                }
+                match self
+                    .mongo_engine
+                    .update_margin_brackets(&symbol, &bracket.convert_into_margin_brackets())
+                    .await
+                {
+                    Ok(_) => info!("Update {} margin brackets successfully", symbol),
+                    Err(e) => error!("{}", e),
+                }
+            }
+        }
+    }
+
+    fn get_start_time(&self) -> i64 {
         let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
         let time = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
         let naive_datetime = NaiveDateTime::new(date, time);
-        let mut start_time = naive_datetime.and_utc().timestamp_millis();
-        match self.mongo_engine.fetch_latest_kline(symbol).await {
+        naive_datetime.and_utc().timestamp_millis()
+    }
+
+    pub async fn fetch_single_batch_his_kline(
+        &self,
+        symbol: &str,
+        start_time: i64,
+    ) -> Option<Vec<Kline>> {
+        self.fetch_single_batch_price_kline(PriceType::Last, symbol, start_time)
+            .await
+    }
+
+    /// Mark and index price klines only carry prices, their volume fields are 0.
+    pub async fn fetch_single_batch_price_kline(
+        &self,
+        price_type: PriceType,
+        symbol: &str,
+        start_time: i64,
+    ) -> Option<Vec<Kline>> {
+        let interval = self.get_symbol_interval(symbol).get_interval_string();
+        let request_url = match price_type {
+            PriceType::Last => format!(
+                "{}?symbol={}&interval={}&startTime={}&limit=1000",
+                self.get_api(FuturesApi::Klines),
+                symbol,
+                interval,
+                start_time
+            ),
+            PriceType::Mark => format!(
+                "{}?symbol={}&interval={}&startTime={}&limit=1000",
+                self.get_api(FuturesApi::MarkPriceKlines),
+                symbol,
+                interval,
+                start_time
+            ),
+            PriceType::Index => format!(
+                "{}?pair={}&interval={}&startTime={}&limit=1000",
+                self.get_api(FuturesApi::IndexPriceKlines),
+                symbol,
+                interval,
+                start_time
+            ),
+        };
+        let data = self.request(&request_url).await?;
+        if let Ok(klines) = serde_json::from_str::<Vec<Vec<Value>>>(&data) {
+            let format_kline: Vec<Kline> = klines
+                .iter()
+                .map(|x| {
+                    Kline::new(
+                        x[0].as_i64().unwrap(),
+                        x[6].as_i64().unwrap(),
+                        x[1].as_str().unwrap().parse::<f64>().unwrap(),
+                        x[2].as_str().unwrap().parse::<f64>().unwrap(),
+                        x[3].as_str().unwrap().parse::<f64>().unwrap(),
+                        x[4].as_str().unwrap().parse::<f64>().unwrap(),
+                        x[5].as_str().unwrap().parse::<f64>().unwrap_or(0.0),
+                        x[8].as_i64().unwrap_or(0),
+                        x[9].as_str().unwrap().parse::<f64>().unwrap_or(0.0),
+                        x[10].as_str().unwrap().parse::<f64>().unwrap_or(0.0),
+                    )
+                })
+                .collect();
+            Some(format_kline)
+        } else {
+            error!("Failed to parse kline data");
+            None
+        }
+    }
+
+    /// Order book snapshot used to start or resync a local book.
+    pub async fn fetch_depth_snapshot(&self, symbol: &str) -> Option<OrderBook> {
+        let request_url = format!(
+            "{}?symbol={}&limit=1000",
+            self.get_api(FuturesApi::Depth),
+            symbol
+        );
+        let data = self.request(&request_url).await?;
+        match serde_json::from_str::<rest_data::DepthResponse>(&data) {
+            Ok(depth) => Some(depth.convert_into_order_book()),
+            Err(e) => {
+                error!("Failed to parse depth snapshot {} {}", symbol, e);
+                None
+            }
+        }
+    }
+
+    pub async fn fetch_single_batch_funding_rate(
+        &self,
+        symbol: &str,
+        start_time: i64,
+    ) -> Option<Vec<FundingRate>> {
+        let request_url = format!(
+            "{}?symbol={}&startTime={}&limit=1000",
+            self.get_api(FuturesApi::FundingRate),
+            symbol,
+            start_time
+        );
+        let data = self.request(&request_url).await?;
+        match serde_json::from_str::<Vec<rest_data::FundingRateResponse>>(&data) {
+            Ok(funding_rates) => match funding_rates
+                .iter()
+                .map(|x| x.convert_into_funding_rate())
+                .collect()
+            {
+                Ok(funding_rates) => Some(funding_rates),
+                Err(e) => {
+                    error!("Failed to parse funding rate values {}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                error!("Failed to parse funding rate data {}", e);
+                None
+            }
+        }
+    }
+
+    /// Binance keeps open interest statistics for the last 30 days only.
+    pub async fn fetch_single_batch_open_interest(
+        &self,
+        symbol: &str,
+        start_time: i64,
+    ) -> Option<Vec<OpenInterest>> {
+        let request_url = format!(
+            "{}?symbol={}&period=5m&startTime={}&endTime={}&limit=500",
+            self.get_api(FuturesApi::OpenInterestHist),
+            symbol,
+            start_time,
+            start_time + OPEN_INTEREST_BATCH_MILLIS - 1
+        );
+        let data = self.request(&request_url).await?;
+        match serde_json::from_str::<Vec<rest_data::OpenInterestResponse>>(&data) {
+            Ok(open_interest) => match open_interest
+                .iter()
+                .map(|x| x.convert_into_open_interest())
+                .collect()
+            {
+                Ok(open_interest) => Some(open_interest),
+                Err(e) => {
+                    error!("Failed to parse open interest values {}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                error!("Failed to parse open interest data {}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn fetch_his_kline(&self, symbol: &str) {
+        self.fetch_his_price_kline(PriceType::Last, symbol).await;
+    }
+
+    /// Backfills klines of the price type from the last stored one, or from 2020-01-01.
+    /// The unfinished kline is never stored.
+    pub async fn fetch_his_price_kline(&self, price_type: PriceType, symbol: &str) {
+        let mut start_time = self.get_start_time();
+        let interval = self.get_symbol_interval(symbol);
+        match self
+            .mongo_engine
+            .fetch_latest_price_kline(price_type, symbol, &interval)
+            .await
+        {
             Ok(last_stored_kline) => {
                 if let Some(last_stored_kline) = last_stored_kline {
                     start_time = last_stored_kline.get_close_time() + 1;
@@ -275,25 +528,35 @@ impl RestDataEngine {
 
                 let mut is_finished = false;
                 loop {
-                    match self.fetch_single_batch_his_kline(symbol, start_time).await {
+                    match self
+                        .fetch_single_batch_price_kline(price_type, symbol, start_time)
+                        .await
+                    {
                         Some(batch_klines) => {
+                            if batch_klines.is_empty() {
+                                break;
+                            }
                             let mut cur_klines = batch_klines.clone();
                             start_time = cur_klines.last().unwrap().get_close_time() + 1;
 
                             if start_time >= chrono::Utc::now().timestamp_millis() {
-                                info!("{} kline data done !", symbol);
+                                info!("{} {:?} kline data done !", symbol, price_type);
                                 is_finished = true;
                                 cur_klines = cur_klines[..cur_klines.len() - 1].to_vec();
                             }
 
-                            if cur_klines.len() != 0 {
-                                match self.mongo_engine.insert_kline(symbol, &cur_klines).await {
+                            if !cur_klines.is_empty() {
+                                match self
+                                    .mongo_engine
+                                    .insert_price_klines(price_type, symbol, &interval, &cur_klines)
+                                    .await
+                                {
                                     Ok(_) => {
                                         let start_datetime =
                                             DateTime::from_timestamp(start_time / 1000, 0).unwrap();
                                         info!(
-                                            "Inserted {} kline data with start_time: {:#?} count: {}",
-                                            symbol, start_datetime, cur_klines.len()
+                                            "Inserted {} {:?} kline data with start_time: {:#?} count: {}",
+                                            symbol, price_type, start_datetime, cur_klines.len()
                                         );
                                     }
                                     Err(e) => {
@@ -305,7 +568,8 @@ impl RestDataEngine {
                             }
                         }
                         None => {
-                            error!("Failed to fetch {} kline data", symbol);
+                            error!("Failed to fetch {} {:?} kline data", symbol, price_type);
+                            break;
                         }
                     }
                     if is_finished {
@@ -319,18 +583,166 @@ impl RestDataEngine {
         }
     }
 
+    /// Backfills settled funding rates from the last stored one, or from 2020-01-01.
+    pub async fn fetch_his_funding_rate(&self, symbol: &str) {
+        let mut start_time = self.get_start_time();
+        match self.mongo_engine.fetch_latest_funding_rate(symbol).await {
+            Ok(last_stored) => {
+                if let Some(last_stored) = last_stored {
+                    start_time = last_stored.get_funding_time() + 1;
+                }
+                loop {
+                    match self
+                        .fetch_single_batch_funding_rate(symbol, start_time)
+                        .await
+                    {
+                        Some(funding_rates) => {
+                            if funding_rates.is_empty() {
+                                info!("{} funding rate data done !", symbol);
+                                break;
+                            }
+                            start_time = funding_rates.last().unwrap().get_funding_time() + 1;
+                            match self
+                                .mongo_engine
+                                .insert_funding_rates(symbol, &funding_rates)
+                                .await
+                            {
+                                Ok(_) => {
+                                    info!(
+                                        "Inserted {} funding rate data count: {}",
+                                        symbol,
+                                        funding_rates.len()
+                                    );
+                                }
+                                Err(e) => {
+                                    error!("Failed to insert_funding_rates {} {}", symbol, e);
+                                    break;
+                                }
+                            }
+                        }
+                        None => {
+                            error!("Failed to fetch {} funding rate data", symbol);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                error!(
+                    "failed to fetch_latest_funding_rate {} Error: {}",
+                    symbol, e
+                );
+            }
+        }
+    }
+
+    /// Backfills 5m open interest from the last stored record, limited to the window
+    /// Binance still serves.
+    pub async fn fetch_his_open_interest(&self, symbol: &str) {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut start_time = now - OPEN_INTEREST_HISTORY_MILLIS;
+        match self.mongo_engine.fetch_latest_open_interest(symbol).await {
+            Ok(last_stored) => {
+                if let Some(last_stored) = last_stored {
+                    start_time = start_time.max(last_stored.get_timestamp() + 1);
+                }
+                while start_time < now {
+                    match self
+                        .fetch_single_batch_open_interest(symbol, start_time)
+                        .await
+                    {
+                        Some(open_interest) => {
+                            start_time = match open_interest.last() {
+                                Some(last) => last.get_timestamp() + 1,
+                                None => start_time + OPEN_INTEREST_BATCH_MILLIS,
+                            };
+                            match self
+                                .mongo_engine
+                                .insert_open_interest(symbol, &open_interest)
+                                .await
+                            {
+                                Ok(_) => {
+                                    info!(
+                                        "Inserted {} open interest data count: {}",
+                                        symbol,
+                                        open_interest.len()
+                                    );
+                                }
+                                Err(e) => {
+                                    error!("Failed to insert_open_interest {} {}", symbol, e);
+                                    break;
+                                }
+                            }
+                        }
+                        None => {
+                            error!("Failed to fetch {} open interest data", symbol);
+                            break;
+                        }
+                    }
+                }
+                info!("{} open interest data done !", symbol);
+            }
+            Err(e) => {
+                error!(
+                    "failed to fetch_latest_open_interest {} Error: {}",
+                    symbol, e
+                );
+            }
+        }
+    }
+
+    /// Selects the series backfilled by `start`, klines only by default.
+    pub fn set_history_data(&mut self, history_data: Vec<HistoryData>) {
+        self.history_data = history_data;
+    }
+
     pub async fn start(&self) {
         let symbol_list = self.symbols.clone();
         let mut handles = Vec::new();
         for symbol in symbol_list {
-            let cur_engine = self.clone();
-            let handle = tokio::spawn(async move {
-                cur_engine.fetch_his_kline(&symbol).await;
-            });
-            handles.push(handle);
+            for history_data in self.history_data.clone() {
+                let cur_engine = self.clone();
+                let symbol = symbol.clone();
+                let handle = tokio::spawn(async move {
+                    match history_data {
+                        HistoryData::Klines => cur_engine.fetch_his_kline(&symbol).await,
+                        HistoryData::MarkPriceKlines => {
+                            cur_engine
+                                .fetch_his_price_kline(PriceType::Mark, &symbol)
+                                .await
+                        }
+                        HistoryData::IndexPriceKlines => {
+                            cur_engine
+                                .fetch_his_price_kline(PriceType::Index, &symbol)
+                                .await
+                        }
+                        HistoryData::FundingRate => {
+                            cur_engine.fetch_his_funding_rate(&symbol).await
+                        }
+                        HistoryData::OpenInterest => {
+                            cur_engine.fetch_his_open_interest(&symbol).await
+                        }
+                    }
+                });
+                handles.push(handle);
+            }
         }
         for handle in handles {
             handle.await.unwrap();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_weight_exhausted() {
+        let mut engine = RestDataEngine::default();
+        assert!(!engine.is_weight_exhausted(100));
+        engine.set_request_weight_limit(2400);
+        assert!(!engine.is_weight_exhausted(1000));
+        assert!(engine.is_weight_exhausted(1920));
+    }
+}
@@ -1,19 +1,126 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
-use public::tools::api_tools;
-use public::base_model::api_model::MarketData;
-use public::base_model::market_model::kline_model::Kline;
+use super::rest_data_engine::RestDataEngine;
+use crate::backoff::ReconnectBackoff;
 use futures_util::{SinkExt, StreamExt};
+use public::base_enum::market_enums::{Interval, SymbolIntervals};
+use public::base_model::api_model::{MarketData, MarketDataType};
+use public::base_model::market_model::kline_model::Kline;
+use public::base_model::market_model::order_book_model::{DepthUpdate, OrderBook, OrderBookUpdate};
+use public::tools::api_tools;
 use tokio::sync::broadcast::Sender;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
 use tokio_tungstenite::tungstenite::Message;
 use tracing::{error, info};
 
+const MAX_BUFFERED_DEPTH_UPDATES: usize = 1000;
+
+/// Local order book of a symbol. Diff events arriving while it is out of sync are
+/// buffered and replayed on the next REST snapshot, failed snapshots are retried
+/// with backoff.
+struct BookSync {
+    order_book: OrderBook,
+    buffer: VecDeque<DepthUpdate>,
+    syncing: bool,
+    fetching: bool,
+    retry_at: Option<Instant>,
+    backoff: ReconnectBackoff,
+}
+
+impl BookSync {
+    fn new() -> Self {
+        Self {
+            order_book: OrderBook::default(),
+            buffer: VecDeque::new(),
+            syncing: true,
+            fetching: false,
+            retry_at: None,
+            backoff: ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(60)),
+        }
+    }
+
+    /// Applies the diff event, or buffers it while the book is out of sync. Returns
+    /// whether the book changed.
+    fn apply_update(&mut self, update: DepthUpdate) -> bool {
+        if !self.syncing {
+            match self.order_book.apply_update(&update) {
+                OrderBookUpdate::Applied => return true,
+                OrderBookUpdate::Skipped => return false,
+                OrderBookUpdate::OutOfSync => self.syncing = true,
+            }
+        }
+        if self.buffer.len() >= MAX_BUFFERED_DEPTH_UPDATES {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(update);
+        false
+    }
+
+    fn needs_snapshot(&self, now: Instant) -> bool {
+        self.syncing && !self.fetching && self.retry_at.is_none_or(|t| now >= t)
+    }
+
+    /// Replays the buffered events on the snapshot. Returns whether the book is in
+    /// sync, otherwise the next snapshot is due after the backoff delay.
+    fn apply_snapshot(&mut self, snapshot: Option<OrderBook>, now: Instant) -> bool {
+        self.fetching = false;
+        if let Some(snapshot) = snapshot {
+            self.order_book = snapshot;
+            while let Some(update) = self.buffer.front() {
+                // a snapshot older than the buffered events needs a newer one
+                if self.order_book.apply_update(update) == OrderBookUpdate::OutOfSync {
+                    break;
+                }
+                self.buffer.pop_front();
+            }
+            if self.buffer.is_empty() {
+                self.syncing = false;
+                self.retry_at = None;
+                self.backoff.reset();
+                return true;
+            }
+        }
+        self.retry_at = Some(now + self.backoff.next_delay());
+        false
+    }
+}
+
+/// Streams subscribed for every symbol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarketStream {
+    /// Closed klines of the symbol base interval.
+    Kline,
+    /// Local order book, sent as `Depth`.
+    Depth,
+    Trade,
+    AggTrade,
+    BookTicker,
+    MarkPrice,
+    ForceOrder,
+}
+
+/// Runtime subscription change sent to the streaming task.
 #[derive(Debug, Clone)]
+enum SubscribeCommand {
+    Subscribe(Vec<(String, Interval)>),
+    Unsubscribe(Vec<String>),
+}
+
+#[derive(Clone)]
 pub struct WsDataEngine {
     url: String,
     symbols: Vec<String>,
+    symbol_intervals: SymbolIntervals,
     // mongo_engine: MongoEngine,
     kline_records: HashMap<String, Vec<Kline>>,
+    rest_data_engine: RestDataEngine,
+    stale_timeout: Duration,
+    max_connection_time: Duration,
+    depth_levels: usize,
+    streams: Vec<MarketStream>,
+    command_sender: Option<UnboundedSender<SubscribeCommand>>,
 }
 
 impl Default for WsDataEngine {
@@ -21,46 +128,131 @@ impl Default for WsDataEngine {
         Self {
             url: "wss://fstream.binance.com".to_owned(),
             symbols: vec![],
+            symbol_intervals: SymbolIntervals::default(),
             // mongo_engine: MongoEngine::default(),
             kline_records: HashMap::new(),
+            rest_data_engine: RestDataEngine::default(),
+            stale_timeout: Duration::from_secs(60),
+            // binance closes connections after 24 hours
+            max_connection_time: Duration::from_secs(23 * 3600),
+            depth_levels: 20,
+            streams: vec![MarketStream::Kline, MarketStream::Depth],
+            command_sender: None,
         }
     }
 }
 
 impl WsDataEngine {
+    /// Streams market data into `tx`, reconnecting with backoff when the connection
+    /// errors, closes or goes silent for `stale_timeout`. Klines missed while
+    /// disconnected are backfilled over REST before the stream resumes.
     pub async fn start_watch_send(&mut self, tx: Sender<MarketData>) {
         info!("WsDataEngine Start...");
-        let ws_url = self.get_topic_url().clone();
+        let (command_sender, command_receiver) = unbounded_channel();
+        self.command_sender = Some(command_sender);
+        let mut engine = self.clone();
         tokio::spawn(async move {
-            match tokio_tungstenite::connect_async(ws_url).await {
+            engine.watch_send(tx, command_receiver).await;
+        });
+    }
+
+    async fn watch_send(
+        &mut self,
+        tx: Sender<MarketData>,
+        mut command_receiver: UnboundedReceiver<SubscribeCommand>,
+    ) {
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        // open time of the last kline sent per symbol
+        let mut last_klines: HashMap<String, i64> = HashMap::new();
+        let mut request_id = 0;
+        let mut order_books: HashMap<String, BookSync> = HashMap::new();
+        let (snapshot_sender, mut snapshot_receiver) = unbounded_channel();
+        loop {
+            match tokio_tungstenite::connect_async(&self.get_topic_url()).await {
                 Ok((ws_stream, _)) => {
+                    info!("WsDataEngine Connected");
+                    // diff events sent while disconnected are lost
+                    order_books.clear();
+                    self.backfill_klines(&tx, &mut last_klines).await;
+                    let connected_at = Instant::now();
                     let (mut write, mut read) = ws_stream.split();
-                    while let Some(msg) = read.next().await {
-                        match msg {
-                            Ok(msg) => match msg {
-                                Message::Ping(ping) => {
-                                    match write.send(Message::Pong(ping)).await {
-                                        Ok(_) => {}
-                                        Err(e) => {
-                                            error!("WsDataEngine Ping Error: {}", e);
+                    loop {
+                        if connected_at.elapsed() >= self.max_connection_time {
+                            info!("WsDataEngine reconnect before the 24h disconnect");
+                            break;
+                        }
+                        tokio::select! {
+                            msg = read.next() => match msg {
+                                Some(Ok(msg)) => {
+                                    backoff.reset();
+                                    match msg {
+                                        Message::Ping(ping) => {
+                                            match write.send(Message::Pong(ping)).await {
+                                                Ok(_) => {}
+                                                Err(e) => {
+                                                    error!("WsDataEngine Ping Error: {}", e);
+                                                }
+                                            }
                                         }
-                                    }
-                                }
-                                Message::Text(data) => {
-                                    if let Some(market_data) = api_tools::parse_market_data(&data)
-                                    {
-                                        match tx.send(market_data) {
-                                            Ok(_) => {}
-                                            Err(e) => {
-                                                error!("WsDataEngine Send Error: {}", e);
+                                        Message::Text(data) => {
+                                            if let Some((symbol, update)) =
+                                                api_tools::parse_depth_update(&data)
+                                            {
+                                                self.update_order_book(
+                                                    &tx,
+                                                    &mut order_books,
+                                                    &snapshot_sender,
+                                                    symbol,
+                                                    update,
+                                                );
+                                            } else if let Some(market_data) =
+                                                api_tools::parse_market_data(&data)
+                                            {
+                                                Self::send_market_data(
+                                                    &tx,
+                                                    market_data,
+                                                    &mut last_klines,
+                                                );
                                             }
                                         }
+                                        Message::Close(frame) => {
+                                            info!("WsDataEngine Closed: {:?}", frame);
+                                            break;
+                                        }
+                                        _ => {}
                                     }
                                 }
-                                _ => {}
+                                Some(Err(e)) => {
+                                    error!("WsDataEngine ReadMsg Error: {}", e);
+                                    break;
+                                }
+                                None => {
+                                    error!("WsDataEngine stream ended");
+                                    break;
+                                }
                             },
-                            Err(e) => {
-                                error!("WsDataEngine ReadMsg Error: {}", e);
+                            Some((symbol, snapshot)) = snapshot_receiver.recv() => {
+                                self.apply_depth_snapshot(&tx, &mut order_books, symbol, snapshot);
+                            }
+                            Some(command) = command_receiver.recv() => {
+                                request_id += 1;
+                                let message = self.apply_command(command, request_id);
+                                if let Some(topics) = message {
+                                    info!("WsDataEngine {}", topics);
+                                    if let Err(e) = write.send(Message::Text(topics)).await {
+                                        error!("WsDataEngine Subscribe Error: {}", e);
+                                        break;
+                                    }
+                                }
+                                last_klines.retain(|symbol, _| self.symbols.contains(symbol));
+                                order_books.retain(|symbol, _| self.symbols.contains(symbol));
+                            }
+                            _ = tokio::time::sleep(self.stale_timeout), if !self.symbols.is_empty() => {
+                                error!(
+                                    "WsDataEngine no data for {:?}, reconnecting",
+                                    self.stale_timeout
+                                );
+                                break;
                             }
                         }
                     }
@@ -69,38 +261,380 @@ impl WsDataEngine {
                     error!("WsDataEngine Connect Error: {}", e);
                 }
             }
-        });
+            let delay = backoff.next_delay();
+            info!("WsDataEngine reconnect in {:?}", delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Updates the streamed symbols and returns the SUBSCRIBE/UNSUBSCRIBE request for
+    /// the topics that changed.
+    fn apply_command(&mut self, command: SubscribeCommand, request_id: i64) -> Option<String> {
+        let (method, topics) = match command {
+            SubscribeCommand::Subscribe(symbols) => {
+                let mut topics = Vec::new();
+                for (symbol, interval) in symbols {
+                    if self.symbols.contains(&symbol) {
+                        continue;
+                    }
+                    self.set_symbol_interval(&symbol, interval);
+                    topics.extend(self.get_topics(&symbol));
+                    self.symbols.push(symbol);
+                }
+                ("SUBSCRIBE", topics)
+            }
+            SubscribeCommand::Unsubscribe(symbols) => {
+                let mut topics = Vec::new();
+                for symbol in symbols {
+                    if !self.symbols.contains(&symbol) {
+                        continue;
+                    }
+                    topics.extend(self.get_topics(&symbol));
+                    self.symbols.retain(|s| *s != symbol);
+                }
+                ("UNSUBSCRIBE", topics)
+            }
+        };
+        if topics.is_empty() {
+            return None;
+        }
+        Some(
+            serde_json::json!({
+                "method": method,
+                "params": topics,
+                "id": request_id,
+            })
+            .to_string(),
+        )
+    }
+
+    /// Applies a diff depth event and sends the best `depth_levels` levels once
+    /// updated. An out of sync book fetches a REST snapshot in the background.
+    fn update_order_book(
+        &self,
+        tx: &Sender<MarketData>,
+        order_books: &mut HashMap<String, BookSync>,
+        snapshot_sender: &UnboundedSender<(String, Option<OrderBook>)>,
+        symbol: String,
+        update: DepthUpdate,
+    ) {
+        let book = order_books
+            .entry(symbol.clone())
+            .or_insert_with(BookSync::new);
+        if book.apply_update(update) {
+            self.send_depth(tx, symbol.clone(), &book.order_book);
+        }
+        if book.needs_snapshot(Instant::now()) {
+            book.fetching = true;
+            let rest_data_engine = self.rest_data_engine.clone();
+            let snapshot_sender = snapshot_sender.clone();
+            tokio::spawn(async move {
+                let snapshot = rest_data_engine.fetch_depth_snapshot(&symbol).await;
+                let _ = snapshot_sender.send((symbol, snapshot));
+            });
+        }
     }
 
-    fn get_kline_topic(symbol: String) -> String {
-        format!("{}@kline_5m", symbol)
+    /// Syncs the book from a fetched snapshot, dropped when the symbol was removed or
+    /// the connection reset in the meantime.
+    fn apply_depth_snapshot(
+        &self,
+        tx: &Sender<MarketData>,
+        order_books: &mut HashMap<String, BookSync>,
+        symbol: String,
+        snapshot: Option<OrderBook>,
+    ) {
+        let Some(book) = order_books.get_mut(&symbol) else {
+            return;
+        };
+        if !book.fetching {
+            return;
+        }
+        match &snapshot {
+            Some(snapshot) => info!(
+                "WsDataEngine sync {} order book at {:?}",
+                symbol,
+                snapshot.get_last_update_id()
+            ),
+            None => error!("WsDataEngine failed to fetch {} depth snapshot", symbol),
+        }
+        if book.apply_snapshot(snapshot, Instant::now()) {
+            self.send_depth(tx, symbol, &book.order_book);
+        }
     }
 
-    fn get_depth_topic(symbol: String) -> String {
-        format!("{}@depth5", symbol)
+    fn send_depth(&self, tx: &Sender<MarketData>, symbol: String, order_book: &OrderBook) {
+        let depth = order_book.get_depth(self.depth_levels);
+        match tx.send(MarketData::new(symbol, MarketDataType::Depth(depth))) {
+            Ok(_) => {}
+            Err(e) => {
+                error!("WsDataEngine Send Error: {}", e);
+            }
+        }
+    }
+
+    /// Klines already sent, e.g. by a backfill, are dropped.
+    fn send_market_data(
+        tx: &Sender<MarketData>,
+        market_data: MarketData,
+        last_klines: &mut HashMap<String, i64>,
+    ) {
+        if let Some(kline) = market_data.get_kline() {
+            let symbol = market_data.get_symbol();
+            if let Some(last_open_time) = last_klines.get(symbol) {
+                if kline.get_open_time() <= *last_open_time {
+                    return;
+                }
+            }
+            last_klines.insert(symbol.clone(), kline.get_open_time());
+        }
+        match tx.send(market_data) {
+            Ok(_) => {}
+            Err(e) => {
+                error!("WsDataEngine Send Error: {}", e);
+            }
+        }
+    }
+
+    /// Sends the closed klines after the last one each symbol received.
+    async fn backfill_klines(
+        &self,
+        tx: &Sender<MarketData>,
+        last_klines: &mut HashMap<String, i64>,
+    ) {
+        let symbols: Vec<(String, i64)> = last_klines
+            .iter()
+            .map(|(symbol, open_time)| (symbol.clone(), *open_time))
+            .collect();
+        let now = chrono::Utc::now().timestamp_millis();
+        for (symbol, last_open_time) in symbols {
+            let mut start_time = last_open_time + 1;
+            let mut count = 0;
+            loop {
+                match self
+                    .rest_data_engine
+                    .fetch_single_batch_his_kline(&symbol, start_time)
+                    .await
+                {
+                    Some(klines) => {
+                        let closed_klines: Vec<Kline> = klines
+                            .into_iter()
+                            .filter(|k| k.get_close_time() < now)
+                            .collect();
+                        if closed_klines.is_empty() {
+                            break;
+                        }
+                        start_time = closed_klines.last().unwrap().get_open_time() + 1;
+                        count += closed_klines.len();
+                        for kline in closed_klines {
+                            let market_data =
+                                MarketData::new(symbol.clone(), MarketDataType::Kline(kline));
+                            Self::send_market_data(tx, market_data, last_klines);
+                        }
+                    }
+                    None => {
+                        error!("WsDataEngine failed to backfill {} klines", symbol);
+                        break;
+                    }
+                }
+            }
+            if count > 0 {
+                info!("WsDataEngine backfilled {} {} klines", count, symbol);
+            }
+        }
+    }
+
+    fn get_kline_topic(symbol: String, interval: &Interval) -> String {
+        format!("{}@kline_{}", symbol, interval.get_interval_string())
     }
 
     pub fn subscribe_symbols(&mut self, symbols: &Vec<String>) {
         self.symbols = symbols.to_vec();
+        self.rest_data_engine.subscribe_symbols(symbols);
         for s in self.symbols.iter() {
             self.kline_records.insert(s.clone(), vec![]);
         }
     }
 
+    /// Base kline interval streamed for the symbol, 5m unless set.
+    pub fn set_symbol_interval(&mut self, symbol: &str, interval: Interval) {
+        self.symbol_intervals.set_interval(symbol, interval);
+        self.rest_data_engine.set_symbol_interval(symbol, interval);
+    }
+
+    pub fn get_symbol_interval(&self, symbol: &str) -> Interval {
+        self.symbol_intervals.get_interval(symbol)
+    }
+
+    /// Request weight limit of the exchange info, throttles the REST backfills.
+    pub fn set_request_weight_limit(&mut self, request_weight_limit: i64) {
+        self.rest_data_engine
+            .set_request_weight_limit(request_weight_limit);
+    }
+
+    /// Levels per side of the order book sent as `Depth`.
+    pub fn set_depth_levels(&mut self, depth_levels: usize) {
+        self.depth_levels = depth_levels;
+    }
+
+    /// A connection silent for longer than `stale_timeout` is reconnected.
+    pub fn set_stale_timeout(&mut self, stale_timeout: Duration) {
+        self.stale_timeout = stale_timeout;
+    }
+
+    /// Adds symbols to a running stream, or to the initial ones before `start_watch_send`.
+    pub fn add_symbols(&mut self, symbols: &[String]) {
+        let mut new_symbols = Vec::new();
+        for symbol in symbols {
+            if !self.symbols.contains(symbol) {
+                self.symbols.push(symbol.clone());
+                new_symbols.push((symbol.clone(), self.get_symbol_interval(symbol)));
+            }
+        }
+        self.send_command(SubscribeCommand::Subscribe(new_symbols));
+    }
+
+    pub fn remove_symbols(&mut self, symbols: &[String]) {
+        self.symbols.retain(|s| !symbols.contains(s));
+        self.send_command(SubscribeCommand::Unsubscribe(symbols.to_vec()));
+    }
+
+    fn send_command(&self, command: SubscribeCommand) {
+        if let Some(command_sender) = &self.command_sender {
+            if let Err(e) = command_sender.send(command) {
+                error!("WsDataEngine Command Error: {}", e);
+            }
+        }
+    }
+
+    /// Klines and order books by default. Set before `start_watch_send`.
+    pub fn set_streams(&mut self, streams: Vec<MarketStream>) {
+        self.streams = streams;
+    }
+
+    fn get_topics(&self, symbol: &str) -> Vec<String> {
+        self.streams
+            .iter()
+            .map(|stream| match stream {
+                MarketStream::Kline => {
+                    Self::get_kline_topic(symbol.to_string(), &self.get_symbol_interval(symbol))
+                }
+                MarketStream::Depth => api_tools::get_depth_update_topic(symbol),
+                MarketStream::Trade => format!("{}@trade", symbol),
+                MarketStream::AggTrade => format!("{}@aggTrade", symbol),
+                MarketStream::BookTicker => format!("{}@bookTicker", symbol),
+                MarketStream::MarkPrice => format!("{}@markPrice@1s", symbol),
+                MarketStream::ForceOrder => format!("{}@forceOrder", symbol),
+            })
+            .collect()
+    }
+
     fn get_topic_url(&self) -> String {
+        if self.symbols.is_empty() {
+            return format!("{}/stream", self.url);
+        }
         let topic = self
             .symbols
             .iter()
-            .map(|x| {
-                format!(
-                    "{}/{}",
-                    Self::get_kline_topic(x.clone()),
-                    Self::get_depth_topic(x.clone())
-                )
-            })
+            .map(|x| self.get_topics(x).join("/"))
             .collect::<Vec<String>>()
             .join("/");
         let full_topic = format!("{}/stream?streams={}", self.url, topic);
         full_topic
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use public::base_model::market_model::depth_model::PriceLevel;
+
+    fn depth_update(first_update_id: i64, final_update_id: i64, bid: f64) -> DepthUpdate {
+        DepthUpdate::new(
+            0,
+            first_update_id,
+            final_update_id,
+            first_update_id - 1,
+            vec![PriceLevel::new(bid, 1.0)],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_book_sync() {
+        let now = Instant::now();
+        let mut book = BookSync::new();
+        assert!(!book.apply_update(depth_update(10, 12, 100.0)));
+        assert!(book.needs_snapshot(now));
+        book.fetching = true;
+        assert!(!book.needs_snapshot(now));
+        assert!(!book.apply_update(depth_update(13, 15, 101.0)));
+
+        // a failed snapshot is retried after the backoff delay
+        assert!(!book.apply_snapshot(None, now));
+        assert!(!book.needs_snapshot(now));
+        assert!(book.needs_snapshot(now + Duration::from_secs(1)));
+
+        // buffered events older than the snapshot are skipped, the rest replayed
+        let mut snapshot = OrderBook::default();
+        snapshot.apply_snapshot(11, &[PriceLevel::new(99.0, 1.0)], &[]);
+        book.fetching = true;
+        assert!(book.apply_snapshot(Some(snapshot), now));
+        assert_eq!(book.order_book.get_last_update_id(), Some(15));
+        assert_eq!(book.order_book.get_best_bid().unwrap().get_price(), 101.0);
+        assert!(book.buffer.is_empty());
+        assert!(book.apply_update(depth_update(16, 17, 102.0)));
+
+        // a gap resyncs from a snapshot again
+        assert!(!book.apply_update(depth_update(20, 21, 103.0)));
+        assert!(book.needs_snapshot(now));
+    }
+
+    #[test]
+    fn test_apply_command() {
+        let mut engine = WsDataEngine::default();
+        engine.subscribe_symbols(&vec!["btcusdt".to_string()]);
+        let message = engine
+            .apply_command(
+                SubscribeCommand::Subscribe(vec![
+                    ("btcusdt".to_string(), Interval::Min5),
+                    ("ethusdt".to_string(), Interval::Min1),
+                ]),
+                1,
+            )
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&message).unwrap(),
+            serde_json::json!({
+                "method": "SUBSCRIBE",
+                "params": ["ethusdt@kline_1m", "ethusdt@depth@100ms"],
+                "id": 1,
+            })
+        );
+        assert_eq!(
+            engine.get_topic_url(),
+            "wss://fstream.binance.com/stream?streams=btcusdt@kline_5m/btcusdt@depth@100ms/ethusdt@kline_1m/ethusdt@depth@100ms"
+        );
+
+        let message = engine
+            .apply_command(
+                SubscribeCommand::Unsubscribe(vec!["btcusdt".to_string()]),
+                2,
+            )
+            .unwrap();
+        assert!(message.contains(r#""UNSUBSCRIBE""#));
+
+        engine.set_streams(vec![MarketStream::AggTrade, MarketStream::MarkPrice]);
+        assert_eq!(
+            engine.get_topics("ethusdt"),
+            vec!["ethusdt@aggTrade", "ethusdt@markPrice@1s"]
+        );
+        assert_eq!(engine.symbols, vec!["ethusdt".to_string()]);
+        assert!(engine
+            .apply_command(
+                SubscribeCommand::Unsubscribe(vec!["btcusdt".to_string()]),
+                3
+            )
+            .is_none());
+    }
+}
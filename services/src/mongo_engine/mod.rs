@@ -1,11 +1,21 @@
 use futures_util::stream::TryStreamExt;
 // use futures::TryStreamExt;
-use mongodb::{bson::doc, error::Error, Client, Collection};
+use mongodb::{
+    bson::{doc, Document},
+    error::Error,
+    Client, Collection,
+};
+use public::base_enum::market_enums::{Interval, PriceType};
 use public::base_model::info_model::ExchangeInfo;
+use public::base_model::market_model::funding_rate_model::FundingRate;
 use public::base_model::market_model::kline_model::Kline;
+use public::base_model::market_model::open_interest_model::OpenInterest;
 use public::base_model::trade_model::order_model::Order;
 use public::base_model::trade_model::position_model::Position;
+use public::strategy_model::margin_model::MarginBrackets;
 use public::strategy_model::strategy_portfolio::Balance;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
 
 #[derive(Clone)]
 pub struct MongoEngine {
@@ -72,21 +82,35 @@ impl MongoEngine {
         }
     }
 
-    pub async fn insert_kline(&self, symbol: &str, kline: &Vec<Kline>) -> Result<(), Error> {
-        if kline.len() == 0 {
+    /// 5m klines keep the bare symbol collection, other base intervals are suffixed.
+    pub fn get_kline_collection(symbol: &str, interval: &Interval) -> String {
+        match interval {
+            Interval::Min5 => symbol.to_string(),
+            _ => format!("{}_{}", symbol, interval.get_interval_string()),
+        }
+    }
+
+    pub async fn insert_kline(
+        &self,
+        symbol: &str,
+        interval: &Interval,
+        kline: &[Kline],
+    ) -> Result<(), Error> {
+        if kline.is_empty() {
             return Ok(());
         }
         match self.get_client().await {
             Ok(client) => {
                 let db = client.database("klines");
-                let collection: Collection<Kline> = db.collection(symbol);
+                let collection: Collection<Kline> =
+                    db.collection(&Self::get_kline_collection(symbol, interval));
                 if kline.len() == 1 {
-                    match collection.insert_one(kline[0].clone()).await {
+                    match collection.insert_one(kline[0]).await {
                         Ok(_) => Ok(()),
                         Err(e) => Err(e),
                     }
                 } else {
-                    match collection.insert_many(kline.clone()).await {
+                    match collection.insert_many(kline).await {
                         Ok(_) => Ok(()),
                         Err(e) => Err(e),
                     }
@@ -99,14 +123,16 @@ impl MongoEngine {
     pub async fn fetch_klines(
         &self,
         symbol: &str,
+        interval: &Interval,
         start_date: i64,
     ) -> Result<Option<Vec<Kline>>, Error> {
         match self.get_client().await {
             Ok(client) => {
                 let db = client.database("klines");
                 let collections = db.list_collection_names().await?;
-                if collections.contains(&symbol.to_string()) {
-                    let collection: Collection<Kline> = db.collection(symbol);
+                let collection_name = Self::get_kline_collection(symbol, interval);
+                if collections.contains(&collection_name) {
+                    let collection: Collection<Kline> = db.collection(&collection_name);
                     let filter = doc! { "open_time": { "$gte": start_date } };
                     match collection.find(filter).sort(doc! {"open_time": 1}).await {
                         Ok(cursor) => match cursor.try_collect().await {
@@ -126,13 +152,18 @@ impl MongoEngine {
         }
     }
 
-    pub async fn fetch_latest_kline(&self, symbol: &str) -> Result<Option<Kline>, Error> {
+    pub async fn fetch_latest_kline(
+        &self,
+        symbol: &str,
+        interval: &Interval,
+    ) -> Result<Option<Kline>, Error> {
         match self.get_client().await {
             Ok(client) => {
                 let db = client.database("klines");
                 let collections = db.list_collection_names().await?;
-                if collections.contains(&symbol.to_string()) {
-                    let collection: Collection<Kline> = db.collection(symbol);
+                let collection_name = Self::get_kline_collection(symbol, interval);
+                if collections.contains(&collection_name) {
+                    let collection: Collection<Kline> = db.collection(&collection_name);
                     match collection
                         .find_one(doc! {})
                         .sort(doc! {"open_time": -1})
@@ -152,6 +183,196 @@ impl MongoEngine {
         }
     }
 
+    fn get_kline_database(price_type: PriceType) -> &'static str {
+        match price_type {
+            PriceType::Last => "klines",
+            PriceType::Mark => "mark_price_klines",
+            PriceType::Index => "index_price_klines",
+        }
+    }
+
+    async fn insert_records<T>(
+        &self,
+        database: &str,
+        symbol: &str,
+        records: &[T],
+    ) -> Result<(), Error>
+    where
+        T: Serialize + Send + Sync,
+    {
+        if records.is_empty() {
+            return Ok(());
+        }
+        match self.get_client().await {
+            Ok(client) => {
+                let collection: Collection<T> = client.database(database).collection(symbol);
+                match collection.insert_many(records).await {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Records with `time_field >= start_time` in ascending order, `None` if the symbol
+    /// has no collection yet.
+    async fn fetch_records<T>(
+        &self,
+        database: &str,
+        symbol: &str,
+        time_field: &str,
+        start_time: i64,
+    ) -> Result<Option<Vec<T>>, Error>
+    where
+        T: DeserializeOwned + Send + Sync,
+    {
+        match self.get_client().await {
+            Ok(client) => {
+                let db = client.database(database);
+                let collections = db.list_collection_names().await?;
+                if collections.contains(&symbol.to_string()) {
+                    let collection: Collection<T> = db.collection(symbol);
+                    let filter = doc! { time_field: { "$gte": start_time } };
+                    match collection.find(filter).sort(doc! {time_field: 1}).await {
+                        Ok(cursor) => match cursor.try_collect().await {
+                            Ok(res) => Ok(Some(res)),
+                            Err(e) => Err(e),
+                        },
+                        Err(e) => Err(e),
+                    }
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn fetch_latest_record<T>(
+        &self,
+        database: &str,
+        symbol: &str,
+        time_field: &str,
+    ) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned + Send + Sync,
+    {
+        match self.get_client().await {
+            Ok(client) => {
+                let db = client.database(database);
+                let collections = db.list_collection_names().await?;
+                if collections.contains(&symbol.to_string()) {
+                    let collection: Collection<T> = db.collection(symbol);
+                    collection
+                        .find_one(doc! {})
+                        .sort(doc! {time_field: -1})
+                        .await
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn insert_price_klines(
+        &self,
+        price_type: PriceType,
+        symbol: &str,
+        interval: &Interval,
+        klines: &[Kline],
+    ) -> Result<(), Error> {
+        self.insert_records(
+            Self::get_kline_database(price_type),
+            &Self::get_kline_collection(symbol, interval),
+            klines,
+        )
+        .await
+    }
+
+    pub async fn fetch_price_klines(
+        &self,
+        price_type: PriceType,
+        symbol: &str,
+        interval: &Interval,
+        start_date: i64,
+    ) -> Result<Option<Vec<Kline>>, Error> {
+        self.fetch_records(
+            Self::get_kline_database(price_type),
+            &Self::get_kline_collection(symbol, interval),
+            "open_time",
+            start_date,
+        )
+        .await
+    }
+
+    pub async fn fetch_latest_price_kline(
+        &self,
+        price_type: PriceType,
+        symbol: &str,
+        interval: &Interval,
+    ) -> Result<Option<Kline>, Error> {
+        self.fetch_latest_record(
+            Self::get_kline_database(price_type),
+            &Self::get_kline_collection(symbol, interval),
+            "open_time",
+        )
+        .await
+    }
+
+    pub async fn insert_funding_rates(
+        &self,
+        symbol: &str,
+        funding_rates: &[FundingRate],
+    ) -> Result<(), Error> {
+        self.insert_records("funding_rates", symbol, funding_rates)
+            .await
+    }
+
+    pub async fn fetch_funding_rates(
+        &self,
+        symbol: &str,
+        start_date: i64,
+    ) -> Result<Option<Vec<FundingRate>>, Error> {
+        self.fetch_records("funding_rates", symbol, "funding_time", start_date)
+            .await
+    }
+
+    pub async fn fetch_latest_funding_rate(
+        &self,
+        symbol: &str,
+    ) -> Result<Option<FundingRate>, Error> {
+        self.fetch_latest_record("funding_rates", symbol, "funding_time")
+            .await
+    }
+
+    pub async fn insert_open_interest(
+        &self,
+        symbol: &str,
+        open_interest: &[OpenInterest],
+    ) -> Result<(), Error> {
+        self.insert_records("open_interest", symbol, open_interest)
+            .await
+    }
+
+    pub async fn fetch_open_interest(
+        &self,
+        symbol: &str,
+        start_date: i64,
+    ) -> Result<Option<Vec<OpenInterest>>, Error> {
+        self.fetch_records("open_interest", symbol, "timestamp", start_date)
+            .await
+    }
+
+    pub async fn fetch_latest_open_interest(
+        &self,
+        symbol: &str,
+    ) -> Result<Option<OpenInterest>, Error> {
+        self.fetch_latest_record("open_interest", symbol, "timestamp")
+            .await
+    }
+
     pub async fn insert_order(&self, order: &Order) -> Result<(), Error> {
         match self.get_client().await {
             Ok(client) => {
@@ -188,6 +409,111 @@ impl MongoEngine {
         }
     }
 
+    /// Replaces the maintenance margin brackets of a symbol.
+    pub async fn update_margin_brackets(
+        &self,
+        symbol: &str,
+        margin_brackets: &MarginBrackets,
+    ) -> Result<(), Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<MarginBrackets> =
+            client.database("margin_brackets").collection(symbol);
+        collection
+            .replace_one(doc! {}, margin_brackets)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_margin_brackets(
+        &self,
+        symbol: &str,
+    ) -> Result<Option<MarginBrackets>, Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<MarginBrackets> =
+            client.database("margin_brackets").collection(symbol);
+        collection.find_one(doc! {}).await
+    }
+
+    /// Realized pnl of the strategy on `day`, counted in UTC days since the epoch.
+    pub async fn update_daily_pnl(&self, day: i64, strategy: &str, pnl: f64) -> Result<(), Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<Document> = client.database("risk").collection("daily_pnl");
+        collection
+            .replace_one(
+                doc! {"day": day, "strategy": strategy},
+                doc! {"day": day, "strategy": strategy, "pnl": pnl},
+            )
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    /// Realized pnl per strategy on `day`, see `update_daily_pnl`.
+    pub async fn fetch_daily_pnl(&self, day: i64) -> Result<HashMap<String, f64>, Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<Document> = client.database("risk").collection("daily_pnl");
+        let docs: Vec<Document> = collection
+            .find(doc! {"day": day})
+            .await?
+            .try_collect()
+            .await?;
+        let mut daily_pnl = HashMap::new();
+        for d in docs {
+            if let (Ok(strategy), Ok(pnl)) = (d.get_str("strategy"), d.get_f64("pnl")) {
+                daily_pnl.insert(strategy.to_string(), pnl);
+            }
+        }
+        Ok(daily_pnl)
+    }
+
+    /// Stores or updates an order that is still open on the exchange, keyed by cid.
+    pub async fn upsert_open_order(&self, order: &Order) -> Result<(), Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<Order> = client.database("orders").collection("open_orders");
+        collection
+            .replace_one(doc! {"cid": order.get_cid()}, order)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_open_order(&self, cid: &str) -> Result<(), Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<Order> = client.database("orders").collection("open_orders");
+        collection.delete_one(doc! {"cid": cid}).await?;
+        Ok(())
+    }
+
+    /// Orders last seen open, see `upsert_open_order`.
+    pub async fn fetch_open_orders(&self) -> Result<Vec<Order>, Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<Order> = client.database("orders").collection("open_orders");
+        collection.find(doc! {}).await?.try_collect().await
+    }
+
+    /// Updates the stored positions per symbol, closed positions are removed.
+    pub async fn upsert_positions(&self, positions: &[Position]) -> Result<(), Error> {
+        let client = self.get_client().await?;
+        let collection: Collection<Position> = client.database("balance").collection("positions");
+        for position in positions {
+            // the stream and REST resync store symbols in different cases
+            let filter = doc! {
+                "symbol": {"$regex": format!("^{}$", position.get_symbol()), "$options": "i"}
+            };
+            if position.get_quantity() == 0.0 {
+                collection.delete_many(filter).await?;
+            } else {
+                collection
+                    .replace_one(filter, position)
+                    .upsert(true)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the stored positions, an empty list clears them.
     pub async fn update_positions(&self, positions: &Vec<Position>) -> Result<(), Error> {
         match self.get_client().await {
             Ok(client) => {
@@ -196,12 +522,15 @@ impl MongoEngine {
                 let collection: Collection<Position> = db.collection("positions");
                 if collections.contains(&"positions".to_string()) {
                     match collection.delete_many(doc! {}).await {
+                        Ok(_) if positions.is_empty() => Ok(()),
                         Ok(_) => match collection.insert_many(positions.clone()).await {
                             Ok(_) => Ok(()),
                             Err(e) => Err(e),
                         },
                         Err(e) => Err(e),
                     }
+                } else if positions.is_empty() {
+                    Ok(())
                 } else {
                     match collection.insert_many(positions.clone()).await {
                         Ok(_) => Ok(()),
@@ -213,6 +542,20 @@ impl MongoEngine {
         }
     }
 
+    pub async fn fetch_positions(&self) -> Result<Vec<Position>, Error> {
+        match self.get_client().await {
+            Ok(client) => {
+                let db = client.database("balance");
+                let collection: Collection<Position> = db.collection("positions");
+                match collection.find(doc! {}).await {
+                    Ok(cursor) => cursor.try_collect().await,
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     pub async fn get_position(&self, symbol: &str) -> Result<Option<Position>, Error> {
         match self.get_client().await {
             Ok(client) => {
@@ -276,7 +619,7 @@ mod tests {
     #[tokio::test]
     async fn test_fetch_latest_kline() {
         let client = MongoEngine::default();
-        match client.fetch_latest_kline("btcusdt").await {
+        match client.fetch_latest_kline("btcusdt", &Interval::Min5).await {
             Ok(res) => {
                 if let Some(k) = res {
                     println!("{:?}", k);
@@ -287,4 +630,44 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_fetch_klines() {
+        use public::tools::kline_tools;
+
+        let client = MongoEngine::default();
+        let start_date = 1712419200000;
+        let klines = client
+            .fetch_klines("btcusdt", &Interval::Min5, start_date)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!klines.is_empty());
+        assert!(klines[0].get_open_time() >= start_date);
+        for pair in klines.windows(2) {
+            assert!(pair[0].get_open_time() < pair[1].get_open_time());
+        }
+
+        let res =
+            kline_tools::resample_kline_data(klines.clone(), &Interval::Min5, &Interval::Hour4);
+        assert!(!res.is_empty());
+        for kline in &res {
+            assert_eq!(kline.get_open_time() % Interval::Hour4.get_millis(), 0);
+            assert_eq!(
+                kline.get_close_time(),
+                kline.get_open_time() + Interval::Hour4.get_millis() - 1
+            );
+        }
+        // the first 4h bar combines its 48 5m klines once each
+        let first = &res[0];
+        let volume: f64 = klines
+            .iter()
+            .filter(|k| {
+                k.get_open_time() >= first.get_open_time()
+                    && k.get_open_time() <= first.get_close_time()
+            })
+            .map(|k| k.get_volume())
+            .sum();
+        assert!((first.get_volume() - volume).abs() < 1e-6 * volume.max(1.0));
+    }
 }
@@ -1,21 +1,51 @@
 pub mod order_client;
-mod order_listener;
-mod order_services;
+pub mod order_listener;
+pub mod order_services;
+pub mod risk_manager;
+pub mod simulated_order_services;
+
+use order_listener::AccountUpdate;
+use public::base_model::trade_model::order_model::Order;
+use tokio::sync::broadcast::{self, Receiver, Sender};
 
 pub struct OrderManager {
     order_listener: order_listener::OrderListener,
     order_services: order_services::GeneralOrderService,
+    order_sender: Sender<Order>,
+    account_sender: Sender<AccountUpdate>,
 }
 
 impl OrderManager {
     pub fn new() -> Self {
+        let (order_sender, _) = broadcast::channel::<Order>(1024);
+        let (account_sender, _) = broadcast::channel::<AccountUpdate>(1024);
         Self {
             order_listener: order_listener::OrderListener::default(),
             order_services: order_services::GeneralOrderService::default(),
+            order_sender,
+            account_sender,
         }
     }
 
+    /// Order updates of the user data stream, e.g. for a live StrategyEngine.
+    pub fn subscribe_order_updates(&self) -> Receiver<Order> {
+        self.order_sender.subscribe()
+    }
+
+    /// Shares the update channels between the listener and the service.
+    fn connect_channels(&mut self) {
+        self.order_listener
+            .set_order_sender(self.order_sender.clone());
+        self.order_listener
+            .set_account_sender(self.account_sender.clone());
+        self.order_services
+            .set_order_sender(self.order_sender.clone());
+        self.order_services
+            .set_account_sender(self.account_sender.clone());
+    }
+
     pub async fn start_service(&mut self, path: &str) {
+        self.connect_channels();
         let listener = self.order_listener.start_listen(path);
         let service = self.order_services.start_order_service(path);
         tokio::join!(listener, service);
@@ -25,7 +55,58 @@ impl OrderManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures_util::StreamExt;
+    use order_services::order_service::order_service_server::OrderService;
+    use order_services::order_service::update_reply::Update;
+    use order_services::order_service::SubscribeUpdatesRequest;
+    use public::base_enum::order_enums::{OrderSide, OrderStatus, OrderType};
     use tracing_subscriber;
+
+    #[tokio::test]
+    async fn test_connect_channels() {
+        let mut order_manager = OrderManager::new();
+        order_manager.connect_channels();
+        let mut updates = order_manager
+            .order_services
+            .subscribe_updates(tonic::Request::new(SubscribeUpdatesRequest {
+                strategy: "test".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let order = Order::new(
+            "BTCUSDT",
+            95.0,
+            1.0,
+            OrderSide::BUY,
+            OrderType::Limit,
+            0.0,
+            0.0,
+            "test_BTCUSDT_1",
+            "1",
+            OrderStatus::New,
+            0,
+        );
+        order_manager.order_sender.send(order).unwrap();
+        order_manager
+            .account_sender
+            .send(AccountUpdate::new(None, vec![]))
+            .unwrap();
+        // both feeds reach the subscriber, in either order
+        let (mut orders, mut positions) = (0, 0);
+        for _ in 0..2 {
+            match updates.next().await.unwrap().unwrap().update {
+                Some(Update::Order(order)) => {
+                    assert_eq!(order.order_cid, "test_BTCUSDT_1");
+                    orders += 1;
+                }
+                Some(Update::Positions(_)) => positions += 1,
+                other => panic!("Unexpected update: {:?}", other),
+            }
+        }
+        assert_eq!((orders, positions), (1, 1));
+    }
+
     #[tokio::test]
     async fn test_service() {
         tracing_subscriber::fmt::init();
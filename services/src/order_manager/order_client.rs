@@ -1,11 +1,53 @@
 use order_service::order_service_client::OrderServiceClient;
-use order_service::{CancelOrderRequest, MakeOrderRequest};
+use order_service::{
+    BalanceReply, BatchOrderReply, CancelOrderRequest, CancelStrategyOrdersRequest,
+    GetOrderRequest, MakeBatchOrdersRequest, MakeOrderReply, MakeOrderRequest, OrderListReply,
+    PositionListReply, PositionReply, QueryRequest, SubscribeUpdatesRequest, UpdateReply,
+};
+use public::base_enum::order_enums::{OrderSide, OrderStatus, OrderType};
+use public::base_model::trade_model::order_model::Order;
+use public::base_model::trade_model::position_model::Position;
 use tonic::transport::Channel;
+use tonic::Streaming;
+use tracing::{error, info};
 
 pub mod order_service {
     tonic::include_proto!("order_service");
 }
 
+/// Order of a reply, with the symbol lowercased like the engine symbols.
+pub fn convert_reply_into_order(reply: &MakeOrderReply) -> Order {
+    Order::new(
+        &reply.symbol.to_lowercase(),
+        reply.price,
+        reply.quantity,
+        OrderSide::parse_order_side(&reply.side),
+        OrderType::parse_order_type(&reply.order_type),
+        reply.avg_price,
+        reply.filled_qty,
+        &reply.order_cid,
+        "",
+        OrderStatus::parse_order_status(&reply.status),
+        reply.timestamp,
+    )
+}
+
+/// Position of a reply, with the symbol lowercased like the engine symbols.
+pub fn convert_reply_into_position(reply: &PositionReply) -> Position {
+    Position::new(
+        &reply.symbol.to_lowercase(),
+        reply.entry_price,
+        reply.quantity,
+        OrderSide::parse_order_side(&reply.side),
+        reply.break_even_price,
+        reply.leverage,
+        reply.unrealized_pnl,
+        0.0,
+        reply.margin,
+        reply.timestamp,
+    )
+}
+
 #[derive(Debug)]
 pub struct GeneralOrderClient {
     server_url: String,
@@ -22,6 +64,13 @@ impl Default for GeneralOrderClient {
 }
 
 impl GeneralOrderClient {
+    pub fn new(server_url: &str) -> Self {
+        Self {
+            server_url: server_url.to_owned(),
+            client: None,
+        }
+    }
+
     pub async fn connect(&mut self) {
         self.client = Some(
             OrderServiceClient::connect(format!("{}", self.server_url))
@@ -37,21 +86,134 @@ impl GeneralOrderClient {
         quantity: f64,
         side: String,
         strategy: String,
-    ) {
-        let request = tonic::Request::new(MakeOrderRequest {
+    ) -> Option<MakeOrderReply> {
+        self.make_order_request(MakeOrderRequest {
             symbol,
             side,
             price,
             quantity,
             strategy,
-        });
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Places an order with the full set of request options, e.g. market, IOC or
+    /// reduce only orders.
+    pub async fn make_order_request(
+        &mut self,
+        request: MakeOrderRequest,
+    ) -> Option<MakeOrderReply> {
+        let request = tonic::Request::new(request);
         let mut client = self.client.clone().unwrap();
         match client.make_order(request).await {
             Ok(response) => {
-                println!("RESPONSE={:?}", response);
+                info!("RESPONSE={:?}", response);
+                Some(response.into_inner())
+            }
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Places up to 5 orders in one request, with a result per order.
+    pub async fn make_batch_orders(
+        &mut self,
+        orders: Vec<MakeOrderRequest>,
+    ) -> Option<BatchOrderReply> {
+        let request = tonic::Request::new(MakeBatchOrdersRequest { orders });
+        let mut client = self.client.clone().unwrap();
+        match client.make_batch_orders(request).await {
+            Ok(response) => {
+                info!("RESPONSE={:?}", response);
+                Some(response.into_inner())
+            }
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn get_order(
+        &mut self,
+        symbol: String,
+        order_cid: String,
+    ) -> Option<MakeOrderReply> {
+        let request = tonic::Request::new(GetOrderRequest { symbol, order_cid });
+        let mut client = self.client.clone().unwrap();
+        match client.get_order(request).await {
+            Ok(response) => Some(response.into_inner()),
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// An empty symbol lists the open orders of every symbol.
+    pub async fn list_open_orders(&mut self, symbol: String) -> Option<OrderListReply> {
+        let request = tonic::Request::new(QueryRequest { symbol });
+        let mut client = self.client.clone().unwrap();
+        match client.list_open_orders(request).await {
+            Ok(response) => Some(response.into_inner()),
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// An empty symbol returns every open position.
+    pub async fn get_positions(&mut self, symbol: String) -> Option<PositionListReply> {
+        let request = tonic::Request::new(QueryRequest { symbol });
+        let mut client = self.client.clone().unwrap();
+        match client.get_positions(request).await {
+            Ok(response) => Some(response.into_inner()),
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn get_balance(&mut self) -> Option<BalanceReply> {
+        let request = tonic::Request::new(QueryRequest::default());
+        let mut client = self.client.clone().unwrap();
+        match client.get_balance(request).await {
+            Ok(response) => Some(response.into_inner()),
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Filled orders of the symbol.
+    pub async fn get_trades(&mut self, symbol: String) -> Option<OrderListReply> {
+        let request = tonic::Request::new(QueryRequest { symbol });
+        let mut client = self.client.clone().unwrap();
+        match client.get_trades(request).await {
+            Ok(response) => Some(response.into_inner()),
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
             }
+        }
+    }
+
+    /// Streams order updates of `strategy`, or of every strategy when empty, and
+    /// account updates.
+    pub async fn subscribe_updates(&mut self, strategy: String) -> Option<Streaming<UpdateReply>> {
+        let request = tonic::Request::new(SubscribeUpdatesRequest { strategy });
+        let mut client = self.client.clone().unwrap();
+        match client.subscribe_updates(request).await {
+            Ok(response) => Some(response.into_inner()),
             Err(e) => {
-                println!("Error: {:?}", e);
+                error!("Error: {:?}", e);
+                None
             }
         }
     }
@@ -60,7 +222,7 @@ impl GeneralOrderClient {
         &mut self,
         symbol: String,
         order_cid: String,
-    ) {
+    ) -> Option<MakeOrderReply> {
         let request = tonic::Request::new(CancelOrderRequest {
             symbol,
             order_cid,
@@ -68,10 +230,47 @@ impl GeneralOrderClient {
         let mut client = self.client.clone().unwrap();
         match client.cancel_order(request).await {
             Ok(response) => {
-                println!("RESPONSE={:?}", response);
+                info!("RESPONSE={:?}", response);
+                Some(response.into_inner())
+            }
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn cancel_all_orders(&mut self, symbol: String) -> Option<BatchOrderReply> {
+        let request = tonic::Request::new(QueryRequest { symbol });
+        let mut client = self.client.clone().unwrap();
+        match client.cancel_all_orders(request).await {
+            Ok(response) => {
+                info!("RESPONSE={:?}", response);
+                Some(response.into_inner())
+            }
+            Err(e) => {
+                error!("Error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// An empty symbol cancels the strategy's orders on every symbol.
+    pub async fn cancel_strategy_orders(
+        &mut self,
+        strategy: String,
+        symbol: String,
+    ) -> Option<BatchOrderReply> {
+        let request = tonic::Request::new(CancelStrategyOrdersRequest { strategy, symbol });
+        let mut client = self.client.clone().unwrap();
+        match client.cancel_strategy_orders(request).await {
+            Ok(response) => {
+                info!("RESPONSE={:?}", response);
+                Some(response.into_inner())
             }
             Err(e) => {
-                println!("Error: {:?}", e);
+                error!("Error: {:?}", e);
+                None
             }
         }
     }
@@ -1,11 +1,19 @@
 use futures_util::{SinkExt, StreamExt};
 use public::{
     base_enum::order_enums::OrderStatus,
-    tools::{api_tools, settings_tools},
+    base_model::trade_model::{order_model::Order, position_model::Position},
+    exchange_model::binance_model::rest_data::{BalanceResponse, OrderResponse, PositionResponse},
+    tools::{api_tools, settings_tools, time_tools},
 };
+use public::strategy_model::strategy_portfolio::Balance;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::broadcast::Sender;
+use tokio::time::Instant;
 use tokio_tungstenite::tungstenite::Message;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
+use crate::backoff::ReconnectBackoff;
 use crate::mongo_engine::MongoEngine;
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -14,31 +22,94 @@ struct ListenKey {
     listen_key: String,
 }
 
+/// Balance and position changes of an `ACCOUNT_UPDATE` event or a REST resync.
+/// Stream events only carry the positions that changed.
+#[derive(Debug, Clone)]
+pub struct AccountUpdate {
+    balance: Option<Balance>,
+    positions: Vec<Position>,
+}
+
+impl AccountUpdate {
+    pub fn new(balance: Option<Balance>, positions: Vec<Position>) -> Self {
+        Self { balance, positions }
+    }
+
+    pub fn get_balance(&self) -> Option<&Balance> {
+        self.balance.as_ref()
+    }
+
+    pub fn get_positions(&self) -> &Vec<Position> {
+        &self.positions
+    }
+}
+
 pub struct OrderListener {
     client: reqwest::Client,
+    url: String,
     api_key: String,
+    secret_key: String,
     db_client: MongoEngine,
+    order_sender: Option<Sender<Order>>,
+    account_sender: Option<Sender<AccountUpdate>>,
+    keepalive_interval: Duration,
+    max_connection_time: Duration,
 }
 
 impl Default for OrderListener {
     fn default() -> Self {
         Self {
             client: reqwest::Client::new(),
+            url: "https://fapi.binance.com".to_string(),
             api_key: "".to_string(),
+            secret_key: "".to_string(),
             db_client: MongoEngine::default(),
+            order_sender: None,
+            account_sender: None,
+            // listen keys expire 60 minutes after the last keepalive
+            keepalive_interval: Duration::from_secs(30 * 60),
+            // binance closes connections after 24 hours
+            max_connection_time: Duration::from_secs(23 * 3600),
         }
     }
 }
 
 impl OrderListener {
+    /// Forward every parsed order update to `tx`, e.g. for a live StrategyEngine.
+    pub fn set_order_sender(&mut self, tx: Sender<Order>) {
+        self.order_sender = Some(tx);
+    }
+
+    /// Forward balance and position updates to `tx`, e.g. for a GeneralOrderService.
+    pub fn set_account_sender(&mut self, tx: Sender<AccountUpdate>) {
+        self.account_sender = Some(tx);
+    }
+
+    fn send_account_update(&self, balance: Option<Balance>, positions: Vec<Position>) {
+        if let Some(tx) = &self.account_sender {
+            if let Err(e) = tx.send(AccountUpdate::new(balance, positions)) {
+                error!("Send account update error: {}", e);
+            }
+        }
+    }
+
+    fn send_order(&self, order: Order) {
+        if let Some(tx) = &self.order_sender {
+            if let Err(e) = tx.send(order) {
+                error!("Send order update error: {}", e);
+            }
+        }
+    }
+
     fn load_settings(&mut self, path: &str) {
         let settings = settings_tools::load_settings(path);
         self.api_key = settings.get_api_key();
+        self.secret_key = settings.get_secret_key();
         info!("API_KEY: {}", self.api_key);
     }
 
     async fn get_usr_url(&mut self) -> Option<String> {
-        let url = format!("https://fapi.binance.com/fapi/v1/listenKey");
+        let url = format!("{}/fapi/v1/listenKey", self.url);
         match self
             .client
             .post(&url)
@@ -54,110 +125,339 @@ impl OrderListener {
                         let url = format!("wss://fstream.binance.com/ws/{}", listen_key.listen_key);
                         return Some(url);
                     }
+                } else {
+                    error!(
+                        "GetListenKey Error: {}",
+                        resp.text().await.unwrap_or_default()
+                    );
                 }
             }
             Err(e) => {
                 error!("GetListenKey Error: {}", e);
             }
         }
-        return None;
+        None
+    }
+
+    /// Extends the listen key validity by 60 minutes.
+    async fn keepalive_listen_key(&self) -> bool {
+        let url = format!("{}/fapi/v1/listenKey", self.url);
+        match self
+            .client
+            .put(&url)
+            .header("X-MBX-APIKEY", self.api_key.clone())
+            .send()
+            .await
+        {
+            Ok(resp) => {
+                if resp.status().is_success() {
+                    info!("Keepalive ListenKey");
+                    return true;
+                }
+                error!(
+                    "Keepalive ListenKey Error: {}",
+                    resp.text().await.unwrap_or_default()
+                );
+            }
+            Err(e) => {
+                error!("Keepalive ListenKey Error: {}", e);
+            }
+        }
+        false
     }
 
+    async fn signed_get(&self, path: &str, params: &str) -> Option<String> {
+        let timestamp = format!(
+            "timestamp={}&recvWindow=5000",
+            time_tools::get_now_timestamp()
+        );
+        let params = if params.is_empty() {
+            timestamp
+        } else {
+            format!("{}&{}", params, timestamp)
+        };
+        let signature = api_tools::get_signature(&self.secret_key, &params);
+        let url = format!("{}{}?{}&signature={}", self.url, path, params, signature);
+        match self
+            .client
+            .get(&url)
+            .header("X-MBX-APIKEY", self.api_key.clone())
+            .send()
+            .await
+        {
+            Ok(resp) => {
+                if resp.status().is_success() {
+                    return resp.text().await.ok();
+                }
+                error!(
+                    "Request {} Error: {}",
+                    path,
+                    resp.text().await.unwrap_or_default()
+                );
+            }
+            Err(e) => {
+                error!("Request {} Error: {}", path, e);
+            }
+        }
+        None
+    }
+
+    /// Reloads balance, positions and open orders over REST, since updates sent while
+    /// the stream was down are lost. Orders stored as open that are no longer open are
+    /// reconciled with their final status.
+    async fn resync(&self) {
+        if let Some(data) = self.signed_get("/fapi/v2/balance", "").await {
+            match serde_json::from_str::<Vec<BalanceResponse>>(&data) {
+                Ok(balances) => {
+                    if let Some(balance) = balances.iter().find(|b| b.asset == "USDT") {
+                        let balance = balance.convert_into_balance();
+                        match self.db_client.update_balance(&balance).await {
+                            Ok(_) => {
+                                info!("Resync balance success: {:?}", balance);
+                            }
+                            Err(e) => {
+                                error!("Resync balance error: {:?}", e);
+                            }
+                        }
+                        self.send_account_update(Some(balance), vec![]);
+                    }
+                }
+                Err(e) => {
+                    error!("Parse balance error: {}", e);
+                }
+            }
+        }
+
+        if let Some(data) = self.signed_get("/fapi/v2/positionRisk", "").await {
+            match serde_json::from_str::<Vec<PositionResponse>>(&data) {
+                Ok(positions) => {
+                    let positions: Vec<Position> = positions
+                        .iter()
+                        .map(|p| p.convert_into_position())
+                        .filter(|p| p.get_quantity() != 0.0)
+                        .collect();
+                    match self.db_client.update_positions(&positions).await {
+                        Ok(_) => {
+                            info!("Resync positions success: {:?}", positions);
+                        }
+                        Err(e) => {
+                            error!("Resync positions error: {:?}", e);
+                        }
+                    }
+                    self.send_account_update(None, positions);
+                }
+                Err(e) => {
+                    error!("Parse positions error: {}", e);
+                }
+            }
+        }
+
+        let Some(data) = self.signed_get("/fapi/v1/openOrders", "").await else {
+            return;
+        };
+        let mut open_cids = HashSet::new();
+        match serde_json::from_str::<Vec<OrderResponse>>(&data) {
+            Ok(orders) => {
+                for order in orders.iter().map(|o| o.convert_into_order()) {
+                    info!("Resync open order: {:?}", order);
+                    open_cids.insert(order.get_cid().to_string());
+                    self.store_order(&order).await;
+                    self.send_order(order);
+                }
+            }
+            Err(e) => {
+                error!("Parse open orders error: {}", e);
+                return;
+            }
+        }
+        self.reconcile_orders(&open_cids).await;
+    }
+
+    /// Queries the final status of the orders stored as open but missing from
+    /// `open_cids`, i.e. filled or canceled while the stream was down.
+    async fn reconcile_orders(&self, open_cids: &HashSet<String>) {
+        let stored_orders = match self.db_client.fetch_open_orders().await {
+            Ok(orders) => orders,
+            Err(e) => {
+                error!("Fetch open orders error: {:?}", e);
+                return;
+            }
+        };
+        for stored_order in stored_orders
+            .iter()
+            .filter(|o| !open_cids.contains(o.get_cid()))
+        {
+            let params = format!(
+                "symbol={}&origClientOrderId={}",
+                stored_order.get_symbol().to_uppercase(),
+                stored_order.get_cid()
+            );
+            let Some(data) = self.signed_get("/fapi/v1/order", &params).await else {
+                warn!(
+                    "Reconcile order {} failed, retry on the next resync",
+                    stored_order.get_cid()
+                );
+                continue;
+            };
+            match serde_json::from_str::<OrderResponse>(&data) {
+                Ok(order) => {
+                    let order = order.convert_into_order();
+                    info!("Reconcile order: {:?}", order);
+                    self.store_order(&order).await;
+                    self.send_order(order);
+                }
+                Err(e) => {
+                    error!("Parse order error: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Tracks open orders for `reconcile_orders` and stores filled and canceled ones.
+    async fn store_order(&self, order: &Order) {
+        let res = match order.get_status() {
+            OrderStatus::New | OrderStatus::PartiallyFilled => {
+                self.db_client.upsert_open_order(order).await
+            }
+            _ => self.db_client.remove_open_order(order.get_cid()).await,
+        };
+        if let Err(e) = res {
+            error!("Update open order error: {:?}", e);
+        }
+        if order.get_status() == OrderStatus::Filled || order.get_status() == OrderStatus::Canceled
+        {
+            match self.db_client.insert_order(order).await {
+                Ok(_) => {
+                    info!("Insert order success: {:?}", order);
+                }
+                Err(e) => {
+                    error!("Insert order error: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Listens to the user data stream, renewing the listen key and reconnecting when
+    /// the connection drops, the key expires or the 24h limit nears. State is resynced
+    /// over REST after every connect.
     pub async fn start_listen(&mut self, path: &str) {
         self.load_settings(path);
-        if let Some(url) = self.get_usr_url().await {
-            info!("Start listen order...");
-            match tokio_tungstenite::connect_async(url).await {
-                Ok((ws_stream, _)) => {
-                    let (mut write, mut read) = ws_stream.split();
-                    while let Some(msg) = read.next().await {
-                        match msg {
-                            Ok(msg) => match msg {
-                                Message::Ping(ping) => {
-                                    match write.send(Message::Pong(ping)).await {
-                                        Ok(_) => {}
-                                        Err(e) => {
-                                            error!("Ping Error: {}", e);
-                                        }
-                                    }
-                                }
-                                Message::Text(data) => {
-                                    if let Some(_) =
-                                        data.strip_prefix("{\"e\":\"ORDER_TRADE_UPDATE\"")
-                                    {
-                                        if let Some(order) = api_tools::parse_ws_order(&data) {
-                                            info!("Receive order message: {:?}", order);
-                                            if order.get_status() == OrderStatus::Filled
-                                                || order.get_status() == OrderStatus::Canceled
-                                            {
-                                                match self.db_client.insert_order(&order).await {
-                                                    Ok(_) => {
-                                                        info!("Insert order success: {:?}", order);
-                                                    }
-                                                    Err(e) => {
-                                                        error!("Insert order error: {:?}", e);
-                                                    }
-                                                }
-                                            }
-                                        } else {
-                                            error!("Parse order error: {}", data);
-                                        }
-                                    }
-
-                                    if let Some(_) = data.strip_prefix("{\"e\":\"ACCOUNT_UPDATE\"")
-                                    {
-                                        if let (Some(balance), Some(positions)) =
-                                            api_tools::parse_ws_account(&data)
-                                        {
-                                            info!("Receive account message: {:?}", balance);
-                                            info!("Receive account message: {:?}", positions);
-                                            if balance.get_balance() > 0.0 {
-                                                match self.db_client.update_balance(&balance).await
-                                                {
-                                                    Ok(_) => {
-                                                        info!(
-                                                            "update balance success: {:?}",
-                                                            balance
-                                                        );
-                                                    }
-                                                    Err(e) => {
-                                                        error!("update balance error: {:?}", e);
-                                                    }
-                                                }
-                                            }
-                                            if positions.len() > 0 {
-                                                match self
-                                                    .db_client
-                                                    .update_positions(&positions)
-                                                    .await
-                                                {
-                                                    Ok(_) => {
-                                                        info!(
-                                                            "update positions success: {:?}",
-                                                            positions
-                                                        );
-                                                    }
-                                                    Err(e) => {
-                                                        error!("update positions error: {:?}", e);
-                                                    }
-                                                }
-                                            }
-                                        } else {
-                                            error!("Parse account error: {}", data);
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            },
+        let mut backoff = ReconnectBackoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        loop {
+            if let Some(url) = self.get_usr_url().await {
+                info!("Start listen order...");
+                match tokio_tungstenite::connect_async(url).await {
+                    Ok((ws_stream, _)) => {
+                        backoff.reset();
+                        self.resync().await;
+                        self.listen(ws_stream).await;
+                    }
+                    Err(e) => {
+                        error!("Connect Error: {:?}", e);
+                    }
+                }
+            }
+            let delay = backoff.next_delay();
+            info!("Reconnect user data stream in {:?}", delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn listen(
+        &self,
+        ws_stream: tokio_tungstenite::WebSocketStream<
+            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+        >,
+    ) {
+        let connected_at = Instant::now();
+        let mut keepalive = tokio::time::interval_at(
+            Instant::now() + self.keepalive_interval,
+            self.keepalive_interval,
+        );
+        let (mut write, mut read) = ws_stream.split();
+        loop {
+            if connected_at.elapsed() >= self.max_connection_time {
+                info!("Reconnect user data stream before the 24h disconnect");
+                return;
+            }
+            tokio::select! {
+                _ = keepalive.tick() => {
+                    if !self.keepalive_listen_key().await {
+                        return;
+                    }
+                }
+                msg = read.next() => match msg {
+                    Some(Ok(msg)) => match msg {
+                        Message::Ping(ping) => match write.send(Message::Pong(ping)).await {
+                            Ok(_) => {}
                             Err(e) => {
-                                error!("Receive message error: {:?}", e);
+                                error!("Ping Error: {}", e);
+                            }
+                        },
+                        Message::Text(data) => {
+                            if data.starts_with("{\"e\":\"listenKeyExpired\"") {
+                                warn!("ListenKey expired");
+                                return;
                             }
+                            self.handle_message(&data).await;
                         }
+                        Message::Close(frame) => {
+                            info!("User data stream closed: {:?}", frame);
+                            return;
+                        }
+                        _ => {}
+                    },
+                    Some(Err(e)) => {
+                        error!("Receive message error: {:?}", e);
+                        return;
+                    }
+                    None => {
+                        error!("User data stream ended");
+                        return;
                     }
                 }
-                Err(e) => {
-                    error!("Connect Error: {:?}", e);
+            }
+        }
+    }
+
+    async fn handle_message(&self, data: &str) {
+        if data.starts_with("{\"e\":\"ORDER_TRADE_UPDATE\"") {
+            if let Some(order) = api_tools::parse_ws_order(data) {
+                info!("Receive order message: {:?}", order);
+                self.send_order(order.clone());
+                self.store_order(&order).await;
+            } else {
+                error!("Parse order error: {}", data);
+            }
+        }
+
+        if data.starts_with("{\"e\":\"ACCOUNT_UPDATE\"") {
+            if let (balance, Some(positions)) = api_tools::parse_ws_account(data) {
+                info!("Receive account message: {:?}", balance);
+                info!("Receive account message: {:?}", positions);
+                if let Some(balance) = &balance {
+                    match self.db_client.update_balance(balance).await {
+                        Ok(_) => {
+                            info!("update balance success: {:?}", balance);
+                        }
+                        Err(e) => {
+                            error!("update balance error: {:?}", e);
+                        }
+                    }
                 }
+                if !positions.is_empty() {
+                    match self.db_client.upsert_positions(&positions).await {
+                        Ok(_) => {
+                            info!("update positions success: {:?}", positions);
+                        }
+                        Err(e) => {
+                            error!("update positions error: {:?}", e);
+                        }
+                    }
+                }
+                self.send_account_update(balance, positions);
+            } else {
+                error!("Parse account error: {}", data);
             }
         }
     }
@@ -166,7 +466,87 @@ impl OrderListener {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
     use tracing_subscriber;
+
+    /// Answers one HTTP request with `status` and returns the request.
+    async fn serve_http_once(status: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{{}}",
+                status
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+        (url, handle)
+    }
+
+    /// A user data stream sending `messages`, then staying open until the client leaves.
+    async fn connect_ws(
+        messages: Vec<String>,
+    ) -> tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>
+    {
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", server.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = server.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for message in messages {
+                ws.send(Message::Text(message)).await.unwrap();
+            }
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+        tokio_tungstenite::connect_async(url).await.unwrap().0
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_listen_key() {
+        let (url, request) = serve_http_once("200 OK").await;
+        let mut listener = OrderListener {
+            url,
+            api_key: "test_key".to_string(),
+            ..Default::default()
+        };
+        assert!(listener.keepalive_listen_key().await);
+        let request = request.await.unwrap().to_lowercase();
+        assert!(request.starts_with("put /fapi/v1/listenkey "));
+        assert!(request.contains("x-mbx-apikey: test_key"));
+
+        let (url, _) = serve_http_once("400 Bad Request").await;
+        listener.url = url;
+        assert!(!listener.keepalive_listen_key().await);
+    }
+
+    #[tokio::test]
+    async fn test_listen_reconnects() {
+        // a failed keepalive ends the connection
+        let (url, request) = serve_http_once("400 Bad Request").await;
+        let listener = OrderListener {
+            url,
+            keepalive_interval: Duration::from_millis(100),
+            ..Default::default()
+        };
+        let ws_stream = connect_ws(vec![]).await;
+        tokio::time::timeout(Duration::from_secs(5), listener.listen(ws_stream))
+            .await
+            .unwrap();
+        assert!(request.await.unwrap().starts_with("PUT /fapi/v1/listenKey"));
+
+        // so does an expired listen key
+        let listener = OrderListener::default();
+        let expired = r#"{"e":"listenKeyExpired","E":1576653824250,"listenKey":"test"}"#;
+        let ws_stream = connect_ws(vec![expired.to_string()]).await;
+        tokio::time::timeout(Duration::from_secs(5), listener.listen(ws_stream))
+            .await
+            .unwrap();
+    }
     #[tokio::test]
     async fn test_start_listen() {
         tracing_subscriber::fmt::init();
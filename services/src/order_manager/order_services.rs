@@ -1,22 +1,50 @@
+use public::base_enum::order_enums::{OrderType, PositionSide, TimeInForce, WorkingType};
+use super::order_listener::AccountUpdate;
+use super::risk_manager::{RiskManager, RiskRejection, RiskRule, RiskSnapshot, DAY_MILLIS};
+use crate::mongo_engine::MongoEngine;
+use futures_util::Stream;
+use prost::Message;
+use public::tools::settings_tools::RiskSettings;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{error::RecvError, Receiver, Sender};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use public::base_enum::order_enums::OrderSide;
 use public::base_model::trade_model::order_model::{Order, OrderResponse};
+use public::base_model::trade_model::position_model::Position;
+use public::exchange_model::binance_model::rest_data::{
+    BalanceResponse, PositionResponse, TradeResponse,
+};
+use public::strategy_model::strategy_portfolio::Balance;
 use public::base_model::error_model::RequestError;
 use public::base_model::error_model::StrategyError;
 use public::tools::{settings_tools, time_tools, api_tools};
 use order_service::order_service_server::{OrderService, OrderServiceServer};
-use order_service::{CancelOrderRequest, MakeOrderReply, MakeOrderRequest};
+use order_service::update_reply::Update;
+use order_service::{
+    BalanceReply, BatchOrderReply, BatchOrderResult, CancelOrderRequest,
+    CancelStrategyOrdersRequest, GetOrderRequest, MakeBatchOrdersRequest, MakeOrderReply,
+    MakeOrderRequest, OrderListReply, PositionListReply, PositionReply, QueryRequest,
+    RiskRejectionReply, SubscribeUpdatesRequest, UpdateReply,
+};
 use tonic::{transport::Server, Request, Response, Status};
-use tracing::info;
+use tracing::{info, warn};
 
 pub mod order_service {
     tonic::include_proto!("order_service");
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct GeneralOrderService {
     req_url: String,
     api_key: String,
     secret_key: String,
     client: reqwest::Client,
+    db_client: MongoEngine,
+    order_sender: Option<Sender<Order>>,
+    account_sender: Option<Sender<AccountUpdate>>,
+    risk_manager: Arc<Mutex<RiskManager>>,
 }
 
 impl Default for GeneralOrderService {
@@ -26,10 +54,192 @@ impl Default for GeneralOrderService {
             api_key: "".to_owned(),
             secret_key: "".to_owned(),
             client: reqwest::Client::new(),
+            db_client: MongoEngine::default(),
+            order_sender: None,
+            account_sender: None,
+            risk_manager: Arc::new(Mutex::new(RiskManager::default())),
         }
     }
 }
 
+pub(crate) fn convert_order_into_reply(order: &Order) -> MakeOrderReply {
+    MakeOrderReply {
+        symbol: order.get_symbol().into(),
+        price: order.get_price(),
+        quantity: order.get_qty(),
+        side: order.get_side().string(),
+        strategy: order.get_strategy_name(),
+        status: order.get_status().string(),
+        order_cid: order.get_cid().into(),
+        order_type: order.get_order_type().string(),
+        avg_price: order.get_avg_price(),
+        filled_qty: order.get_filled_qty(),
+        timestamp: order.get_timestamp(),
+    }
+}
+
+pub(crate) fn convert_orders_into_reply(orders: &[Order]) -> OrderListReply {
+    OrderListReply {
+        orders: orders.iter().map(convert_order_into_reply).collect(),
+    }
+}
+
+/// Binance accepts at most 5 orders per batch.
+pub const MAX_BATCH_ORDERS: usize = 5;
+/// Binance rejects longer `newClientOrderId`s.
+const MAX_CID_LEN: usize = 36;
+
+pub(crate) fn convert_results_into_batch_reply(
+    results: Vec<Result<Order, StrategyError>>,
+) -> BatchOrderReply {
+    BatchOrderReply {
+        results: results
+            .into_iter()
+            .map(|res| match res {
+                Ok(order) => BatchOrderResult {
+                    order: Some(convert_order_into_reply(&order)),
+                    error: String::new(),
+                },
+                Err(e) => BatchOrderResult {
+                    order: None,
+                    error: format!("{:?}", e),
+                },
+            })
+            .collect(),
+    }
+}
+
+/// The rejection is attached as an encoded `RiskRejectionReply` in the status details.
+/// Stop loss and take profit requests may only override `default` with another
+/// conditional order type.
+pub(crate) fn parse_conditional_order_type(
+    req: &MakeOrderRequest,
+    default: OrderType,
+) -> Result<OrderType, StrategyError> {
+    if req.order_type.is_empty() {
+        return Ok(default);
+    }
+    match OrderType::try_parse_order_type(&req.order_type) {
+        Some(order_type) if order_type.is_conditional() => Ok(order_type),
+        _ => Err(StrategyError::PlaceOrderError(format!(
+            "Stop loss and take profit orders need a conditional order type: {}",
+            req.order_type
+        ))),
+    }
+}
+
+fn convert_risk_rejection_into_status(rejection: &RiskRejection) -> Status {
+    let details = RiskRejectionReply {
+        rule: rejection.get_rule().string(),
+        reason: rejection.get_reason().to_string(),
+    };
+    Status::with_details(
+        tonic::Code::FailedPrecondition,
+        format!("Risk rejected {}", rejection),
+        details.encode_to_vec().into(),
+    )
+}
+
+pub(crate) type UpdateStream = Pin<Box<dyn Stream<Item = Result<UpdateReply, Status>> + Send>>;
+
+async fn recv_update<T: Clone>(receiver: &mut Option<Receiver<T>>) -> Result<T, RecvError> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn convert_account_update_into_replies(update: &AccountUpdate) -> Vec<UpdateReply> {
+    let mut res = Vec::new();
+    if let Some(balance) = update.get_balance() {
+        res.push(UpdateReply {
+            update: Some(Update::Balance(BalanceReply {
+                balance: balance.get_balance(),
+                update_time: balance.get_update_time(),
+            })),
+        });
+    }
+    // an empty list without a balance is a resync with no open position
+    if !update.get_positions().is_empty() || update.get_balance().is_none() {
+        res.push(UpdateReply {
+            update: Some(Update::Positions(PositionListReply {
+                positions: update
+                    .get_positions()
+                    .iter()
+                    .map(convert_position_into_reply)
+                    .collect(),
+            })),
+        });
+    }
+    res
+}
+
+/// Forwards the order updates of `strategy`, or of every strategy when it is empty,
+/// and all account updates until the subscriber disconnects or both feeds close.
+pub(crate) fn subscribe_update_stream(
+    strategy: String,
+    mut order_receiver: Option<Receiver<Order>>,
+    mut account_receiver: Option<Receiver<AccountUpdate>>,
+) -> UpdateStream {
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<UpdateReply, Status>>(1024);
+    tokio::spawn(async move {
+        while order_receiver.is_some() || account_receiver.is_some() {
+            let replies = tokio::select! {
+                _ = tx.closed() => break,
+                order = recv_update(&mut order_receiver) => match order {
+                    Ok(order) if strategy.is_empty() || order.get_strategy_name() == strategy => {
+                        vec![UpdateReply {
+                            update: Some(Update::Order(convert_order_into_reply(&order))),
+                        }]
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Order update subscriber lagged by {} messages", n);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => {
+                        order_receiver = None;
+                        continue;
+                    }
+                },
+                update = recv_update(&mut account_receiver) => match update {
+                    Ok(update) => convert_account_update_into_replies(&update),
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Account update subscriber lagged by {} messages", n);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => {
+                        account_receiver = None;
+                        continue;
+                    }
+                },
+            };
+            for reply in replies {
+                if tx.send(Ok(reply)).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Box::pin(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|reply| (reply, rx))
+    }))
+}
+
+pub(crate) fn convert_position_into_reply(position: &Position) -> PositionReply {
+    PositionReply {
+        symbol: position.get_symbol().into(),
+        side: position.get_side().string(),
+        quantity: position.get_quantity(),
+        entry_price: position.get_price(),
+        break_even_price: position.get_break_even_price(),
+        leverage: position.get_leverage(),
+        unrealized_pnl: position.get_unrealized_pnl(),
+        margin: position.get_margin(),
+        timestamp: position.get_timestamp(),
+    }
+}
+
 #[tonic::async_trait]
 impl OrderService for GeneralOrderService {
     async fn make_order(
@@ -37,28 +247,18 @@ impl OrderService for GeneralOrderService {
         request: Request<MakeOrderRequest>,
     ) -> Result<Response<MakeOrderReply>, Status> {
         let req = request.into_inner();
-        let cur_req = MakeOrderRequest {
-            symbol: req.symbol.clone(),
-            price: req.price,
-            quantity: req.quantity,
-            side: req.side.clone(),
-            strategy: req.strategy.clone(),
+        let timestamp = match self.check_risk(&req).await {
+            Ok(timestamp) => timestamp,
+            Err(rejection) => return Err(convert_risk_rejection_into_status(&rejection)),
         };
 
-        match self.create_order(&cur_req).await {
+        match self.create_order(&req).await {
             Ok(order) => {
-                let reply = MakeOrderReply {
-                    symbol: order.get_symbol().into(),
-                    price: order.get_price(),
-                    quantity: order.get_qty(),
-                    side: order.get_side().string(),
-                    strategy: order.get_strategy_name(),
-                    status: order.get_status().string(),
-                    order_cid: order.get_cid().into(),
-                };
-                return Ok(tonic::Response::new(reply));
+                self.ack_risk(&req, timestamp, order.get_cid());
+                return Ok(tonic::Response::new(convert_order_into_reply(&order)));
             }
             Err(e) => {
+                self.release_risk(&req, timestamp);
                 return Err(Status::new(tonic::Code::Internal, format!("{:?}", e)));
             }
         };
@@ -69,28 +269,24 @@ impl OrderService for GeneralOrderService {
         request: Request<MakeOrderRequest>,
     ) -> Result<Response<MakeOrderReply>, Status> {
         let req = request.into_inner();
-        let cur_req = MakeOrderRequest {
-            symbol: req.symbol.clone(),
-            price: req.price,
-            quantity: req.quantity,
-            side: req.side.clone(),
-            strategy: req.strategy.clone(),
+        if let Err(e) = parse_conditional_order_type(&req, OrderType::StopMarket) {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                format!("{:?}", e),
+            ));
+        }
+        let timestamp = match self.check_risk(&req).await {
+            Ok(timestamp) => timestamp,
+            Err(rejection) => return Err(convert_risk_rejection_into_status(&rejection)),
         };
 
-        match self.make_stop_loss_order(&cur_req).await {
+        match self.make_stop_loss_order(&req).await {
             Ok(order) => {
-                let reply = MakeOrderReply {
-                    symbol: order.get_symbol().into(),
-                    price: order.get_price(),
-                    quantity: order.get_qty(),
-                    side: order.get_side().string(),
-                    strategy: order.get_strategy_name(),
-                    status: order.get_status().string(),
-                    order_cid: order.get_cid().into(),
-                };
-                return Ok(tonic::Response::new(reply));
+                self.ack_risk(&req, timestamp, order.get_cid());
+                return Ok(tonic::Response::new(convert_order_into_reply(&order)));
             }
             Err(e) => {
+                self.release_risk(&req, timestamp);
                 return Err(Status::new(tonic::Code::Internal, format!("{:?}", e)));
             }
         };
@@ -101,33 +297,156 @@ impl OrderService for GeneralOrderService {
         request: Request<MakeOrderRequest>,
     ) -> Result<Response<MakeOrderReply>, Status> {
         let req = request.into_inner();
-        let cur_req = MakeOrderRequest {
-            symbol: req.symbol.clone(),
-            price: req.price,
-            quantity: req.quantity,
-            side: req.side.clone(),
-            strategy: req.strategy.clone(),
+        if let Err(e) = parse_conditional_order_type(&req, OrderType::TakeProfitMarket) {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                format!("{:?}", e),
+            ));
+        }
+        let timestamp = match self.check_risk(&req).await {
+            Ok(timestamp) => timestamp,
+            Err(rejection) => return Err(convert_risk_rejection_into_status(&rejection)),
         };
 
-        match self.make_take_profit_order(&cur_req).await {
+        match self.make_take_profit_order(&req).await {
             Ok(order) => {
-                let reply = MakeOrderReply {
-                    symbol: order.get_symbol().into(),
-                    price: order.get_price(),
-                    quantity: order.get_qty(),
-                    side: order.get_side().string(),
-                    strategy: order.get_strategy_name(),
-                    status: order.get_status().string(),
-                    order_cid: order.get_cid().into(),
-                };
-                return Ok(tonic::Response::new(reply));
+                self.ack_risk(&req, timestamp, order.get_cid());
+                return Ok(tonic::Response::new(convert_order_into_reply(&order)));
             }
             Err(e) => {
+                self.release_risk(&req, timestamp);
                 return Err(Status::new(tonic::Code::Internal, format!("{:?}", e)));
             }
         };
     }
 
+    async fn get_order(
+        &self,
+        request: Request<GetOrderRequest>,
+    ) -> Result<Response<MakeOrderReply>, Status> {
+        match self.query_order(&request.into_inner()).await {
+            Ok(order) => Ok(Response::new(convert_order_into_reply(&order))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn list_open_orders(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<OrderListReply>, Status> {
+        match self.query_open_orders(&request.into_inner().symbol).await {
+            Ok(orders) => Ok(Response::new(convert_orders_into_reply(&orders))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn get_positions(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<PositionListReply>, Status> {
+        match self.query_positions(&request.into_inner().symbol).await {
+            Ok(positions) => Ok(Response::new(PositionListReply {
+                positions: positions.iter().map(convert_position_into_reply).collect(),
+            })),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn get_balance(
+        &self,
+        _request: Request<QueryRequest>,
+    ) -> Result<Response<BalanceReply>, Status> {
+        match self.query_balance().await {
+            Ok(balance) => Ok(Response::new(BalanceReply {
+                balance: balance.get_balance(),
+                update_time: balance.get_update_time(),
+            })),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn get_trades(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<OrderListReply>, Status> {
+        let req = request.into_inner();
+        if req.symbol.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Trades need a symbol",
+            ));
+        }
+        match self.query_trades(&req.symbol).await {
+            Ok(orders) => Ok(Response::new(convert_orders_into_reply(&orders))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn make_batch_orders(
+        &self,
+        request: Request<MakeBatchOrdersRequest>,
+    ) -> Result<Response<BatchOrderReply>, Status> {
+        match self.make_batch_orders(&request.into_inner().orders).await {
+            Ok(results) => Ok(Response::new(convert_results_into_batch_reply(results))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn cancel_all_orders(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<BatchOrderReply>, Status> {
+        let req = request.into_inner();
+        if req.symbol.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Cancel all needs a symbol",
+            ));
+        }
+        match self.cancel_all_orders(&req.symbol).await {
+            Ok(orders) => Ok(Response::new(convert_results_into_batch_reply(
+                orders.into_iter().map(Ok).collect(),
+            ))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn cancel_strategy_orders(
+        &self,
+        request: Request<CancelStrategyOrdersRequest>,
+    ) -> Result<Response<BatchOrderReply>, Status> {
+        let req = request.into_inner();
+        if req.strategy.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Cancel strategy orders needs a strategy",
+            ));
+        }
+        match self
+            .cancel_strategy_orders(&req.strategy, &req.symbol)
+            .await
+        {
+            Ok(results) => Ok(Response::new(convert_results_into_batch_reply(results))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    type SubscribeUpdatesStream = UpdateStream;
+
+    async fn subscribe_updates(
+        &self,
+        request: Request<SubscribeUpdatesRequest>,
+    ) -> Result<Response<Self::SubscribeUpdatesStream>, Status> {
+        if self.order_sender.is_none() && self.account_sender.is_none() {
+            return Err(Status::failed_precondition("Updates are not enabled"));
+        }
+        Ok(Response::new(subscribe_update_stream(
+            request.into_inner().strategy,
+            self.order_sender.as_ref().map(|tx| tx.subscribe()),
+            self.account_sender.as_ref().map(|tx| tx.subscribe()),
+        )))
+    }
+
     async fn cancel_order(
         &self,
         request: Request<CancelOrderRequest>,
@@ -136,16 +455,7 @@ impl OrderService for GeneralOrderService {
 
         match self.make_cancel_order(&req).await {
             Ok(order) => {
-                let reply = MakeOrderReply {
-                    symbol: order.get_symbol().into(),
-                    price: order.get_price(),
-                    quantity: order.get_qty(),
-                    side: order.get_side().string(),
-                    strategy: order.get_strategy_name(),
-                    status: order.get_status().string(),
-                    order_cid: order.get_cid().into(),
-                };
-                return Ok(tonic::Response::new(reply));
+                return Ok(tonic::Response::new(convert_order_into_reply(&order)));
             }
             Err(e) => {
                 return Err(Status::new(tonic::Code::Internal, format!("{:?}", e)));
@@ -155,10 +465,220 @@ impl OrderService for GeneralOrderService {
 }
 
 impl GeneralOrderService {
+    /// Order updates pushed to `SubscribeUpdates` clients, shared with the OrderListener.
+    pub fn set_order_sender(&mut self, tx: Sender<Order>) {
+        self.order_sender = Some(tx);
+    }
+
+    /// Account updates pushed to `SubscribeUpdates` clients, shared with the OrderListener.
+    pub fn set_account_sender(&mut self, tx: Sender<AccountUpdate>) {
+        self.account_sender = Some(tx);
+    }
+
+    /// Mongo database the OrderListener writes orders, positions and balance into.
+    pub fn set_db_client(&mut self, db_client: MongoEngine) {
+        self.db_client = db_client;
+    }
+
+    /// Pre-trade risk limits checked before orders are sent, all disabled by default.
+    pub fn set_risk_settings(&mut self, settings: RiskSettings) {
+        self.risk_manager.lock().unwrap().set_settings(settings);
+    }
+
     pub fn load_settings(&mut self, path: &str) {
         let settings = settings_tools::load_settings(path);
         self.api_key = settings.get_api_key();
         self.secret_key = settings.get_secret_key();
+        self.set_risk_settings(settings.get_risk_settings());
+    }
+
+    /// Last trade price of the symbol from the public ticker.
+    async fn fetch_last_price(&self, symbol: &str) -> Result<f64, StrategyError> {
+        let url = format!(
+            "{}/fapi/v1/ticker/price?symbol={}",
+            self.req_url,
+            symbol.to_uppercase()
+        );
+        let text = match self.client.get(&url).send().await {
+            Ok(res) => match res.text().await {
+                Ok(text) => text,
+                Err(e) => {
+                    return Err(StrategyError::PlaceOrderError(format!(
+                        "Fetch last price failed: {}",
+                        e
+                    )));
+                }
+            },
+            Err(e) => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Fetch last price failed: {}",
+                    e
+                )));
+            }
+        };
+        match serde_json::from_str::<serde_json::Value>(&text) {
+            Ok(ticker) => match ticker["price"].as_str().and_then(|px| px.parse().ok()) {
+                Some(price) => Ok(price),
+                None => Err(StrategyError::PlaceOrderError(format!(
+                    "Parse last price failed: {}",
+                    text
+                ))),
+            },
+            Err(e) => Err(StrategyError::PlaceOrderError(format!(
+                "Parse last price failed: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Runs the pre-trade risk checks, fetching only the account state the limits of
+    /// the symbol need. Missing position or balance data rejects the order. Returns the
+    /// timestamp the order was accepted with, which `ack_risk` and `release_risk` take.
+    async fn check_risk(&self, req: &MakeOrderRequest) -> Result<i64, RiskRejection> {
+        let (needs_position, needs_balance, needs_last_price, cached_price) = {
+            let risk_manager = self.risk_manager.lock().unwrap();
+            (
+                risk_manager.needs_position(&req.symbol),
+                risk_manager.needs_balance(&req.symbol),
+                risk_manager.needs_last_price(req),
+                risk_manager.get_last_price(&req.symbol),
+            )
+        };
+        let mut snapshot = RiskSnapshot::default();
+        if needs_position {
+            snapshot.position = match self.query_positions(&req.symbol).await {
+                Ok(positions) => positions.iter().map(|p| p.get_signed_quantity()).sum(),
+                Err(e) => {
+                    return Err(RiskRejection::new(
+                        RiskRule::MaxPosition,
+                        format!("Position unavailable: {:?}", e),
+                    ));
+                }
+            };
+            let open_orders = match self.query_open_orders(&req.symbol).await {
+                Ok(orders) => orders,
+                Err(e) => {
+                    return Err(RiskRejection::new(
+                        RiskRule::MaxPosition,
+                        format!("Open orders unavailable: {:?}", e),
+                    ));
+                }
+            };
+            for order in open_orders {
+                let remaining = order.get_qty() - order.get_filled_qty();
+                match order.get_side() {
+                    OrderSide::BUY => snapshot.open_buy += remaining,
+                    _ => snapshot.open_sell += remaining,
+                }
+            }
+        }
+        if needs_balance {
+            snapshot.balance = match self.query_balance().await {
+                Ok(balance) => balance.get_balance(),
+                Err(e) => {
+                    return Err(RiskRejection::new(
+                        RiskRule::MaxLeverage,
+                        format!("Balance unavailable: {:?}", e),
+                    ));
+                }
+            };
+        }
+        if needs_last_price {
+            // fall back to the last fill seen by the risk feed
+            snapshot.last_price = match self.fetch_last_price(&req.symbol).await {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    warn!("{:?}", e);
+                    cached_price
+                }
+            };
+        }
+        let timestamp = time_tools::get_now_timestamp();
+        self.risk_manager
+            .lock()
+            .unwrap()
+            .check_order(req, &snapshot, timestamp)?;
+        Ok(timestamp)
+    }
+
+    /// Takes an order the exchange did not accept out of the order rate.
+    fn release_risk(&self, req: &MakeOrderRequest, timestamp: i64) {
+        self.risk_manager
+            .lock()
+            .unwrap()
+            .release_order(&req.symbol, timestamp);
+    }
+
+    /// Keeps an order the exchange accepted counted as open until its order update.
+    fn ack_risk(&self, req: &MakeOrderRequest, timestamp: i64, cid: &str) {
+        self.risk_manager
+            .lock()
+            .unwrap()
+            .ack_order(&req.symbol, timestamp, cid);
+    }
+
+    /// Starts the feeds of the order updates shared by the OrderListener.
+    fn start_feeds(&self) {
+        if let Some(tx) = &self.order_sender {
+            self.start_risk_feed(tx.subscribe());
+        }
+    }
+
+    /// Applies the order updates of the OrderListener to the daily pnl of the risk checks.
+    /// The daily pnl is restored from Mongo and written back on each change, so it
+    /// outlives a restart.
+    fn start_risk_feed(&self, mut rx: Receiver<Order>) -> tokio::task::JoinHandle<()> {
+        let risk_manager = self.risk_manager.clone();
+        let (pnl_tx, pnl_rx) = mpsc::unbounded_channel::<String>();
+        self.start_daily_pnl_store(pnl_rx);
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(order) => {
+                        let changed = risk_manager.lock().unwrap().on_order_update(&order);
+                        if let Some(strategy) = changed {
+                            let _ = pnl_tx.send(strategy);
+                        }
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Risk feed lagged by {} order updates", n);
+                    }
+                    Err(RecvError::Closed) => {
+                        warn!("Risk feed order channel closed");
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Restores the daily pnl of the day, then stores the daily pnl of each strategy
+    /// received from the risk feed.
+    fn start_daily_pnl_store(&self, mut rx: UnboundedReceiver<String>) {
+        let risk_manager = self.risk_manager.clone();
+        let db_client = self.db_client.clone();
+        tokio::spawn(async move {
+            let timestamp = time_tools::get_now_timestamp();
+            match db_client.fetch_daily_pnl(timestamp / DAY_MILLIS).await {
+                Ok(daily_pnl) => risk_manager
+                    .lock()
+                    .unwrap()
+                    .restore_daily_pnl(timestamp, daily_pnl),
+                Err(e) => warn!("Failed to restore daily pnl: {:?}", e),
+            }
+            while let Some(strategy) = rx.recv().await {
+                let (day, pnl) = {
+                    let risk_manager = risk_manager.lock().unwrap();
+                    (
+                        risk_manager.get_pnl_day(),
+                        risk_manager.get_daily_pnl(&strategy),
+                    )
+                };
+                if let Err(e) = db_client.update_daily_pnl(day, &strategy, pnl).await {
+                    warn!("Failed to store daily pnl of {}: {:?}", strategy, e);
+                }
+            }
+        });
     }
 
     fn parse_response_order(&self, data: String) -> Option<Order> {
@@ -177,21 +697,6 @@ impl GeneralOrderService {
         }
     }
 
-    fn convert_base_order_request_into_params(
-        &self,
-        symbol: &str,
-        side: &str,
-        quantity: f64,
-    ) -> String {
-        format!(
-            "symbol={}&side={}&quantity={}&timestamp={}&timeInForce=GTC&recvWindow=5000",
-            symbol,
-            side,
-            quantity,
-            time_tools::get_now_timestamp()
-        )
-    }
-
     fn convert_cancel_order_request_into_params(&self, symbol: &str, cid: &str) -> String {
         format!(
             "symbol={}&origClientOrderId={}&timestamp={}&recvWindow=5000",
@@ -201,55 +706,155 @@ impl GeneralOrderService {
         )
     }
 
-    fn convert_make_order_request_into_params(
-        &self,
-        symbol: &str,
-        side: &str,
-        price: f64,
-        quantity: f64,
-        strategy: &str,
-    ) -> String {
-        let mut params = self.convert_base_order_request_into_params(symbol, side, quantity);
-        let cid = self.generate_cid(symbol, strategy);
-        params.push_str(&format!(
-            "&newClientOrderId={}&price={}&type=LIMIT",
-            cid, price
-        ));
-        params
+    /// Empty request fields fall back to `default`.
+    fn parse_request_field<T>(
+        name: &str,
+        value: &str,
+        default: T,
+        parse: fn(&str) -> Option<T>,
+    ) -> Result<T, StrategyError> {
+        if value.is_empty() {
+            return Ok(default);
+        }
+        match parse(value) {
+            Some(res) => Ok(res),
+            None => Err(StrategyError::PlaceOrderError(format!(
+                "Invalid {}: {}",
+                name, value
+            ))),
+        }
     }
 
-    fn conver_stop_loss_order_request_into_params(
+    /// Signed order params of the request, see `convert_make_order_request_into_fields`.
+    fn convert_make_order_request_into_params(
         &self,
-        symbol: &str,
-        side: &str,
-        price: f64,
-        quantity: f64,
-        strategy: &str,
-    ) -> String {
-        let mut params = self.convert_base_order_request_into_params(symbol, side, quantity);
-        let cid = self.generate_cid(symbol, strategy);
+        req: &MakeOrderRequest,
+        default_type: OrderType,
+    ) -> Result<String, StrategyError> {
+        let cid = self.generate_cid(&req.symbol, &req.strategy);
+        let fields = Self::convert_make_order_request_into_fields(req, default_type, &cid)?;
+        let mut params = fields
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<String>>()
+            .join("&");
         params.push_str(&format!(
-            "&newClientOrderId={}&stopPrice={}&type=STOP_MARKET",
-            cid, price
+            "&timestamp={}&recvWindow=5000",
+            time_tools::get_now_timestamp()
         ));
-        params
+        Ok(params)
     }
 
-    fn conver_take_profit_order_request_into_params(
-        &self,
-        symbol: &str,
-        side: &str,
-        price: f64,
-        quantity: f64,
-        strategy: &str,
-    ) -> String {
-        let mut params = self.convert_base_order_request_into_params(symbol, side, quantity);
-        let cid = self.generate_cid(symbol, strategy);
-        params.push_str(&format!(
-            "&newClientOrderId={}&stopPrice={}&type=TAKE_PROFIT_MARKET",
-            cid, price
-        ));
-        params
+    /// Binance order fields of the request. `default_type` applies when the request
+    /// leaves `order_type` empty; limit prices default to GTC.
+    fn convert_make_order_request_into_fields(
+        req: &MakeOrderRequest,
+        default_type: OrderType,
+        cid: &str,
+    ) -> Result<Vec<(&'static str, String)>, StrategyError> {
+        let order_type = Self::parse_request_field(
+            "order type",
+            &req.order_type,
+            default_type,
+            OrderType::try_parse_order_type,
+        )?;
+        let has_limit_price = match order_type {
+            OrderType::Limit | OrderType::Stop | OrderType::TakeProfit => true,
+            OrderType::Market | OrderType::StopMarket | OrderType::TakeProfitMarket => false,
+            _ => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Unsupported order type: {}",
+                    order_type.string()
+                )));
+            }
+        };
+        let time_in_force = Self::parse_request_field(
+            "time in force",
+            &req.time_in_force,
+            TimeInForce::GTC,
+            TimeInForce::try_parse_time_in_force,
+        )?;
+        let position_side = Self::parse_request_field(
+            "position side",
+            &req.position_side,
+            PositionSide::Both,
+            PositionSide::try_parse_position_side,
+        )?;
+        let working_type = Self::parse_request_field(
+            "working type",
+            &req.working_type,
+            WorkingType::ContractPrice,
+            WorkingType::try_parse_working_type,
+        )?;
+
+        if !req.time_in_force.is_empty() && !has_limit_price {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Time in force needs a limit price: {}",
+                order_type.string()
+            )));
+        }
+        if !req.working_type.is_empty() && !order_type.is_conditional() {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Working type needs a conditional order: {}",
+                order_type.string()
+            )));
+        }
+        if req.close_position
+            && !matches!(
+                order_type,
+                OrderType::StopMarket | OrderType::TakeProfitMarket
+            )
+        {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Close position needs a STOP_MARKET or TAKE_PROFIT_MARKET order: {}",
+                order_type.string()
+            )));
+        }
+        // Binance rejects reduce only in hedge mode and with close position
+        if req.reduce_only && (req.close_position || position_side != PositionSide::Both) {
+            return Err(StrategyError::PlaceOrderError(
+                "Reduce only cannot be combined with close position or hedge mode".to_string(),
+            ));
+        }
+
+        if cid.len() > MAX_CID_LEN {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Client order id longer than {} characters: {}",
+                MAX_CID_LEN, cid
+            )));
+        }
+
+        let mut fields = vec![
+            ("symbol", req.symbol.clone()),
+            ("side", req.side.clone()),
+            ("type", order_type.string()),
+            ("newClientOrderId", cid.to_string()),
+        ];
+        if req.close_position {
+            fields.push(("closePosition", "true".to_string()));
+        } else {
+            fields.push(("quantity", req.quantity.to_string()));
+        }
+        if has_limit_price {
+            fields.push(("price", req.price.to_string()));
+            fields.push(("timeInForce", time_in_force.string()));
+        }
+        if order_type.is_conditional() {
+            let stop_price = if req.stop_price > 0.0 {
+                req.stop_price
+            } else {
+                req.price
+            };
+            fields.push(("stopPrice", stop_price.to_string()));
+            fields.push(("workingType", working_type.string()));
+        }
+        if req.reduce_only {
+            fields.push(("reduceOnly", "true".to_string()));
+        }
+        if position_side != PositionSide::Both {
+            fields.push(("positionSide", position_side.string()));
+        }
+        Ok(fields)
     }
 
     fn generate_cid(&self, symbol: &str, strategy: &str) -> String {
@@ -257,6 +862,11 @@ impl GeneralOrderService {
         format!("{}_{}_{}", strategy, symbol, timestamp)
     }
 
+    /// Orders of one batch share the timestamp, the index keeps their cids unique.
+    fn generate_batch_cid(symbol: &str, strategy: &str, timestamp: i64, index: usize) -> String {
+        format!("{}_{}_{}_{}", strategy, symbol, timestamp, index)
+    }
+
     async fn base_create_order(&self, params: &str) -> Result<Order, StrategyError> {
         let signature = api_tools::get_signature(&self.secret_key, &params);
         let url = format!(
@@ -302,13 +912,7 @@ impl GeneralOrderService {
     }
 
     async fn create_order(&self, order: &MakeOrderRequest) -> Result<Order, StrategyError> {
-        let params = self.convert_make_order_request_into_params(
-            &order.symbol,
-            &order.side,
-            order.price,
-            order.quantity,
-            &order.strategy,
-        );
+        let params = self.convert_make_order_request_into_params(order, OrderType::Limit)?;
         self.base_create_order(&params).await
     }
 
@@ -316,24 +920,13 @@ impl GeneralOrderService {
         &self,
         order: &MakeOrderRequest,
     ) -> Result<Order, StrategyError> {
-        let params = self.conver_take_profit_order_request_into_params(
-            &order.symbol,
-            &order.side,
-            order.price,
-            order.quantity,
-            &order.strategy,
-        );
+        let params =
+            self.convert_make_order_request_into_params(order, OrderType::TakeProfitMarket)?;
         self.base_create_order(&params).await
     }
 
     async fn make_stop_loss_order(&self, order: &MakeOrderRequest) -> Result<Order, StrategyError> {
-        let params = self.conver_stop_loss_order_request_into_params(
-            &order.symbol,
-            &order.side,
-            order.price,
-            order.quantity,
-            &order.strategy,
-        );
+        let params = self.convert_make_order_request_into_params(order, OrderType::StopMarket)?;
         self.base_create_order(&params).await
     }
 
@@ -387,9 +980,385 @@ impl GeneralOrderService {
         }
     }
 
+    async fn signed_get(&self, path: &str, params: &str) -> Result<String, StrategyError> {
+        self.signed_request(reqwest::Method::GET, path, params)
+            .await
+    }
+
+    /// Signed request of `path` with `params`, returning the body or the Binance error.
+    async fn signed_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        params: &str,
+    ) -> Result<String, StrategyError> {
+        let params = if params.is_empty() {
+            format!(
+                "timestamp={}&recvWindow=5000",
+                time_tools::get_now_timestamp()
+            )
+        } else {
+            format!(
+                "{}&timestamp={}&recvWindow=5000",
+                params,
+                time_tools::get_now_timestamp()
+            )
+        };
+        let signature = api_tools::get_signature(&self.secret_key, &params);
+        let url = format!("{}{}?{}&signature={}", self.req_url, path, params, signature);
+        match self
+            .client
+            .request(method, &url)
+            .header("X-MBX-APIKEY", self.api_key.clone())
+            .send()
+            .await
+        {
+            Ok(res) => match res.text().await {
+                Ok(text) => match self.parse_response_error(text.clone()) {
+                    // cancel all reports success as code 200
+                    Some(request_error) if request_error.code != 200 => {
+                        Err(request_error.parse_request_error_into_strategy_error())
+                    }
+                    _ => Ok(text),
+                },
+                Err(e) => Err(StrategyError::PlaceOrderError(format!(
+                    "Query {} failed: {}",
+                    path, e
+                ))),
+            },
+            Err(e) => Err(StrategyError::PlaceOrderError(format!(
+                "Query {} failed: {}",
+                path, e
+            ))),
+        }
+    }
+
+    fn parse_response_orders(&self, path: &str, data: &str) -> Result<Vec<Order>, StrategyError> {
+        match serde_json::from_str::<Vec<OrderResponse>>(data) {
+            Ok(orders) => Ok(orders
+                .iter()
+                .map(|o| o.order_response_into_order())
+                .collect()),
+            Err(e) => Err(StrategyError::PlaceOrderError(format!(
+                "Parse {} failed: {}",
+                path, e
+            ))),
+        }
+    }
+
+    /// Closed orders come from Mongo, anything else from REST.
+    async fn query_order(&self, req: &GetOrderRequest) -> Result<Order, StrategyError> {
+        let symbol = req.symbol.to_uppercase();
+        match self.db_client.fetch_orders(&symbol).await {
+            Ok(Some(orders)) => {
+                if let Some(order) = orders.into_iter().find(|o| o.get_cid() == req.order_cid) {
+                    return Ok(order);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Fetch orders from mongo failed: {:?}", e),
+        }
+        self.query_exchange_order(&symbol, &req.order_cid).await
+    }
+
+    async fn query_exchange_order(&self, symbol: &str, cid: &str) -> Result<Order, StrategyError> {
+        let params = format!("symbol={}&origClientOrderId={}", symbol, cid);
+        let text = self.signed_get("/fapi/v1/order", &params).await?;
+        match self.parse_response_order(text) {
+            Some(order) => Ok(order),
+            None => Err(StrategyError::PlaceOrderError(
+                "Query order parse failed".to_string(),
+            )),
+        }
+    }
+
+    /// The OrderListener only stores closed orders, so open orders always come from REST.
+    /// Open orders stored by the OrderListener, from REST when Mongo fails. Orders
+    /// placed moments ago may not be stored yet.
+    async fn query_open_orders(&self, symbol: &str) -> Result<Vec<Order>, StrategyError> {
+        match self.db_client.fetch_open_orders().await {
+            Ok(orders) => {
+                return Ok(orders
+                    .into_iter()
+                    .filter(|o| symbol.is_empty() || o.get_symbol().eq_ignore_ascii_case(symbol))
+                    .collect());
+            }
+            Err(e) => warn!("Fetch open orders from mongo failed: {:?}", e),
+        }
+        self.query_exchange_open_orders(symbol).await
+    }
+
+    /// Open orders from REST, for cancels that must not miss an unstored order.
+    async fn query_exchange_open_orders(&self, symbol: &str) -> Result<Vec<Order>, StrategyError> {
+        let params = if symbol.is_empty() {
+            String::new()
+        } else {
+            format!("symbol={}", symbol.to_uppercase())
+        };
+        let text = self.signed_get("/fapi/v1/openOrders", &params).await?;
+        self.parse_response_orders("open orders", &text)
+    }
+
+    /// Open positions stored by the OrderListener, which replaces them all on resync
+    /// and keeps them up to date from the stream. From REST when Mongo fails.
+    async fn query_positions(&self, symbol: &str) -> Result<Vec<Position>, StrategyError> {
+        match self.db_client.fetch_positions().await {
+            Ok(positions) => {
+                return Ok(positions
+                    .into_iter()
+                    .filter(|p| p.get_quantity() != 0.0)
+                    .filter(|p| symbol.is_empty() || p.get_symbol().eq_ignore_ascii_case(symbol))
+                    .collect());
+            }
+            Err(e) => warn!("Fetch positions from mongo failed: {:?}", e),
+        }
+        let text = self.signed_get("/fapi/v2/positionRisk", "").await?;
+        let positions: Vec<Position> = match serde_json::from_str::<Vec<PositionResponse>>(&text) {
+            Ok(positions) => positions
+                .iter()
+                .map(|p| p.convert_into_position())
+                .collect(),
+            Err(e) => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Parse positions failed: {}",
+                    e
+                )));
+            }
+        };
+        Ok(positions
+            .into_iter()
+            .filter(|p| p.get_quantity() != 0.0)
+            .filter(|p| symbol.is_empty() || p.get_symbol().eq_ignore_ascii_case(symbol))
+            .collect())
+    }
+
+    /// USDT balance from Mongo, or from REST before the OrderListener stored one.
+    async fn query_balance(&self) -> Result<Balance, StrategyError> {
+        match self.db_client.get_balance().await {
+            Ok(Some(balance)) => return Ok(balance),
+            Ok(None) => {}
+            Err(e) => warn!("Fetch balance from mongo failed: {:?}", e),
+        }
+        let text = self.signed_get("/fapi/v2/balance", "").await?;
+        match serde_json::from_str::<Vec<BalanceResponse>>(&text) {
+            Ok(balances) => match balances.iter().find(|b| b.asset == "USDT") {
+                Some(balance) => Ok(balance.convert_into_balance()),
+                None => Err(StrategyError::PlaceOrderError(
+                    "No USDT balance".to_string(),
+                )),
+            },
+            Err(e) => Err(StrategyError::PlaceOrderError(format!(
+                "Parse balance failed: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Fills of the symbol from REST trade history, including partial fills of
+    /// canceled orders.
+    async fn query_trades(&self, symbol: &str) -> Result<Vec<Order>, StrategyError> {
+        let params = format!("symbol={}", symbol.to_uppercase());
+        let text = self.signed_get("/fapi/v1/userTrades", &params).await?;
+        let trades = match serde_json::from_str::<Vec<TradeResponse>>(&text) {
+            Ok(trades) => trades,
+            Err(e) => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Parse trades failed: {}",
+                    e
+                )));
+            }
+        };
+        // trades only carry the exchange order id
+        let text = self.signed_get("/fapi/v1/allOrders", &params).await?;
+        let orders: HashMap<String, Order> = self
+            .parse_response_orders("all orders", &text)?
+            .into_iter()
+            .map(|o| (o.get_oid().to_string(), o))
+            .collect();
+        Ok(trades
+            .iter()
+            .map(|t| match orders.get(&t.order_id.to_string()) {
+                Some(order) => t.convert_into_order(order.get_cid(), order.get_order_type()),
+                None => t.convert_into_order("", OrderType::Limit),
+            })
+            .collect())
+    }
+
+    /// Per-order results of a batch endpoint, in request order.
+    fn parse_batch_response(
+        &self,
+        data: &str,
+        len: usize,
+    ) -> Result<Vec<Result<Order, StrategyError>>, StrategyError> {
+        let items = match serde_json::from_str::<Vec<serde_json::Value>>(data) {
+            Ok(items) if items.len() == len => items,
+            Ok(items) => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Batch response has {} results for {} orders",
+                    items.len(),
+                    len
+                )));
+            }
+            Err(e) => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Parse batch response failed: {}",
+                    e
+                )));
+            }
+        };
+        Ok(items
+            .into_iter()
+            .map(|item| {
+                if let Ok(request_error) = serde_json::from_value::<RequestError>(item.clone()) {
+                    return Err(request_error.parse_request_error_into_strategy_error());
+                }
+                match serde_json::from_value::<OrderResponse>(item) {
+                    Ok(order) => Ok(order.order_response_into_order()),
+                    Err(e) => Err(StrategyError::PlaceOrderError(format!(
+                        "Parse batch order failed: {}",
+                        e
+                    ))),
+                }
+            })
+            .collect())
+    }
+
+    /// Places up to `MAX_BATCH_ORDERS` orders in one request. Orders failing
+    /// validation are not sent and get their error in place.
+    async fn make_batch_orders(
+        &self,
+        orders: &[MakeOrderRequest],
+    ) -> Result<Vec<Result<Order, StrategyError>>, StrategyError> {
+        if orders.is_empty() || orders.len() > MAX_BATCH_ORDERS {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Batch needs 1 to {} orders, got {}",
+                MAX_BATCH_ORDERS,
+                orders.len()
+            )));
+        }
+        let mut res: Vec<Option<Result<Order, StrategyError>>> = Vec::new();
+        let mut batch = Vec::new();
+        let mut accepted: Vec<(&MakeOrderRequest, i64)> = Vec::new();
+        let timestamp = time_tools::get_now_timestamp();
+        for (i, order) in orders.iter().enumerate() {
+            let cid = Self::generate_batch_cid(&order.symbol, &order.strategy, timestamp, i);
+            let fields =
+                match Self::convert_make_order_request_into_fields(order, OrderType::Limit, &cid) {
+                    Ok(fields) => fields,
+                    Err(e) => {
+                        res.push(Some(Err(e)));
+                        continue;
+                    }
+                };
+            match self.check_risk(order).await {
+                Ok(timestamp) => accepted.push((order, timestamp)),
+                Err(rejection) => {
+                    res.push(Some(Err(StrategyError::PlaceOrderError(format!(
+                        "Risk rejected {}",
+                        rejection
+                    )))));
+                    continue;
+                }
+            }
+            let fields: serde_json::Map<String, serde_json::Value> = fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), serde_json::Value::String(v)))
+                .collect();
+            batch.push(serde_json::Value::Object(fields));
+            res.push(None);
+        }
+        if !batch.is_empty() {
+            let params = format!(
+                "batchOrders={}",
+                api_tools::url_encode(&serde_json::Value::Array(batch.clone()).to_string())
+            );
+            let results = match self
+                .signed_request(reqwest::Method::POST, "/fapi/v1/batchOrders", &params)
+                .await
+                .and_then(|text| self.parse_batch_response(&text, batch.len()))
+            {
+                Ok(results) => results,
+                Err(e) => {
+                    for (order, timestamp) in accepted {
+                        self.release_risk(order, timestamp);
+                    }
+                    return Err(e);
+                }
+            };
+            for ((order, timestamp), result) in accepted.iter().zip(results.iter()) {
+                match result {
+                    Ok(placed) => self.ack_risk(order, *timestamp, placed.get_cid()),
+                    Err(_) => self.release_risk(order, *timestamp),
+                }
+            }
+            let mut results = results.into_iter();
+            for item in res.iter_mut().filter(|r| r.is_none()) {
+                *item = results.next();
+            }
+        }
+        Ok(res.into_iter().flatten().collect())
+    }
+
+    /// Cancels every open order of the symbol, returning the orders that were open
+    /// with their final status, since some may fill before the cancel. The cancel only
+    /// reports success, so each order is queried afterwards; failed queries keep the
+    /// status seen before the cancel.
+    async fn cancel_all_orders(&self, symbol: &str) -> Result<Vec<Order>, StrategyError> {
+        let symbol = symbol.to_uppercase();
+        let orders = self.query_exchange_open_orders(&symbol).await?;
+        let params = format!("symbol={}", symbol);
+        self.signed_request(reqwest::Method::DELETE, "/fapi/v1/allOpenOrders", &params)
+            .await?;
+        let mut res = Vec::with_capacity(orders.len());
+        for order in orders {
+            match self.query_exchange_order(&symbol, order.get_cid()).await {
+                Ok(order) => res.push(order),
+                Err(e) => {
+                    warn!("Query canceled order {} failed: {:?}", order.get_cid(), e);
+                    res.push(order);
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    /// Cancels the open orders placed by `strategy`, on one symbol or all of them when
+    /// `symbol` is empty, batching up to 10 cancels per symbol and request.
+    async fn cancel_strategy_orders(
+        &self,
+        strategy: &str,
+        symbol: &str,
+    ) -> Result<Vec<Result<Order, StrategyError>>, StrategyError> {
+        let mut orders_by_symbol: HashMap<String, Vec<String>> = HashMap::new();
+        for order in self.query_exchange_open_orders(symbol).await? {
+            if order.get_strategy_name() == strategy {
+                orders_by_symbol
+                    .entry(order.get_symbol().to_uppercase())
+                    .or_default()
+                    .push(order.get_cid().to_string());
+            }
+        }
+        let mut res = Vec::new();
+        for (symbol, cids) in orders_by_symbol {
+            for chunk in cids.chunks(10) {
+                let params = format!(
+                    "symbol={}&origClientOrderIdList={}",
+                    symbol,
+                    api_tools::url_encode(&serde_json::to_string(chunk).unwrap())
+                );
+                let text = self
+                    .signed_request(reqwest::Method::DELETE, "/fapi/v1/batchOrders", &params)
+                    .await?;
+                res.extend(self.parse_batch_response(&text, chunk.len())?);
+            }
+        }
+        Ok(res)
+    }
+
     pub async fn start_order_service(&mut self, path: &str) {
         let addr = "[::1]:50051".parse().unwrap();
         self.load_settings(path);
+        self.start_feeds();
         info!("start order service...");
         Server::builder()
             .add_service(OrderServiceServer::new(self.clone()))
@@ -397,14 +1366,185 @@ impl GeneralOrderService {
             .await
             .unwrap();
     }
-
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use public::base_enum::order_enums::OrderStatus;
+    use public::tools::settings_tools::SymbolRiskLimits;
     use tracing_subscriber;
+
+    #[test]
+    fn test_convert_make_order_request_into_params() {
+        let service = GeneralOrderService::default();
+        let req = MakeOrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: "BUY".to_string(),
+            price: 60000.0,
+            quantity: 0.01,
+            strategy: "test".to_string(),
+            time_in_force: "GTX".to_string(),
+            reduce_only: true,
+            ..Default::default()
+        };
+        let params = service
+            .convert_make_order_request_into_params(&req, OrderType::Limit)
+            .unwrap();
+        assert!(params.contains("&type=LIMIT&"));
+        assert!(params.contains("&quantity=0.01&price=60000&timeInForce=GTX"));
+        assert!(params.contains("&reduceOnly=true"));
+
+        let req = MakeOrderRequest {
+            order_type: "MARKET".to_string(),
+            ..req
+        };
+        assert!(service
+            .convert_make_order_request_into_params(&req, OrderType::Limit)
+            .is_err());
+
+        let req = MakeOrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: "SELL".to_string(),
+            price: 55000.0,
+            strategy: "test".to_string(),
+            close_position: true,
+            working_type: "MARK_PRICE".to_string(),
+            position_side: "LONG".to_string(),
+            ..Default::default()
+        };
+        let params = service
+            .convert_make_order_request_into_params(&req, OrderType::StopMarket)
+            .unwrap();
+        assert!(params.contains("&type=STOP_MARKET&"));
+        assert!(params.contains("&closePosition=true&stopPrice=55000&workingType=MARK_PRICE"));
+        assert!(params.contains("&positionSide=LONG"));
+        assert!(!params.contains("quantity"));
+        assert!(!params.contains("timeInForce"));
+    }
+
+    #[test]
+    fn test_batch_cid() {
+        let cid = GeneralOrderService::generate_batch_cid("BTCUSDT", "test", 1712534399000, 3);
+        assert_eq!(cid, "test_BTCUSDT_1712534399000_3");
+        let order = Order::new(
+            "BTCUSDT",
+            95.0,
+            1.0,
+            OrderSide::BUY,
+            OrderType::Limit,
+            0.0,
+            0.0,
+            &cid,
+            "1",
+            OrderStatus::New,
+            0,
+        );
+        assert_eq!(order.get_strategy_name(), "test");
+
+        let req = MakeOrderRequest {
+            symbol: "1000SHIBUSDT".to_string(),
+            side: "BUY".to_string(),
+            price: 0.02,
+            quantity: 1000.0,
+            strategy: "super_trend".to_string(),
+            ..Default::default()
+        };
+        let cid =
+            GeneralOrderService::generate_batch_cid(&req.symbol, &req.strategy, 1712534399000, 4);
+        assert!(GeneralOrderService::convert_make_order_request_into_fields(
+            &req,
+            OrderType::Limit,
+            &cid
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_batch_response() {
+        let service = GeneralOrderService::default();
+        let data = r#"[{"symbol":"BTCUSDT","price":"60000","origQty":"0.01","side":"BUY",
+            "avgPrice":"0.00","executedQty":"0","type":"LIMIT","orderId":1,
+            "clientOrderId":"test_BTCUSDT_17000000000000","status":"NEW","updateTime":1700000000000},
+            {"code":-2019,"msg":"Margin is insufficient."}]"#;
+        let results = service.parse_batch_response(data, 2).unwrap();
+        assert_eq!(
+            results[0].as_ref().unwrap().get_cid(),
+            "test_BTCUSDT_17000000000000"
+        );
+        assert!(results[1].is_err());
+        assert!(service.parse_batch_response(data, 3).is_err());
+    }
+    #[tokio::test]
+    async fn test_risk_rejection() {
+        let mut service = GeneralOrderService::default();
+        let limits = SymbolRiskLimits::new(1000.0, 0.0, 0, 0.0, 0.0);
+        service.set_risk_settings(RiskSettings::new(limits, HashMap::new(), 0.0));
+        let req = MakeOrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: "BUY".to_string(),
+            price: 60000.0,
+            quantity: 0.1,
+            strategy: "test".to_string(),
+            ..Default::default()
+        };
+        let status = service.make_order(Request::new(req)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        let rejection = RiskRejectionReply::decode(status.details()).unwrap();
+        assert_eq!(rejection.rule, "MAX_NOTIONAL");
+    }
+
+    #[tokio::test]
+    async fn test_daily_loss_from_feed() {
+        let mut service = GeneralOrderService::default();
+        let settings = RiskSettings::new(SymbolRiskLimits::default(), HashMap::new(), 100.0);
+        service.set_risk_settings(settings);
+        let (tx, _) = tokio::sync::broadcast::channel::<Order>(16);
+        service.set_order_sender(tx.clone());
+        service.start_feeds();
+
+        let timestamp = time_tools::get_now_timestamp();
+        for (side, price, cid, realized_pnl) in [
+            (OrderSide::BUY, 50000.0, "test_BTCUSDT_1", 0.0),
+            (OrderSide::SELL, 49800.0, "test_BTCUSDT_2", -200.0),
+        ] {
+            let mut fill = Order::new(
+                "BTCUSDT",
+                price,
+                1.0,
+                side,
+                OrderType::Limit,
+                price,
+                1.0,
+                cid,
+                "1",
+                OrderStatus::Filled,
+                timestamp,
+            );
+            fill.set_realized_pnl(realized_pnl);
+            tx.send(fill).unwrap();
+        }
+        for _ in 0..100 {
+            if service.risk_manager.lock().unwrap().get_daily_pnl("test") < 0.0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let req = MakeOrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: "BUY".to_string(),
+            price: 48000.0,
+            quantity: 0.1,
+            strategy: "test".to_string(),
+            ..Default::default()
+        };
+        let status = service.make_order(Request::new(req)).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+        let rejection = RiskRejectionReply::decode(status.details()).unwrap();
+        assert_eq!(rejection.rule, "DAILY_LOSS");
+    }
+
     #[tokio::test]
     async fn test_order_service() {
         tracing_subscriber::fmt::init();
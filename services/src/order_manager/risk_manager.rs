@@ -0,0 +1,581 @@
+use super::order_services::order_service::MakeOrderRequest;
+use public::base_enum::order_enums::OrderStatus;
+use public::base_model::trade_model::order_model::Order;
+use public::tools::settings_tools::RiskSettings;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use tracing::{info, warn};
+
+pub(crate) const DAY_MILLIS: i64 = 86_400_000;
+const RATE_WINDOW_MILLIS: i64 = 60_000;
+/// Accepted orders count as open for at most this long without an order update.
+const IN_FLIGHT_MILLIS: i64 = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RiskRule {
+    MaxNotional,
+    MaxPosition,
+    MaxOrderRate,
+    PriceBand,
+    MaxLeverage,
+    DailyLoss,
+}
+
+impl RiskRule {
+    pub fn string(&self) -> String {
+        match self {
+            RiskRule::MaxNotional => "MAX_NOTIONAL".to_string(),
+            RiskRule::MaxPosition => "MAX_POSITION".to_string(),
+            RiskRule::MaxOrderRate => "MAX_ORDER_RATE".to_string(),
+            RiskRule::PriceBand => "PRICE_BAND".to_string(),
+            RiskRule::MaxLeverage => "MAX_LEVERAGE".to_string(),
+            RiskRule::DailyLoss => "DAILY_LOSS".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RiskRejection {
+    rule: RiskRule,
+    reason: String,
+}
+
+impl RiskRejection {
+    pub fn new(rule: RiskRule, reason: String) -> Self {
+        Self { rule, reason }
+    }
+
+    pub fn get_rule(&self) -> RiskRule {
+        self.rule
+    }
+
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.rule.string(), self.reason)
+    }
+}
+
+/// Account state an order is checked against. Only the values needed by the
+/// configured limits have to be filled in, see `needs_position` and `needs_balance`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RiskSnapshot {
+    /// Signed position of the symbol.
+    pub position: f64,
+    /// Unfilled quantity of open buy orders. `RiskManager` adds the accepted orders
+    /// that are not open yet.
+    pub open_buy: f64,
+    /// Unfilled quantity of open sell orders, see `open_buy`.
+    pub open_sell: f64,
+    pub balance: f64,
+    pub last_price: Option<f64>,
+}
+
+/// An accepted order that may not be in the open orders of the snapshot yet.
+#[derive(Debug, Clone)]
+struct InFlightOrder {
+    symbol: String,
+    timestamp: i64,
+    cid: Option<String>,
+    signed_qty: f64,
+}
+
+/// Pre-trade checks of `GeneralOrderService`. Orders reducing exposure (reduce only or
+/// close position) are only subject to the order rate and price band. Realized pnl per
+/// strategy is summed from the order updates of the OrderListener on top of the pnl
+/// restored for the day, see `restore_daily_pnl`.
+/// Accepted orders count as open orders until their first order update, so concurrent
+/// orders cannot all pass the position and leverage limits.
+#[derive(Default)]
+pub struct RiskManager {
+    settings: RiskSettings,
+    order_times: HashMap<String, VecDeque<i64>>,
+    in_flight: Vec<InFlightOrder>,
+    last_prices: HashMap<String, f64>,
+    fills: HashMap<String, Order>,
+    daily_pnl: HashMap<String, f64>,
+    pnl_day: i64,
+}
+
+impl RiskManager {
+    pub fn new(settings: RiskSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: RiskSettings) {
+        self.settings = settings;
+    }
+
+    pub fn needs_position(&self, symbol: &str) -> bool {
+        let limits = self.settings.get_symbol_limits(symbol);
+        limits.get_max_position() > 0.0 || limits.get_max_leverage() > 0.0
+    }
+
+    pub fn needs_balance(&self, symbol: &str) -> bool {
+        self.settings.get_symbol_limits(symbol).get_max_leverage() > 0.0
+    }
+
+    /// Price bands and orders without a price to value them at need the last trade.
+    pub fn needs_last_price(&self, req: &MakeOrderRequest) -> bool {
+        let limits = self.settings.get_symbol_limits(&req.symbol);
+        let has_price = req.price > 0.0 || req.stop_price > 0.0;
+        limits.get_price_band() > 0.0
+            || (!has_price && (limits.get_max_notional() > 0.0 || limits.get_max_leverage() > 0.0))
+    }
+
+    pub fn get_last_price(&self, symbol: &str) -> Option<f64> {
+        self.last_prices.get(&symbol.to_uppercase()).copied()
+    }
+
+    pub fn set_last_price(&mut self, symbol: &str, price: f64) {
+        self.last_prices.insert(symbol.to_uppercase(), price);
+    }
+
+    pub fn get_daily_pnl(&self, strategy: &str) -> f64 {
+        self.daily_pnl.get(strategy).copied().unwrap_or(0.0)
+    }
+
+    /// UTC day of the daily pnl, counted in days since the epoch.
+    pub fn get_pnl_day(&self) -> i64 {
+        self.pnl_day
+    }
+
+    /// Adds the pnl stored for the day of `timestamp` before a restart to the pnl
+    /// tracked since.
+    pub fn restore_daily_pnl(&mut self, timestamp: i64, daily_pnl: HashMap<String, f64>) {
+        self.roll_day(timestamp);
+        for (strategy, pnl) in daily_pnl {
+            info!("Restore daily pnl of {}: {}", strategy, pnl);
+            *self.daily_pnl.entry(strategy).or_default() += pnl;
+        }
+    }
+
+    fn roll_day(&mut self, timestamp: i64) {
+        let day = timestamp / DAY_MILLIS;
+        if day != self.pnl_day {
+            self.pnl_day = day;
+            self.daily_pnl.clear();
+        }
+    }
+
+    /// Checks the order and counts it towards the order rate and the open orders if
+    /// accepted, see `ack_order` and `release_order`.
+    pub fn check_order(
+        &mut self,
+        req: &MakeOrderRequest,
+        snapshot: &RiskSnapshot,
+        timestamp: i64,
+    ) -> Result<(), RiskRejection> {
+        self.roll_day(timestamp);
+        let symbol = req.symbol.to_uppercase();
+        self.in_flight
+            .retain(|o| o.timestamp > timestamp - IN_FLIGHT_MILLIS);
+        let mut snapshot = *snapshot;
+        for order in self.in_flight.iter().filter(|o| o.symbol == symbol) {
+            if order.signed_qty > 0.0 {
+                snapshot.open_buy += order.signed_qty;
+            } else {
+                snapshot.open_sell -= order.signed_qty;
+            }
+        }
+        match self.evaluate_order(&symbol, req, &snapshot, timestamp) {
+            Ok(_) => {
+                info!(
+                    "Risk accepted {} {} order of {} | px: {} | qty: {}",
+                    symbol, req.side, req.strategy, req.price, req.quantity
+                );
+                if !req.reduce_only && !req.close_position {
+                    let signed_qty = match req.side.as_str() {
+                        "BUY" => req.quantity,
+                        _ => -req.quantity,
+                    };
+                    self.in_flight.push(InFlightOrder {
+                        symbol: symbol.clone(),
+                        timestamp,
+                        cid: None,
+                        signed_qty,
+                    });
+                }
+                self.order_times
+                    .entry(symbol)
+                    .or_default()
+                    .push_back(timestamp);
+                Ok(())
+            }
+            Err(rejection) => {
+                warn!(
+                    "Risk rejected {} {} order of {} | px: {} | qty: {} | {}",
+                    symbol, req.side, req.strategy, req.price, req.quantity, rejection
+                );
+                Err(rejection)
+            }
+        }
+    }
+
+    /// Removes an accepted order from the order rate and the open orders when placing
+    /// it failed.
+    pub fn release_order(&mut self, symbol: &str, timestamp: i64) {
+        let symbol = symbol.to_uppercase();
+        if let Some(times) = self.order_times.get_mut(&symbol) {
+            if let Some(i) = times.iter().position(|t| *t == timestamp) {
+                times.remove(i);
+            }
+        }
+        if let Some(i) = self.find_in_flight(&symbol, timestamp) {
+            self.in_flight.remove(i);
+        }
+    }
+
+    /// Ties an accepted order the exchange acknowledged to its client id. It stays
+    /// counted as open until the order update of the id arrives.
+    pub fn ack_order(&mut self, symbol: &str, timestamp: i64, cid: &str) {
+        if let Some(i) = self.find_in_flight(&symbol.to_uppercase(), timestamp) {
+            self.in_flight[i].cid = Some(cid.to_string());
+        }
+    }
+
+    fn find_in_flight(&self, symbol: &str, timestamp: i64) -> Option<usize> {
+        self.in_flight
+            .iter()
+            .position(|o| o.cid.is_none() && o.symbol == symbol && o.timestamp == timestamp)
+    }
+
+    fn evaluate_order(
+        &mut self,
+        symbol: &str,
+        req: &MakeOrderRequest,
+        snapshot: &RiskSnapshot,
+        timestamp: i64,
+    ) -> Result<(), RiskRejection> {
+        let limits = self.settings.get_symbol_limits(symbol).clone();
+        let reduces = req.reduce_only || req.close_position;
+
+        let max_daily_loss = self.settings.get_max_daily_loss();
+        let daily_pnl = self.get_daily_pnl(&req.strategy);
+        if max_daily_loss > 0.0 && !reduces && daily_pnl <= -max_daily_loss {
+            return Err(RiskRejection::new(
+                RiskRule::DailyLoss,
+                format!(
+                    "Daily pnl {} of {} exceeds loss limit {}",
+                    daily_pnl, req.strategy, max_daily_loss
+                ),
+            ));
+        }
+
+        if limits.get_max_orders_per_minute() > 0 {
+            let times = self.order_times.entry(symbol.to_string()).or_default();
+            while times
+                .front()
+                .is_some_and(|t| *t <= timestamp - RATE_WINDOW_MILLIS)
+            {
+                times.pop_front();
+            }
+            if times.len() >= limits.get_max_orders_per_minute() {
+                return Err(RiskRejection::new(
+                    RiskRule::MaxOrderRate,
+                    format!(
+                        "{} orders in the last minute, limit {}",
+                        times.len(),
+                        limits.get_max_orders_per_minute()
+                    ),
+                ));
+            }
+        }
+
+        if limits.get_price_band() > 0.0 && req.price > 0.0 {
+            match snapshot.last_price {
+                Some(last_price) if last_price > 0.0 => {
+                    let deviation = (req.price / last_price - 1.0).abs();
+                    if deviation > limits.get_price_band() {
+                        return Err(RiskRejection::new(
+                            RiskRule::PriceBand,
+                            format!(
+                                "Price {} deviates {:.4} from last trade {}, band {}",
+                                req.price,
+                                deviation,
+                                last_price,
+                                limits.get_price_band()
+                            ),
+                        ));
+                    }
+                }
+                _ => {
+                    return Err(RiskRejection::new(
+                        RiskRule::PriceBand,
+                        "No last trade price".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if req.close_position {
+            return Ok(());
+        }
+        // market orders are valued at the last trade
+        let ref_price = if req.price > 0.0 {
+            Some(req.price)
+        } else if req.stop_price > 0.0 {
+            Some(req.stop_price)
+        } else {
+            snapshot.last_price
+        };
+        let notional = ref_price.map(|px| px * req.quantity);
+
+        if limits.get_max_notional() > 0.0 {
+            match notional {
+                Some(notional) if notional <= limits.get_max_notional() => {}
+                Some(notional) => {
+                    return Err(RiskRejection::new(
+                        RiskRule::MaxNotional,
+                        format!(
+                            "Notional {} exceeds limit {}",
+                            notional,
+                            limits.get_max_notional()
+                        ),
+                    ));
+                }
+                None => {
+                    return Err(RiskRejection::new(
+                        RiskRule::MaxNotional,
+                        "No price to value the order".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if reduces {
+            return Ok(());
+        }
+        // open orders on the same side fill into the position as well
+        let (position, signed_qty) = match req.side.as_str() {
+            "BUY" => (snapshot.position + snapshot.open_buy, req.quantity),
+            _ => (snapshot.position - snapshot.open_sell, -req.quantity),
+        };
+        let new_position = position + signed_qty;
+        if limits.get_max_position() > 0.0
+            && new_position.abs() > limits.get_max_position()
+            && new_position.abs() > position.abs()
+        {
+            return Err(RiskRejection::new(
+                RiskRule::MaxPosition,
+                format!(
+                    "Position {} with open orders exceeds limit {}",
+                    new_position,
+                    limits.get_max_position()
+                ),
+            ));
+        }
+
+        if limits.get_max_leverage() > 0.0 && new_position.abs() > position.abs() {
+            let leverage = match ref_price {
+                Some(px) if snapshot.balance > 0.0 => new_position.abs() * px / snapshot.balance,
+                _ => f64::INFINITY,
+            };
+            if leverage > limits.get_max_leverage() {
+                return Err(RiskRejection::new(
+                    RiskRule::MaxLeverage,
+                    format!(
+                        "Leverage {:.2} on balance {} exceeds limit {}",
+                        leverage,
+                        snapshot.balance,
+                        limits.get_max_leverage()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the realized pnl and fee of an order update with a new fill to the
+    /// strategy's daily pnl, returning the strategy if its pnl changed.
+    pub fn on_order_update(&mut self, order: &Order) -> Option<String> {
+        self.roll_day(order.get_timestamp());
+        let cid = order.get_cid().to_string();
+        // the order is in the open orders or done from now on
+        self.in_flight
+            .retain(|o| o.cid.as_deref() != Some(order.get_cid()));
+        let (prev_qty, prev_amount) = match self.fills.get(&cid) {
+            Some(prev) => (
+                prev.get_filled_qty(),
+                prev.get_avg_price() * prev.get_filled_qty(),
+            ),
+            None => (0.0, 0.0),
+        };
+        let delta_qty = order.get_filled_qty() - prev_qty;
+        if delta_qty > 0.0 {
+            let delta_px =
+                (order.get_avg_price() * order.get_filled_qty() - prev_amount) / delta_qty;
+            self.set_last_price(order.get_symbol(), delta_px);
+        }
+        match order.get_status() {
+            OrderStatus::New | OrderStatus::PartiallyFilled => {
+                self.fills.insert(cid, order.clone());
+            }
+            _ => {
+                self.fills.remove(&cid);
+            }
+        }
+        if delta_qty <= 0.0 {
+            return None;
+        }
+        let strategy = order.get_strategy_name();
+        *self.daily_pnl.entry(strategy.clone()).or_default() +=
+            order.get_realized_pnl() - order.get_fee();
+        Some(strategy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use public::base_enum::order_enums::{OrderSide, OrderType};
+    use public::tools::settings_tools::SymbolRiskLimits;
+
+    fn request(side: &str, price: f64, quantity: f64) -> MakeOrderRequest {
+        MakeOrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: side.to_string(),
+            price,
+            quantity,
+            strategy: "test".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn fill(side: OrderSide, price: f64, qty: f64, cid: &str, realized_pnl: f64) -> Order {
+        let mut order = Order::new(
+            "BTCUSDT",
+            price,
+            qty,
+            side,
+            OrderType::Limit,
+            price,
+            qty,
+            cid,
+            "1",
+            OrderStatus::Filled,
+            1_000,
+        );
+        order.set_realized_pnl(realized_pnl);
+        order
+    }
+
+    #[test]
+    fn test_check_order() {
+        let limits = SymbolRiskLimits::new(10000.0, 0.2, 2, 0.05, 5.0);
+        let mut risk = RiskManager::new(RiskSettings::new(limits, HashMap::new(), 100.0));
+        let snapshot = RiskSnapshot {
+            position: 0.1,
+            balance: 2000.0,
+            last_price: Some(50000.0),
+            ..Default::default()
+        };
+        let rule = |res: Result<(), RiskRejection>| res.unwrap_err().get_rule();
+
+        assert!(risk
+            .check_order(&request("BUY", 50000.0, 0.1), &snapshot, 0)
+            .is_ok());
+        // the accepted buy counts as open until its order update arrives
+        assert_eq!(
+            rule(risk.check_order(&request("BUY", 50000.0, 0.1), &snapshot, 0)),
+            RiskRule::MaxPosition
+        );
+        risk.ack_order("btcusdt", 0, "test_BTCUSDT_1");
+        risk.on_order_update(&fill(OrderSide::BUY, 50000.0, 0.1, "test_BTCUSDT_1", 0.0));
+        assert_eq!(
+            rule(risk.check_order(&request("BUY", 50000.0, 0.3), &snapshot, 0)),
+            RiskRule::MaxNotional
+        );
+        assert_eq!(
+            rule(risk.check_order(&request("BUY", 40000.0, 0.1), &snapshot, 0)),
+            RiskRule::PriceBand
+        );
+        let snapshot = RiskSnapshot {
+            position: 0.15,
+            ..snapshot
+        };
+        assert_eq!(
+            rule(risk.check_order(&request("BUY", 50000.0, 0.1), &snapshot, 0)),
+            RiskRule::MaxPosition
+        );
+        // open buys count towards the position, open sells do not
+        let pending = RiskSnapshot {
+            position: 0.05,
+            open_buy: 0.1,
+            open_sell: 0.2,
+            ..snapshot
+        };
+        assert_eq!(
+            rule(risk.check_order(&request("BUY", 50000.0, 0.1), &pending, 0)),
+            RiskRule::MaxPosition
+        );
+        let snapshot = RiskSnapshot {
+            balance: 1000.0,
+            ..snapshot
+        };
+        assert_eq!(
+            rule(risk.check_order(&request("BUY", 50000.0, 0.04), &snapshot, 0)),
+            RiskRule::MaxLeverage
+        );
+        // selling reduces the position and passes
+        assert!(risk
+            .check_order(&request("SELL", 50000.0, 0.1), &snapshot, 0)
+            .is_ok());
+        assert_eq!(
+            rule(risk.check_order(&request("SELL", 50000.0, 0.1), &snapshot, 0)),
+            RiskRule::MaxOrderRate
+        );
+        // a released order frees its slot
+        risk.release_order("btcusdt", 0);
+        assert!(risk
+            .check_order(&request("SELL", 50000.0, 0.1), &snapshot, 1)
+            .is_ok());
+        assert!(risk
+            .check_order(
+                &request("SELL", 50000.0, 0.1),
+                &snapshot,
+                RATE_WINDOW_MILLIS + 1
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_daily_loss() {
+        let settings = RiskSettings::new(SymbolRiskLimits::default(), HashMap::new(), 100.0);
+        let mut risk = RiskManager::new(settings);
+        risk.on_order_update(&fill(OrderSide::BUY, 50000.0, 1.0, "test_BTCUSDT_1", 0.0));
+        let update = fill(OrderSide::SELL, 49800.0, 1.0, "test_BTCUSDT_2", -200.0);
+        assert_eq!(risk.on_order_update(&update), Some("test".to_string()));
+        assert_eq!(risk.get_daily_pnl("test"), -200.0);
+        assert_eq!(risk.get_last_price("btcusdt"), Some(49800.0));
+
+        let snapshot = RiskSnapshot::default();
+        let res = risk.check_order(&request("BUY", 48000.0, 0.1), &snapshot, 1_000);
+        assert_eq!(res.unwrap_err().get_rule(), RiskRule::DailyLoss);
+        let reduce = MakeOrderRequest {
+            reduce_only: true,
+            ..request("SELL", 48000.0, 0.1)
+        };
+        assert!(risk.check_order(&reduce, &snapshot, 1_000).is_ok());
+        // the loss limit resets on the next UTC day
+        assert!(risk
+            .check_order(&request("BUY", 48000.0, 0.1), &snapshot, DAY_MILLIS)
+            .is_ok());
+
+        // after a restart the stored loss of the day still counts
+        let mut risk = RiskManager::new(risk.settings.clone());
+        let update = fill(OrderSide::SELL, 49800.0, 1.0, "test_BTCUSDT_3", -50.0);
+        risk.on_order_update(&update);
+        risk.restore_daily_pnl(1_000, HashMap::from([("test".to_string(), -60.0)]));
+        assert_eq!(risk.get_daily_pnl("test"), -110.0);
+        let res = risk.check_order(&request("BUY", 48000.0, 0.1), &snapshot, 1_000);
+        assert_eq!(res.unwrap_err().get_rule(), RiskRule::DailyLoss);
+    }
+}
@@ -0,0 +1,1293 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use super::order_services::order_service::order_service_server::{
+    OrderService, OrderServiceServer,
+};
+use super::order_services::order_service::{
+    BalanceReply, BatchOrderReply, CancelOrderRequest, CancelStrategyOrdersRequest,
+    GetOrderRequest, MakeBatchOrdersRequest, MakeOrderReply, MakeOrderRequest, OrderListReply,
+    PositionListReply, QueryRequest, SubscribeUpdatesRequest,
+};
+use super::order_services::{
+    convert_order_into_reply, convert_orders_into_reply, convert_position_into_reply,
+    convert_results_into_batch_reply,
+    parse_conditional_order_type, subscribe_update_stream, UpdateStream, MAX_BATCH_ORDERS,
+};
+use public::base_enum::order_enums::{OrderSide, OrderStatus, OrderType, TimeInForce};
+use public::base_model::api_model::MarketData;
+use public::base_model::error_model::StrategyError;
+use public::base_model::market_model::depth_model::Depth;
+use public::base_model::market_model::kline_model::Kline;
+use public::base_model::trade_model::order_model::Order;
+use public::base_model::trade_model::position_model::Position;
+use public::strategy_model::strategy_portfolio::Balance;
+use public::tools::time_tools;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+use tonic::transport::server::TcpIncoming;
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::{error, info, warn};
+
+/// Order options of the Binance API honoured by `SimulatedExchange`.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderOptions {
+    pub time_in_force: TimeInForce,
+    pub reduce_only: bool,
+    pub close_position: bool,
+}
+
+impl Default for OrderOptions {
+    fn default() -> Self {
+        Self {
+            time_in_force: TimeInForce::GTC,
+            reduce_only: false,
+            close_position: false,
+        }
+    }
+}
+
+/// In-process matching engine used by `SimulatedOrderService`.
+/// Resting orders are matched against klines (high/low crossing) and depth
+/// snapshots (best bid/ask crossing). Every state change is returned as an
+/// `Order`, mirroring the `ORDER_TRADE_UPDATE` events parsed by the order listener.
+/// Fills update a one-way net position per symbol, which caps reduce only orders, and
+/// the wallet balance by their fee and realized pnl.
+pub struct SimulatedExchange {
+    maker_fee_rate: f64,
+    taker_fee_rate: f64,
+    balance: f64,
+    balance_update_time: i64,
+    order_seq: i64,
+    open_orders: HashMap<String, Vec<Order>>,
+    closed_orders: HashMap<String, Vec<Order>>,
+    last_prices: HashMap<String, f64>,
+    positions: HashMap<String, Position>,
+    reduce_only_cids: HashSet<String>,
+    close_position_cids: HashSet<String>,
+}
+
+impl Default for SimulatedExchange {
+    fn default() -> Self {
+        Self {
+            maker_fee_rate: 0.0002,
+            taker_fee_rate: 0.0005,
+            balance: 10000.0,
+            balance_update_time: 0,
+            order_seq: 0,
+            open_orders: HashMap::new(),
+            closed_orders: HashMap::new(),
+            last_prices: HashMap::new(),
+            positions: HashMap::new(),
+            reduce_only_cids: HashSet::new(),
+            close_position_cids: HashSet::new(),
+        }
+    }
+}
+
+impl SimulatedExchange {
+    pub fn set_fee_rate(&mut self, maker_fee_rate: f64, taker_fee_rate: f64) {
+        self.maker_fee_rate = maker_fee_rate;
+        self.taker_fee_rate = taker_fee_rate;
+    }
+
+    /// Starting wallet balance in USDT, 10000 by default.
+    pub fn set_balance(&mut self, balance: f64) {
+        self.balance = balance;
+        self.balance_update_time = time_tools::get_now_timestamp();
+    }
+
+    pub fn get_balance(&self) -> Balance {
+        let mut balance = Balance::default();
+        balance.set_balance(self.balance);
+        balance.set_update_time(self.balance_update_time);
+        balance
+    }
+
+    /// Open positions of the symbol, or of every symbol when empty.
+    pub fn get_positions(&self, symbol: &str) -> Vec<Position> {
+        let key = symbol.to_lowercase();
+        self.positions
+            .values()
+            .filter(|p| p.get_quantity() != 0.0)
+            .filter(|p| key.is_empty() || p.get_symbol() == key)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_open_orders(&self, symbol: &str) -> Vec<Order> {
+        self.open_orders
+            .get(&symbol.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Open, filled or canceled order with the client id.
+    pub fn get_order(&self, symbol: &str, cid: &str) -> Option<Order> {
+        let key = symbol.to_lowercase();
+        self.open_orders
+            .get(&key)
+            .into_iter()
+            .chain(self.closed_orders.get(&key))
+            .flatten()
+            .find(|o| o.get_cid() == cid)
+            .cloned()
+    }
+
+    pub fn get_filled_orders(&self, symbol: &str) -> Vec<Order> {
+        self.closed_orders
+            .get(&symbol.to_lowercase())
+            .into_iter()
+            .flatten()
+            .filter(|o| o.get_status() == OrderStatus::Filled)
+            .cloned()
+            .collect()
+    }
+
+    fn close_order(&mut self, order: &Order) {
+        self.reduce_only_cids.remove(order.get_cid());
+        self.close_position_cids.remove(order.get_cid());
+        self.closed_orders
+            .entry(order.get_symbol().to_lowercase())
+            .or_default()
+            .push(order.clone());
+    }
+
+    pub fn get_last_price(&self, symbol: &str) -> Option<f64> {
+        self.last_prices.get(&symbol.to_lowercase()).copied()
+    }
+
+    /// Net position of the symbol, positive for long and negative for short.
+    pub fn get_net_position(&self, symbol: &str) -> f64 {
+        self.positions
+            .get(&symbol.to_lowercase())
+            .map_or(0.0, |p| p.get_signed_quantity())
+    }
+
+    /// Quantity an order of `side` can close of the net position.
+    fn get_reducible_qty(&self, symbol: &str, side: OrderSide) -> f64 {
+        let net_position = self.get_net_position(symbol);
+        match side {
+            OrderSide::BUY => (-net_position).max(0.0),
+            OrderSide::SELL => net_position.max(0.0),
+        }
+    }
+
+    fn generate_cid(&self, symbol: &str, strategy: &str) -> String {
+        format!(
+            "{}_{}_{}_{}",
+            strategy,
+            symbol,
+            time_tools::get_now_timestamp(),
+            self.order_seq
+        )
+    }
+
+    pub fn place_order(
+        &mut self,
+        symbol: &str,
+        side: OrderSide,
+        order_type: OrderType,
+        price: f64,
+        quantity: f64,
+        strategy: &str,
+    ) -> Result<Vec<Order>, StrategyError> {
+        self.place_order_with_options(
+            symbol,
+            side,
+            order_type,
+            price,
+            quantity,
+            strategy,
+            &OrderOptions::default(),
+        )
+    }
+
+    /// Market orders and limit orders crossing the last price fill immediately as taker.
+    /// IOC and FOK limit orders that do not cross expire, GTX ones that cross are
+    /// rejected. Reduce only orders are capped at the opposite net position when placed
+    /// and when filled, close position orders take the whole position when triggered.
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_order_with_options(
+        &mut self,
+        symbol: &str,
+        side: OrderSide,
+        order_type: OrderType,
+        price: f64,
+        quantity: f64,
+        strategy: &str,
+        options: &OrderOptions,
+    ) -> Result<Vec<Order>, StrategyError> {
+        match order_type {
+            OrderType::Limit
+            | OrderType::Market
+            | OrderType::StopMarket
+            | OrderType::TakeProfitMarket => {}
+            _ => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Unsupported order type: {}",
+                    order_type.string()
+                )));
+            }
+        }
+        if options.close_position && !order_type.is_conditional() {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Close position on a {} order",
+                order_type.string()
+            )));
+        }
+        // market orders have no price, close position orders no quantity
+        if (quantity <= 0.0 && !options.close_position)
+            || (price <= 0.0 && !matches!(order_type, OrderType::Market))
+        {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Invalid order px: {} | qty: {}",
+                price, quantity
+            )));
+        }
+
+        let key = symbol.to_lowercase();
+        let reducible_qty = self.get_reducible_qty(&key, side);
+        let quantity = if options.close_position {
+            reducible_qty
+        } else if options.reduce_only {
+            if reducible_qty <= 0.0 {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Reduce only {} order on {} net position {}",
+                    side.string(),
+                    symbol,
+                    self.get_net_position(&key)
+                )));
+            }
+            quantity.min(reducible_qty)
+        } else {
+            quantity
+        };
+        let last_price = self.get_last_price(&key);
+        let taker_price = match order_type {
+            OrderType::Market => match last_price {
+                Some(last_price) => Some(last_price),
+                None => {
+                    return Err(StrategyError::PlaceOrderError(format!(
+                        "No last price to fill market order on {}",
+                        symbol
+                    )));
+                }
+            },
+            OrderType::Limit => last_price.filter(|last_price| {
+                (side == OrderSide::BUY && *last_price <= price)
+                    || (side == OrderSide::SELL && *last_price >= price)
+            }),
+            _ => None,
+        };
+        let is_limit = matches!(order_type, OrderType::Limit);
+        if let (true, TimeInForce::GTX, Some(taker_price)) =
+            (is_limit, options.time_in_force, taker_price)
+        {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Post only order px: {} would cross last price {}",
+                price, taker_price
+            )));
+        }
+
+        self.order_seq += 1;
+        let cid = self.generate_cid(symbol, strategy);
+        let order = Order::new(
+            &symbol.to_uppercase(),
+            price,
+            quantity,
+            side,
+            order_type,
+            0.0,
+            0.0,
+            &cid,
+            &self.order_seq.to_string(),
+            OrderStatus::New,
+            time_tools::get_now_timestamp(),
+        );
+        if options.close_position {
+            self.close_position_cids.insert(cid);
+        } else if options.reduce_only {
+            self.reduce_only_cids.insert(cid);
+        }
+        let mut events = vec![order.clone()];
+        match taker_price {
+            Some(taker_price) => {
+                events.push(self.execute_order(order, taker_price, self.taker_fee_rate));
+            }
+            None if is_limit
+                && matches!(options.time_in_force, TimeInForce::IOC | TimeInForce::FOK) =>
+            {
+                events.push(self.expire_order(order));
+            }
+            None => self.open_orders.entry(key).or_default().push(order),
+        }
+        Ok(events)
+    }
+
+    pub fn cancel_order(&mut self, symbol: &str, cid: &str) -> Result<Order, StrategyError> {
+        let orders = self.open_orders.entry(symbol.to_lowercase()).or_default();
+        match orders.iter().position(|o| o.get_cid() == cid) {
+            Some(idx) => {
+                let mut order = orders.remove(idx);
+                order.set_status(OrderStatus::Canceled);
+                order.set_timestamp(time_tools::get_now_timestamp());
+                self.close_order(&order);
+                Ok(order)
+            }
+            None => Err(StrategyError::PlaceOrderError(format!(
+                "Unknown order: {} {}",
+                symbol, cid
+            ))),
+        }
+    }
+
+    /// Cancels the open orders of the symbol, or of every symbol when empty, placed by
+    /// `strategy` if given.
+    pub fn cancel_open_orders(&mut self, symbol: &str, strategy: Option<&str>) -> Vec<Order> {
+        let symbols: Vec<String> = match symbol {
+            "" => self.open_orders.keys().cloned().collect(),
+            _ => vec![symbol.to_lowercase()],
+        };
+        let mut canceled = Vec::new();
+        for key in symbols {
+            let cids: Vec<String> = self
+                .open_orders
+                .get(&key)
+                .into_iter()
+                .flatten()
+                .filter(|o| strategy.is_none_or(|s| o.get_strategy_name() == s))
+                .map(|o| o.get_cid().to_string())
+                .collect();
+            for cid in cids {
+                if let Ok(order) = self.cancel_order(&key, &cid) {
+                    canceled.push(order);
+                }
+            }
+        }
+        canceled
+    }
+
+    /// Fills the order, first capping reduce only and close position orders at the
+    /// net position they can close. Expires them when there is nothing to close.
+    fn execute_order(&mut self, mut order: Order, price: f64, fee_rate: f64) -> Order {
+        let reducible_qty = self.get_reducible_qty(order.get_symbol(), order.get_side());
+        if self.close_position_cids.contains(order.get_cid()) {
+            order.set_qty(reducible_qty);
+        } else if self.reduce_only_cids.contains(order.get_cid()) {
+            order.set_qty(order.get_qty().min(reducible_qty));
+        }
+        if order.get_qty() <= 0.0 {
+            return self.expire_order(order);
+        }
+        self.fill_order(order, price, fee_rate)
+    }
+
+    fn fill_order(&mut self, mut order: Order, price: f64, fee_rate: f64) -> Order {
+        order.set_avg_price(price);
+        order.set_filled_qty(order.get_qty());
+        order.set_fee(price * order.get_qty() * fee_rate);
+        order.set_status(OrderStatus::Filled);
+        order.set_timestamp(time_tools::get_now_timestamp());
+        let key = order.get_symbol().to_lowercase();
+        let realized_pnl = self
+            .positions
+            .entry(key.clone())
+            .or_insert_with(|| {
+                Position::new(&key, 0.0, 0.0, OrderSide::BUY, 0.0, 1.0, 0.0, 0.0, 0.0, 0)
+            })
+            .update_order(&order);
+        order.set_realized_pnl(realized_pnl);
+        self.balance += realized_pnl - order.get_fee();
+        self.balance_update_time = order.get_timestamp();
+        self.close_order(&order);
+        order
+    }
+
+    fn expire_order(&mut self, mut order: Order) -> Order {
+        order.set_status(OrderStatus::Expired);
+        order.set_timestamp(time_tools::get_now_timestamp());
+        self.close_order(&order);
+        order
+    }
+
+    /// Returns the fill price of an order within a price range `[low, high]`,
+    /// or None if the order is not triggered.
+    fn match_price(order: &Order, low: f64, high: f64) -> Option<f64> {
+        let price = order.get_price();
+        match (order.get_order_type(), order.get_side()) {
+            (OrderType::Limit, OrderSide::BUY) if low <= price => Some(price.min(high)),
+            (OrderType::Limit, OrderSide::SELL) if high >= price => Some(price.max(low)),
+            (OrderType::StopMarket, OrderSide::BUY) if high >= price => Some(price.max(low)),
+            (OrderType::StopMarket, OrderSide::SELL) if low <= price => Some(price.min(high)),
+            (OrderType::TakeProfitMarket, OrderSide::BUY) if low <= price => Some(price.min(high)),
+            (OrderType::TakeProfitMarket, OrderSide::SELL) if high >= price => Some(price.max(low)),
+            _ => None,
+        }
+    }
+
+    /// Buy and sell orders are matched against separate ranges so depth snapshots
+    /// can match buys on the ask and sells on the bid.
+    fn match_orders(
+        &mut self,
+        symbol: &str,
+        buy_range: (f64, f64),
+        sell_range: (f64, f64),
+    ) -> Vec<Order> {
+        let mut events = Vec::new();
+        let orders = match self.open_orders.remove(symbol) {
+            Some(orders) => orders,
+            None => return events,
+        };
+        let mut remain_orders = Vec::new();
+        for order in orders {
+            let (low, high) = match order.get_side() {
+                OrderSide::BUY => buy_range,
+                OrderSide::SELL => sell_range,
+            };
+            match Self::match_price(&order, low, high) {
+                Some(price) => {
+                    let fee_rate = match order.get_order_type() {
+                        OrderType::Limit => self.maker_fee_rate,
+                        _ => self.taker_fee_rate,
+                    };
+                    events.push(self.execute_order(order, price, fee_rate));
+                }
+                None => remain_orders.push(order),
+            }
+        }
+        self.open_orders.insert(symbol.to_string(), remain_orders);
+        events
+    }
+
+    /// Marks the position of the symbol to the last price.
+    fn update_last_price(&mut self, symbol: String, price: f64) {
+        if let Some(position) = self.positions.get_mut(&symbol) {
+            position.update_market_price(price);
+        }
+        self.last_prices.insert(symbol, price);
+    }
+
+    pub fn on_kline(&mut self, symbol: &str, kline: &Kline) -> Vec<Order> {
+        let symbol = symbol.to_lowercase();
+        let range = (kline.get_low(), kline.get_high());
+        let events = self.match_orders(&symbol, range, range);
+        self.update_last_price(symbol, kline.get_close());
+        events
+    }
+
+    pub fn on_depth(&mut self, symbol: &str, depth: &Depth) -> Vec<Order> {
+        let symbol = symbol.to_lowercase();
+        let best_ask = depth
+            .get_asks()
+            .iter()
+            .map(|l| l.get_price())
+            .fold(f64::MAX, f64::min);
+        let best_bid = depth
+            .get_bids()
+            .iter()
+            .map(|l| l.get_price())
+            .fold(f64::MIN, f64::max);
+        if best_ask == f64::MAX || best_bid == f64::MIN {
+            return Vec::new();
+        }
+        let events = self.match_orders(&symbol, (best_ask, best_ask), (best_bid, best_bid));
+        self.update_last_price(symbol, (best_ask + best_bid) / 2.0);
+        events
+    }
+
+    pub fn on_market_data(&mut self, market_data: &MarketData) -> Vec<Order> {
+        if let Some(kline) = market_data.get_kline() {
+            return self.on_kline(market_data.get_symbol(), &kline);
+        }
+        if let Some(depth) = market_data.get_depth() {
+            return self.on_depth(market_data.get_symbol(), &depth);
+        }
+        Vec::new()
+    }
+}
+
+/// `OrderService` backed by `SimulatedExchange` instead of Binance, so strategies
+/// and `GeneralOrderClient` can be tested end to end without network or keys.
+#[derive(Clone)]
+pub struct SimulatedOrderService {
+    exchange: Arc<Mutex<SimulatedExchange>>,
+    order_sender: Sender<Order>,
+}
+
+impl Default for SimulatedOrderService {
+    fn default() -> Self {
+        let (order_sender, _) = broadcast::channel::<Order>(1024);
+        Self {
+            exchange: Arc::new(Mutex::new(SimulatedExchange::default())),
+            order_sender,
+        }
+    }
+}
+
+impl SimulatedOrderService {
+    pub fn set_fee_rate(&mut self, maker_fee_rate: f64, taker_fee_rate: f64) {
+        self.exchange
+            .lock()
+            .unwrap()
+            .set_fee_rate(maker_fee_rate, taker_fee_rate);
+    }
+
+    pub fn set_balance(&mut self, balance: f64) {
+        self.exchange.lock().unwrap().set_balance(balance);
+    }
+
+    /// Order updates equivalent to the `ORDER_TRADE_UPDATE` user data stream.
+    pub fn subscribe_order_updates(&self) -> Receiver<Order> {
+        self.order_sender.subscribe()
+    }
+
+    fn publish(&self, events: Vec<Order>) {
+        for order in events {
+            info!("Simulated order update: {:?}", order);
+            // no receiver is not an error for the simulator
+            let _ = self.order_sender.send(order);
+        }
+    }
+
+    fn place_order(
+        &self,
+        req: &MakeOrderRequest,
+        order_type: OrderType,
+    ) -> Result<Order, StrategyError> {
+        let side = match req.side.as_str() {
+            "BUY" => OrderSide::BUY,
+            "SELL" => OrderSide::SELL,
+            _ => {
+                return Err(StrategyError::PlaceOrderError(format!(
+                    "Invalid order side: {}",
+                    req.side
+                )));
+            }
+        };
+        // conditional orders trigger at the stop price, falling back to the price
+        let price = if order_type.is_conditional() && req.stop_price > 0.0 {
+            req.stop_price
+        } else {
+            req.price
+        };
+        let options = OrderOptions {
+            time_in_force: TimeInForce::try_parse_time_in_force(&req.time_in_force)
+                .unwrap_or(TimeInForce::GTC),
+            reduce_only: req.reduce_only,
+            close_position: req.close_position,
+        };
+        let events = self.exchange.lock().unwrap().place_order_with_options(
+            &req.symbol,
+            side,
+            order_type,
+            price,
+            req.quantity,
+            &req.strategy,
+            &options,
+        )?;
+        let order = events[0].clone();
+        self.publish(events);
+        Ok(order)
+    }
+
+    /// Order type of the request, `default` when empty, once its options are checked.
+    /// Stop loss and take profit requests keep a conditional order type.
+    fn parse_request_order_type(
+        req: &MakeOrderRequest,
+        default: OrderType,
+    ) -> Result<OrderType, StrategyError> {
+        let order_type = if default.is_conditional() {
+            parse_conditional_order_type(req, default)?
+        } else {
+            match req.order_type.as_str() {
+                "" => default,
+                s => OrderType::try_parse_order_type(s).ok_or(StrategyError::PlaceOrderError(
+                    format!("Invalid order type: {}", s),
+                ))?,
+            }
+        };
+        Self::check_request_options(req, order_type)?;
+        Ok(order_type)
+    }
+
+    /// Rejects the order options the simulator cannot honour. Orders trigger on
+    /// contract prices and positions are tracked in one-way mode.
+    fn check_request_options(
+        req: &MakeOrderRequest,
+        order_type: OrderType,
+    ) -> Result<(), StrategyError> {
+        if !req.time_in_force.is_empty()
+            && TimeInForce::try_parse_time_in_force(&req.time_in_force).is_none()
+        {
+            return Err(StrategyError::PlaceOrderError(format!(
+                "Invalid time in force: {}",
+                req.time_in_force
+            )));
+        }
+        if req.reduce_only && req.close_position {
+            return Err(StrategyError::PlaceOrderError(
+                "Reduce only and close position are exclusive".to_string(),
+            ));
+        }
+        let unsupported = if !matches!(req.position_side.as_str(), "" | "BOTH") {
+            Some(format!("position side {}", req.position_side))
+        } else if !matches!(req.working_type.as_str(), "" | "CONTRACT_PRICE") {
+            Some(format!("working type {}", req.working_type))
+        } else if req.stop_price > 0.0 && !order_type.is_conditional() {
+            Some(format!("stop price on a {} order", order_type.string()))
+        } else {
+            None
+        };
+        match unsupported {
+            Some(option) => Err(StrategyError::PlaceOrderError(format!(
+                "Simulated orders do not support {}",
+                option
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Feed klines/depth from a MarketDataEngine (live or replayed) into the matching engine.
+    pub fn start_market_feed(&self, mut rx: Receiver<MarketData>) -> tokio::task::JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(market_data) => {
+                        let events = service
+                            .exchange
+                            .lock()
+                            .unwrap()
+                            .on_market_data(&market_data);
+                        service.publish(events);
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Simulated exchange lagged by {} messages", n);
+                    }
+                    Err(RecvError::Closed) => {
+                        error!("Simulated exchange market data channel closed");
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    pub async fn start_order_service(&self, addr: &str) {
+        let listener = TcpListener::bind(addr).await.unwrap();
+        self.serve(listener).await;
+    }
+
+    /// Serves on a bound listener, which accepts connections before this is polled.
+    pub async fn serve(&self, listener: TcpListener) {
+        info!("start simulated order service...");
+        Server::builder()
+            .add_service(OrderServiceServer::new(self.clone()))
+            .serve_with_incoming(TcpIncoming::from_listener(listener, true, None).unwrap())
+            .await
+            .unwrap();
+    }
+}
+
+#[tonic::async_trait]
+impl OrderService for SimulatedOrderService {
+    async fn make_order(
+        &self,
+        request: Request<MakeOrderRequest>,
+    ) -> Result<Response<MakeOrderReply>, Status> {
+        let req = request.into_inner();
+        let order_type = match Self::parse_request_order_type(&req, OrderType::Limit) {
+            Ok(order_type) => order_type,
+            Err(e) => {
+                return Err(Status::new(
+                    tonic::Code::InvalidArgument,
+                    format!("{:?}", e),
+                ));
+            }
+        };
+        match self.place_order(&req, order_type) {
+            Ok(order) => Ok(Response::new(convert_order_into_reply(&order))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn stop_loss_order(
+        &self,
+        request: Request<MakeOrderRequest>,
+    ) -> Result<Response<MakeOrderReply>, Status> {
+        let req = request.into_inner();
+        let order_type = match Self::parse_request_order_type(&req, OrderType::StopMarket) {
+            Ok(order_type) => order_type,
+            Err(e) => {
+                return Err(Status::new(
+                    tonic::Code::InvalidArgument,
+                    format!("{:?}", e),
+                ));
+            }
+        };
+        match self.place_order(&req, order_type) {
+            Ok(order) => Ok(Response::new(convert_order_into_reply(&order))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn take_profit_order(
+        &self,
+        request: Request<MakeOrderRequest>,
+    ) -> Result<Response<MakeOrderReply>, Status> {
+        let req = request.into_inner();
+        let order_type = match Self::parse_request_order_type(&req, OrderType::TakeProfitMarket) {
+            Ok(order_type) => order_type,
+            Err(e) => {
+                return Err(Status::new(
+                    tonic::Code::InvalidArgument,
+                    format!("{:?}", e),
+                ));
+            }
+        };
+        match self.place_order(&req, order_type) {
+            Ok(order) => Ok(Response::new(convert_order_into_reply(&order))),
+            Err(e) => Err(Status::new(tonic::Code::Internal, format!("{:?}", e))),
+        }
+    }
+
+    async fn get_order(
+        &self,
+        request: Request<GetOrderRequest>,
+    ) -> Result<Response<MakeOrderReply>, Status> {
+        let req = request.into_inner();
+        let order = self
+            .exchange
+            .lock()
+            .unwrap()
+            .get_order(&req.symbol, &req.order_cid);
+        match order {
+            Some(order) => Ok(Response::new(convert_order_into_reply(&order))),
+            None => Err(Status::new(
+                tonic::Code::NotFound,
+                format!("Unknown order: {} {}", req.symbol, req.order_cid),
+            )),
+        }
+    }
+
+    async fn list_open_orders(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<OrderListReply>, Status> {
+        let orders = self
+            .exchange
+            .lock()
+            .unwrap()
+            .get_open_orders(&request.into_inner().symbol);
+        Ok(Response::new(convert_orders_into_reply(&orders)))
+    }
+
+    async fn get_positions(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<PositionListReply>, Status> {
+        let positions = self
+            .exchange
+            .lock()
+            .unwrap()
+            .get_positions(&request.into_inner().symbol);
+        Ok(Response::new(PositionListReply {
+            positions: positions.iter().map(convert_position_into_reply).collect(),
+        }))
+    }
+
+    async fn get_balance(
+        &self,
+        _request: Request<QueryRequest>,
+    ) -> Result<Response<BalanceReply>, Status> {
+        let balance = self.exchange.lock().unwrap().get_balance();
+        Ok(Response::new(BalanceReply {
+            balance: balance.get_balance(),
+            update_time: balance.get_update_time(),
+        }))
+    }
+
+    async fn get_trades(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<OrderListReply>, Status> {
+        let orders = self
+            .exchange
+            .lock()
+            .unwrap()
+            .get_filled_orders(&request.into_inner().symbol);
+        Ok(Response::new(convert_orders_into_reply(&orders)))
+    }
+
+    async fn make_batch_orders(
+        &self,
+        request: Request<MakeBatchOrdersRequest>,
+    ) -> Result<Response<BatchOrderReply>, Status> {
+        let orders = request.into_inner().orders;
+        if orders.is_empty() || orders.len() > MAX_BATCH_ORDERS {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                format!(
+                    "Batch needs 1 to {} orders, got {}",
+                    MAX_BATCH_ORDERS,
+                    orders.len()
+                ),
+            ));
+        }
+        let results = orders
+            .iter()
+            .map(|req| {
+                Self::parse_request_order_type(req, OrderType::Limit)
+                    .and_then(|order_type| self.place_order(req, order_type))
+            })
+            .collect();
+        Ok(Response::new(convert_results_into_batch_reply(results)))
+    }
+
+    async fn cancel_all_orders(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<BatchOrderReply>, Status> {
+        let req = request.into_inner();
+        if req.symbol.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Cancel all needs a symbol",
+            ));
+        }
+        let orders = self
+            .exchange
+            .lock()
+            .unwrap()
+            .cancel_open_orders(&req.symbol, None);
+        let reply = convert_results_into_batch_reply(orders.iter().cloned().map(Ok).collect());
+        self.publish(orders);
+        Ok(Response::new(reply))
+    }
+
+    async fn cancel_strategy_orders(
+        &self,
+        request: Request<CancelStrategyOrdersRequest>,
+    ) -> Result<Response<BatchOrderReply>, Status> {
+        let req = request.into_inner();
+        if req.strategy.is_empty() {
+            return Err(Status::new(
+                tonic::Code::InvalidArgument,
+                "Cancel strategy orders needs a strategy",
+            ));
+        }
+        let orders = self
+            .exchange
+            .lock()
+            .unwrap()
+            .cancel_open_orders(&req.symbol, Some(&req.strategy));
+        let reply = convert_results_into_batch_reply(orders.iter().cloned().map(Ok).collect());
+        self.publish(orders);
+        Ok(Response::new(reply))
+    }
+
+    type SubscribeUpdatesStream = UpdateStream;
+
+    /// Order updates only, positions and balance are queried.
+    async fn subscribe_updates(
+        &self,
+        request: Request<SubscribeUpdatesRequest>,
+    ) -> Result<Response<Self::SubscribeUpdatesStream>, Status> {
+        Ok(Response::new(subscribe_update_stream(
+            request.into_inner().strategy,
+            Some(self.order_sender.subscribe()),
+            None,
+        )))
+    }
+
+    async fn cancel_order(
+        &self,
+        request: Request<CancelOrderRequest>,
+    ) -> Result<Response<MakeOrderReply>, Status> {
+        let req = request.into_inner();
+        let res = self
+            .exchange
+            .lock()
+            .unwrap()
+            .cancel_order(&req.symbol, &req.order_cid);
+        match res {
+            Ok(order) => {
+                let reply = convert_order_into_reply(&order);
+                self.publish(vec![order]);
+                Ok(Response::new(reply))
+            }
+            Err(e) => Err(Status::new(tonic::Code::NotFound, format!("{:?}", e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_manager::order_client::GeneralOrderClient;
+
+    /// Serves on a free port and returns a connected client.
+    async fn start_test_service(service: &SimulatedOrderService) -> GeneralOrderClient {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = service.clone();
+        tokio::spawn(async move { server.serve(listener).await });
+        let mut client = GeneralOrderClient::new(&format!("http://{}", addr));
+        client.connect().await;
+        client
+    }
+
+    fn kline(low: f64, high: f64, close: f64) -> Kline {
+        Kline::new(0, 299999, close, high, low, close, 1.0, 1, 0.0, 0.0)
+    }
+
+    #[test]
+    fn test_match_limit_and_stop_orders() {
+        let mut exchange = SimulatedExchange::default();
+        exchange.on_kline("btcusdt", &kline(99.0, 101.0, 100.0));
+
+        let events = exchange
+            .place_order(
+                "BTCUSDT",
+                OrderSide::BUY,
+                OrderType::Limit,
+                95.0,
+                1.0,
+                "test",
+            )
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        exchange
+            .place_order(
+                "BTCUSDT",
+                OrderSide::SELL,
+                OrderType::StopMarket,
+                90.0,
+                1.0,
+                "test",
+            )
+            .unwrap();
+        assert_eq!(exchange.get_open_orders("btcusdt").len(), 2);
+
+        let events = exchange.on_kline("btcusdt", &kline(94.0, 99.0, 96.0));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].get_status(), OrderStatus::Filled);
+        assert_eq!(events[0].get_avg_price(), 95.0);
+
+        let events = exchange.on_kline("btcusdt", &kline(85.0, 96.0, 88.0));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].get_avg_price(), 90.0);
+        assert!(exchange.get_open_orders("btcusdt").is_empty());
+    }
+
+    #[test]
+    fn test_marketable_limit_and_cancel() {
+        let mut exchange = SimulatedExchange::default();
+        exchange.on_kline("btcusdt", &kline(99.0, 101.0, 100.0));
+
+        let events = exchange
+            .place_order(
+                "BTCUSDT",
+                OrderSide::BUY,
+                OrderType::Limit,
+                105.0,
+                1.0,
+                "test",
+            )
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].get_avg_price(), 100.0);
+
+        let events = exchange
+            .place_order(
+                "BTCUSDT",
+                OrderSide::SELL,
+                OrderType::Limit,
+                110.0,
+                1.0,
+                "test",
+            )
+            .unwrap();
+        let canceled = exchange
+            .cancel_order("BTCUSDT", events[0].get_cid())
+            .unwrap();
+        assert_eq!(canceled.get_status(), OrderStatus::Canceled);
+        assert!(exchange.cancel_order("BTCUSDT", "unknown").is_err());
+    }
+
+    fn place(
+        exchange: &mut SimulatedExchange,
+        side: OrderSide,
+        order_type: OrderType,
+        price: f64,
+        quantity: f64,
+        options: OrderOptions,
+    ) -> Result<Vec<Order>, StrategyError> {
+        exchange.place_order_with_options(
+            "BTCUSDT", side, order_type, price, quantity, "test", &options,
+        )
+    }
+
+    #[test]
+    fn test_order_options() {
+        use OrderSide::{BUY, SELL};
+        use OrderType::{Limit, Market, StopMarket};
+
+        let mut exchange = SimulatedExchange::default();
+        let gtc = OrderOptions::default();
+        let ioc = OrderOptions {
+            time_in_force: TimeInForce::IOC,
+            ..gtc
+        };
+        let gtx = OrderOptions {
+            time_in_force: TimeInForce::GTX,
+            ..gtc
+        };
+        let reduce_only = OrderOptions {
+            reduce_only: true,
+            ..gtc
+        };
+        let close_position = OrderOptions {
+            close_position: true,
+            ..gtc
+        };
+        // a market order needs a last price to fill at
+        assert!(place(&mut exchange, BUY, Market, 0.0, 1.0, gtc).is_err());
+        exchange.on_kline("btcusdt", &kline(99.0, 101.0, 100.0));
+
+        let events = place(&mut exchange, BUY, Market, 0.0, 2.0, gtc).unwrap();
+        assert_eq!(events[1].get_status(), OrderStatus::Filled);
+        assert_eq!(events[1].get_avg_price(), 100.0);
+        let events = place(&mut exchange, SELL, Limit, 110.0, 1.0, ioc).unwrap();
+        assert_eq!(events[1].get_status(), OrderStatus::Expired);
+        let events = place(&mut exchange, BUY, Limit, 105.0, 1.0, ioc).unwrap();
+        assert_eq!(events[1].get_status(), OrderStatus::Filled);
+        assert!(place(&mut exchange, BUY, Limit, 105.0, 1.0, gtx).is_err());
+        let events = place(&mut exchange, BUY, Limit, 95.0, 1.0, gtx).unwrap();
+        assert_eq!(events.len(), 1);
+
+        // the reduce only sell is capped at the long of 3
+        let events = place(&mut exchange, SELL, Limit, 90.0, 5.0, reduce_only).unwrap();
+        assert_eq!(events[1].get_filled_qty(), 3.0);
+        assert!(place(&mut exchange, SELL, Market, 0.0, 1.0, reduce_only).is_err());
+
+        assert!(place(&mut exchange, SELL, Limit, 90.0, 0.0, close_position).is_err());
+        place(&mut exchange, SELL, StopMarket, 90.0, 0.0, close_position).unwrap();
+        place(&mut exchange, BUY, Market, 0.0, 2.0, gtc).unwrap();
+        // the resting post only buy fills first, then the stop closes the whole long
+        let events = exchange.on_kline("btcusdt", &kline(85.0, 99.0, 88.0));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].get_filled_qty(), 3.0);
+        assert_eq!(exchange.get_net_position("btcusdt"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_simulated_service_with_client() {
+        let service = SimulatedOrderService::default();
+        let mut updates = service.subscribe_order_updates();
+        let (tx, rx) = broadcast::channel::<MarketData>(16);
+        service.start_market_feed(rx);
+        let mut client = start_test_service(&service).await;
+        let reply = client
+            .make_order(
+                "BTCUSDT".to_string(),
+                95.0,
+                1.0,
+                "BUY".to_string(),
+                "test".to_string(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reply.status, "NEW");
+        assert_eq!(updates.recv().await.unwrap().get_status(), OrderStatus::New);
+
+        tx.send(MarketData::new(
+            "btcusdt".to_string(),
+            public::base_model::api_model::MarketDataType::Kline(kline(94.0, 99.0, 96.0)),
+        ))
+        .unwrap();
+        let filled = updates.recv().await.unwrap();
+        assert_eq!(filled.get_cid(), reply.order_cid);
+        assert_eq!(filled.get_status(), OrderStatus::Filled);
+
+        let order = client
+            .get_order("BTCUSDT".to_string(), reply.order_cid.clone())
+            .await
+            .unwrap();
+        assert_eq!(order.status, "FILLED");
+        assert_eq!(order.filled_qty, 1.0);
+        let open_orders = client
+            .list_open_orders("BTCUSDT".to_string())
+            .await
+            .unwrap();
+        assert!(open_orders.orders.is_empty());
+        let trades = client.get_trades("BTCUSDT".to_string()).await.unwrap();
+        assert_eq!(trades.orders.len(), 1);
+        assert_eq!(trades.orders[0].avg_price, 95.0);
+
+        // the long is marked to the kline close and the maker fee is charged
+        let positions = client.get_positions(String::new()).await.unwrap();
+        assert_eq!(positions.positions.len(), 1);
+        assert_eq!(positions.positions[0].side, "BUY");
+        assert_eq!(positions.positions[0].entry_price, 95.0);
+        assert_eq!(positions.positions[0].unrealized_pnl, 1.0);
+        let balance = client.get_balance().await.unwrap();
+        assert!((balance.balance - (10000.0 - 95.0 * 0.0002)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_updates() {
+        use crate::order_manager::order_client::order_service::update_reply::Update;
+
+        let service = SimulatedOrderService::default();
+        let mut client = start_test_service(&service).await;
+        let mut updates = client.subscribe_updates("test".to_string()).await.unwrap();
+        for strategy in ["other", "test"] {
+            client
+                .make_order(
+                    "BTCUSDT".to_string(),
+                    95.0,
+                    1.0,
+                    "BUY".to_string(),
+                    strategy.to_string(),
+                )
+                .await
+                .unwrap();
+        }
+        // the order of the other strategy is filtered out
+        let reply = updates.message().await.unwrap().unwrap();
+        match reply.update {
+            Some(Update::Order(order)) => {
+                assert_eq!(order.strategy, "test");
+                assert_eq!(order.status, "NEW");
+            }
+            other => panic!("Unexpected update: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_orders_and_cancel_all() {
+        use crate::order_manager::order_client::order_service::MakeOrderRequest;
+
+        let service = SimulatedOrderService::default();
+        let mut client = start_test_service(&service).await;
+        let order = |symbol: &str, strategy: &str, order_type: &str| MakeOrderRequest {
+            symbol: symbol.to_string(),
+            side: "BUY".to_string(),
+            price: 95.0,
+            quantity: 1.0,
+            strategy: strategy.to_string(),
+            order_type: order_type.to_string(),
+            ..Default::default()
+        };
+        let reply = client
+            .make_batch_orders(vec![
+                order("BTCUSDT", "test", ""),
+                order("BTCUSDT", "other", ""),
+                order("ETHUSDT", "test", "UNKNOWN"),
+                order("ETHUSDT", "test", "LIMIT"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(reply.results.len(), 4);
+        assert!(reply.results[2].order.is_none());
+        assert!(!reply.results[2].error.is_empty());
+        let too_many = client.make_batch_orders(vec![order("BTCUSDT", "test", ""); 6]);
+        assert!(too_many.await.is_none());
+
+        let reply = client
+            .cancel_strategy_orders("test".to_string(), String::new())
+            .await
+            .unwrap();
+        assert_eq!(reply.results.len(), 2);
+        for res in reply.results {
+            let order = res.order.unwrap();
+            assert_eq!(order.strategy, "test");
+            assert_eq!(order.status, "CANCELED");
+        }
+
+        let reply = client
+            .cancel_all_orders("BTCUSDT".to_string())
+            .await
+            .unwrap();
+        assert_eq!(reply.results.len(), 1);
+        assert_eq!(reply.results[0].order.as_ref().unwrap().strategy, "other");
+        let open_orders = client
+            .list_open_orders("BTCUSDT".to_string())
+            .await
+            .unwrap();
+        assert!(open_orders.orders.is_empty());
+    }
+
+    #[test]
+    fn test_request_options() {
+        let req = MakeOrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: "BUY".to_string(),
+            price: 95.0,
+            quantity: 1.0,
+            strategy: "test".to_string(),
+            time_in_force: "GTC".to_string(),
+            ..Default::default()
+        };
+        let parse = SimulatedOrderService::parse_request_order_type;
+        assert!(matches!(
+            parse(&req, OrderType::Limit),
+            Ok(OrderType::Limit)
+        ));
+        assert!(matches!(
+            parse(&req, OrderType::StopMarket),
+            Ok(OrderType::StopMarket)
+        ));
+        // stop loss and take profit keep a conditional order type
+        let limit = MakeOrderRequest {
+            order_type: "LIMIT".to_string(),
+            ..req.clone()
+        };
+        assert!(parse(&limit, OrderType::StopMarket).is_err());
+        let stop = MakeOrderRequest {
+            stop_price: 90.0,
+            ..req.clone()
+        };
+        assert!(parse(&stop, OrderType::Limit).is_err());
+        assert!(parse(&stop, OrderType::TakeProfitMarket).is_ok());
+        for supported in [
+            MakeOrderRequest {
+                time_in_force: "IOC".to_string(),
+                ..req.clone()
+            },
+            MakeOrderRequest {
+                reduce_only: true,
+                ..req.clone()
+            },
+            MakeOrderRequest {
+                close_position: true,
+                ..req.clone()
+            },
+        ] {
+            assert!(parse(&supported, OrderType::StopMarket).is_ok());
+        }
+        for unsupported in [
+            MakeOrderRequest {
+                time_in_force: "GTD".to_string(),
+                ..req.clone()
+            },
+            MakeOrderRequest {
+                reduce_only: true,
+                close_position: true,
+                ..req.clone()
+            },
+            MakeOrderRequest {
+                position_side: "LONG".to_string(),
+                ..req.clone()
+            },
+            MakeOrderRequest {
+                working_type: "MARK_PRICE".to_string(),
+                ..req.clone()
+            },
+        ] {
+            assert!(parse(&unsupported, OrderType::Limit).is_err());
+        }
+    }
+}
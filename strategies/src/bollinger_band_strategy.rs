@@ -1,14 +1,16 @@
-use base_libs::base_strategy::base_strategy::BaseStrategy;
-use base_libs::base_strategy::common_module::TargetPosition;
+use public::base_model::market_model::kline_model::Kline;
+use public::strategy_model::market_context::MarketContext;
+use public::strategy_model::strategy_portfolio::StrategyPortfolio;
+use public::strategy_model::target_position::TargetPosition;
 use quant_libs::tech_analysis::bollinger;
 use tracing::info;
+use trade_engine::base_strategy::TargetPositionStrategy;
 
 pub struct BollingerBandStrategy {
     strategy_name: String,
     symbol: String,
     bollinger: bollinger::Bollinger,
     cur_trend: i8,
-    last_close: f64,
     last_mean: f64,
 }
 
@@ -19,23 +21,24 @@ impl BollingerBandStrategy {
             symbol,
             bollinger: bollinger::Bollinger::new(period, multiplier),
             cur_trend: 0,
-            last_close: 0.0,
             last_mean: 0.0,
         }
     }
 }
 
-impl BaseStrategy for BollingerBandStrategy {
+impl TargetPositionStrategy for BollingerBandStrategy {
     fn on_schedule(
         &mut self,
-        klines: &std::collections::HashMap<
-            String,
-            base_libs::market_data_module::general_data::Kline,
-        >,
-        portfolio: &base_libs::base_strategy::portfolio::Portfolio,
+        klines: &std::collections::HashMap<String, Kline>,
+        context: &MarketContext,
+        portfolio: &StrategyPortfolio,
     ) -> Option<std::collections::HashMap<String, TargetPosition>> {
         let mut res = std::collections::HashMap::new();
         let kline = klines.get(&self.symbol).unwrap();
+        let last_close = match context.get_closes(&self.symbol, 2)[..] {
+            [last_close, _] => last_close,
+            _ => 0.0,
+        };
         self.bollinger.add(kline.get_close());
 
         let (mean, upper, lower) = self.bollinger.get();
@@ -70,15 +73,15 @@ impl BaseStrategy for BollingerBandStrategy {
         }
 
         if let Some(current_position) = portfolio.get_position(&self.symbol) {
-            let mut new_pos = current_position.get_qty();
-            if current_position.get_qty() > 0.0 && self.cur_trend == -1 {
+            let mut new_pos = current_position.get_signed_quantity();
+            if current_position.get_signed_quantity() > 0.0 && self.cur_trend == -1 {
                 info!(
                     "{}: close below lower band, CLOSE LONG POSITION",
                     self.get_strategy_name(),
                 );
 
                 new_pos = 0.0;
-            } else if current_position.get_qty() < 0.0 && self.cur_trend == 1 {
+            } else if current_position.get_signed_quantity() < 0.0 && self.cur_trend == 1 {
                 info!(
                     "{}: close above upper band, CLOSE SHORT POSITION",
                     self.get_strategy_name(),
@@ -86,11 +89,11 @@ impl BaseStrategy for BollingerBandStrategy {
                 new_pos = 0.0;
             }
 
-            if current_position.get_qty() == 0.0 {
+            if current_position.get_signed_quantity() == 0.0 {
                 if self.cur_trend == 1
-                    && self.last_close < self.last_mean
+                    && last_close < self.last_mean
                     && kline.get_close() > mean
-                    && self.last_close != 0.0
+                    && last_close != 0.0
                     && mean != 0.0
                 {
                     let available_cash = portfolio.get_available_cash();
@@ -100,7 +103,7 @@ impl BaseStrategy for BollingerBandStrategy {
                         self.get_strategy_name()
                     );
                 } else if self.cur_trend == -1
-                    && self.last_close > self.last_mean
+                    && last_close > self.last_mean
                     && kline.get_close() < mean
                     && self.last_mean != 0.0
                 {
@@ -112,17 +115,21 @@ impl BaseStrategy for BollingerBandStrategy {
                     );
                 }
             }
-            if new_pos != current_position.get_qty() {
+            if new_pos != current_position.get_signed_quantity() {
                 res.insert(self.symbol.clone(), TargetPosition::new(new_pos));
             }
         }
-        self.last_close = kline.get_close();
         self.last_mean = mean;
         if res.len() > 0 {
             return Some(res);
         }
         None
     }
+
+    fn get_history_size(&self) -> usize {
+        2
+    }
+
     fn get_strategy_name(&self) -> String {
         format!("{}_{}", self.strategy_name, self.symbol)
     }
@@ -1,11 +1,12 @@
-use base_libs::base_strategy::base_strategy::BaseStrategy;
-use base_libs::base_strategy::common_module::TargetPosition;
-use base_libs::base_strategy::portfolio;
-use base_libs::market_data_module::general_data;
-use base_libs::tools::time_tools;
+use public::base_model::market_model::kline_model::Kline;
+use public::strategy_model::market_context::MarketContext;
+use public::strategy_model::strategy_portfolio::StrategyPortfolio;
+use public::strategy_model::target_position::TargetPosition;
+use public::tools::time_tools;
 use quant_libs::tech_analysis::ma;
 use quant_libs::tech_analysis::rsi;
 use std::collections::HashMap;
+use trade_engine::base_strategy::TargetPositionStrategy;
 pub struct FiveRsiStrategy {
     strategy_name: String,
     symbol: String,
@@ -15,7 +16,6 @@ pub struct FiveRsiStrategy {
     high_ema: ma::EMA,
     close_ema: ma::EMA,
     low_ema: ma::EMA,
-    last_kline: Option<general_data::Kline>,
     last_bound: f64,
 }
 
@@ -35,17 +35,17 @@ impl FiveRsiStrategy {
             high_ema: ma::EMA::new(200),
             close_ema: ma::EMA::new(200),
             low_ema: ma::EMA::new(200),
-            last_kline: None,
             last_bound: 0.0,
         }
     }
 }
 
-impl BaseStrategy for FiveRsiStrategy {
+impl TargetPositionStrategy for FiveRsiStrategy {
     fn on_schedule(
         &mut self,
-        klines: &HashMap<String, general_data::Kline>,
-        portfolio: &portfolio::Portfolio,
+        klines: &HashMap<String, Kline>,
+        _context: &MarketContext,
+        portfolio: &StrategyPortfolio,
     ) -> Option<HashMap<String, TargetPosition>> {
         let kline = klines.get(&self.symbol).unwrap();
         let cur_time = time_tools::get_datetime_from_timestamp(kline.get_close_time()).to_string();
@@ -71,8 +71,8 @@ impl BaseStrategy for FiveRsiStrategy {
         );
 
         if let Some(current_position) = portfolio.get_position(&self.symbol) {
-            let mut new_pos = current_position.get_qty();
-            if current_position.get_qty() > 0.0
+            let mut new_pos = current_position.get_signed_quantity();
+            if current_position.get_signed_quantity() > 0.0
                 && (self.rsi_ma.get() > 70.0
                     || kline.get_close() < self.close_ema.get()
                     || kline.get_close() < self.last_bound)
@@ -83,11 +83,11 @@ impl BaseStrategy for FiveRsiStrategy {
                     cur_time,
                     kline.get_close(),
                     self.rsi_ma.get(),
-                    current_position.get_qty()
+                    current_position.get_signed_quantity()
                 );
             }
 
-            if current_position.get_qty() < 0.0
+            if current_position.get_signed_quantity() < 0.0
                 && (self.rsi_ma.get() < 30.0
                     || kline.get_close() > self.close_ema.get()
                     || kline.get_close() > self.last_bound)
@@ -101,7 +101,7 @@ impl BaseStrategy for FiveRsiStrategy {
                 );
             }
 
-            if current_position.get_qty() == 0.0 && self.rsi_ma_vec.len() == 2 {
+            if current_position.get_signed_quantity() == 0.0 && self.rsi_ma_vec.len() == 2 {
                 if kline.get_close() >= self.close_ema.get()
                     && self.rsi_ma_vec[1] < 25.0
                     && self.rsi_ma_vec[1] > self.rsi_ma_vec[0]
@@ -135,11 +135,10 @@ impl BaseStrategy for FiveRsiStrategy {
                 }
             }
 
-            if new_pos != current_position.get_qty() {
+            if new_pos != current_position.get_signed_quantity() {
                 res.insert(self.symbol.clone(), TargetPosition::new(new_pos));
             }
         }
-        self.last_kline = Some(kline.clone());
         if res.len() > 0 {
             return Some(res);
         }
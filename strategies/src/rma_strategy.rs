@@ -1,10 +1,11 @@
-use base_libs::base_strategy::base_strategy::BaseStrategy;
-use base_libs::base_strategy::common_module::TargetPosition;
-use base_libs::base_strategy::portfolio;
-use base_libs::market_data_module::general_data;
-use base_libs::tools::time_tools;
+use public::base_model::market_model::kline_model::Kline;
+use public::strategy_model::market_context::MarketContext;
+use public::strategy_model::strategy_portfolio::StrategyPortfolio;
+use public::strategy_model::target_position::TargetPosition;
+use public::tools::time_tools;
 use quant_libs::tech_analysis::super_trend;
 use std::collections::HashMap;
+use trade_engine::base_strategy::TargetPositionStrategy;
 pub struct RmaStrategy {
     strategy_name: String,
     symbol: String,
@@ -21,11 +22,12 @@ impl RmaStrategy {
     }
 }
 
-impl BaseStrategy for RmaStrategy {
+impl TargetPositionStrategy for RmaStrategy {
     fn on_schedule(
         &mut self,
-        klines: &HashMap<String, general_data::Kline>,
-        _portfolio: &portfolio::Portfolio,
+        klines: &HashMap<String, Kline>,
+        _context: &MarketContext,
+        _portfolio: &StrategyPortfolio,
     ) -> Option<HashMap<String, TargetPosition>> {
         let kline = klines.get(&self.symbol).unwrap();
         self.super_trend.add(kline.clone());
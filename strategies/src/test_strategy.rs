@@ -1,10 +1,11 @@
 use public::base_enum::order_enums::OrderSide;
 use public::base_model::market_model::kline_model::Kline;
 use public::base_model::trade_model::order_model::Order;
+use public::strategy_model::market_context::MarketContext;
 use public::strategy_model::strategy_portfolio::StrategyPortfolio;
 use public::tools::time_tools;
 use tracing::info;
-use trade_engine::BaseStrategy;
+use trade_engine::base_strategy::BaseStrategy;
 
 use quant_libs::tech_analysis::ma;
 use std::collections::HashMap;
@@ -42,6 +43,7 @@ impl BaseStrategy for TestStrategy {
     fn on_schedule(
         &mut self,
         klines: &HashMap<String, Kline>,
+        _context: &MarketContext,
         portfolio: &StrategyPortfolio,
     ) -> Option<HashMap<String, Order>> {
         let mut res = HashMap::new();
@@ -171,8 +173,8 @@ mod tests {
     use std::vec;
 
     use super::*;
-    use base_libs::tools::time_tools;
-    use trade_engine::StrategyEngine;
+    use public::tools::time_tools;
+    use trade_engine::strategy_engine::{StrategyEngine, TradeMode};
     
     #[tokio::test]
     async fn test_test_strategy() {
@@ -196,7 +198,7 @@ mod tests {
             HashMap::new(),
             start_timestamp,
             Box::new(strategy),
-            true,
+            TradeMode::BackTest,
         );
         trade_engine.run().await;
         
@@ -0,0 +1,454 @@
+use public::base_enum::market_enums::Interval;
+use public::base_enum::order_enums::OrderSide;
+use public::base_model::market_model::depth_model::Depth;
+use public::base_model::market_model::kline_model::Kline;
+use public::base_model::market_model::trade_model::Trade;
+use public::base_model::trade_model::order_model::Order;
+use public::strategy_model::market_context::MarketContext;
+use public::strategy_model::strategy_portfolio::StrategyPortfolio;
+use public::strategy_model::target_position::TargetPosition;
+use std::collections::HashMap;
+
+/// Strategy trait run by `StrategyEngine`, returning explicit orders keyed by symbol.
+/// Only `on_schedule` is required; the other callbacks run in backtest, paper and real
+/// trading alike and may also return orders. Backtests feed `on_depth` and `on_trade`
+/// from the depth and trade data set on the engine. Per backtest kline, fills come first,
+/// then the depths, trades and timers up to its close in time order, then the kline
+/// callbacks. Kline callbacks get the last `get_history_size` base klines of every
+/// symbol, current one included.
+pub trait BaseStrategy {
+    fn on_schedule(
+        &mut self,
+        klines: &HashMap<String, Kline>,
+        context: &MarketContext,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>>;
+
+    fn get_strategy_name(&self) -> String {
+        "BaseStrategy".to_string()
+    }
+
+    /// Called once before warm up.
+    fn on_start(&mut self, _portfolio: &StrategyPortfolio) {}
+
+    /// Called for each symbol's closed kline, before `on_schedule`.
+    fn on_kline(
+        &mut self,
+        _symbol: &str,
+        _kline: &Kline,
+        _context: &MarketContext,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        None
+    }
+
+    /// Higher timeframes the strategy needs on top of the base interval.
+    fn get_intervals(&self) -> Vec<Interval> {
+        vec![]
+    }
+
+    /// Base klines kept per symbol in the `MarketContext`.
+    fn get_history_size(&self) -> usize {
+        0
+    }
+
+    /// Called when a bar of one of `get_intervals` closes, right after `on_kline`
+    /// for its last base kline.
+    fn on_interval_kline(
+        &mut self,
+        _symbol: &str,
+        _interval: Interval,
+        _kline: &Kline,
+        _context: &MarketContext,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        None
+    }
+
+    fn on_depth(
+        &mut self,
+        _symbol: &str,
+        _depth: &Depth,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        None
+    }
+
+    /// Raw and aggregate trades.
+    fn on_trade(
+        &mut self,
+        _symbol: &str,
+        _trade: &Trade,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        None
+    }
+
+    /// Fills, cancels and rejects of the strategy's orders, after the portfolio has
+    /// been updated.
+    fn on_order_update(
+        &mut self,
+        _order: &Order,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        None
+    }
+
+    /// Called every `get_timer_interval` milliseconds, on kline time in backtests.
+    fn on_timer(
+        &mut self,
+        _timestamp: i64,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        None
+    }
+
+    /// `None` disables `on_timer`.
+    fn get_timer_interval(&self) -> Option<i64> {
+        None
+    }
+
+    /// Called once when the run ends.
+    fn on_stop(&mut self, _portfolio: &StrategyPortfolio) {}
+}
+
+/// Strategy trait returning the signed target position of each symbol,
+/// positive for long and negative for short. Wrap it with `TargetPositionAdapter`
+/// to run it on `StrategyEngine`. Callbacks mirror `BaseStrategy`.
+pub trait TargetPositionStrategy {
+    fn on_schedule(
+        &mut self,
+        klines: &HashMap<String, Kline>,
+        context: &MarketContext,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>>;
+
+    fn get_strategy_name(&self) -> String {
+        "TargetPositionStrategy".to_string()
+    }
+
+    fn on_start(&mut self, _portfolio: &StrategyPortfolio) {}
+
+    fn on_kline(
+        &mut self,
+        _symbol: &str,
+        _kline: &Kline,
+        _context: &MarketContext,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>> {
+        None
+    }
+
+    fn get_intervals(&self) -> Vec<Interval> {
+        vec![]
+    }
+
+    /// Base klines kept per symbol in the `MarketContext`.
+    fn get_history_size(&self) -> usize {
+        0
+    }
+
+    fn on_interval_kline(
+        &mut self,
+        _symbol: &str,
+        _interval: Interval,
+        _kline: &Kline,
+        _context: &MarketContext,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>> {
+        None
+    }
+
+    fn on_depth(
+        &mut self,
+        _symbol: &str,
+        _depth: &Depth,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>> {
+        None
+    }
+
+    fn on_trade(
+        &mut self,
+        _symbol: &str,
+        _trade: &Trade,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>> {
+        None
+    }
+
+    fn on_order_update(
+        &mut self,
+        _order: &Order,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>> {
+        None
+    }
+
+    fn on_timer(
+        &mut self,
+        _timestamp: i64,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>> {
+        None
+    }
+
+    fn get_timer_interval(&self) -> Option<i64> {
+        None
+    }
+
+    fn on_stop(&mut self, _portfolio: &StrategyPortfolio) {}
+}
+
+/// Converts target positions into the orders needed to reach them from the
+/// current portfolio position, priced at the latest close.
+pub struct TargetPositionAdapter<S: TargetPositionStrategy> {
+    strategy: S,
+    last_klines: HashMap<String, Kline>,
+}
+
+impl<S: TargetPositionStrategy> TargetPositionAdapter<S> {
+    pub fn new(strategy: S) -> Self {
+        Self {
+            strategy,
+            last_klines: HashMap::new(),
+        }
+    }
+
+    pub fn get_strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    fn convert_target_position(
+        symbol: &str,
+        target_position: &TargetPosition,
+        kline: &Kline,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<Order> {
+        let cur_qty = match portfolio.get_position(symbol) {
+            Some(position) => position.get_signed_quantity(),
+            None => 0.0,
+        };
+        let delta_qty = target_position.get_position() - cur_qty;
+        if delta_qty == 0.0 {
+            return None;
+        }
+        let mut order = Order::default();
+        order.set_symbol(symbol);
+        order.set_price(kline.get_close());
+        order.set_qty(delta_qty.abs());
+        order.set_timestamp(kline.get_close_time());
+        if delta_qty > 0.0 {
+            order.set_side(OrderSide::BUY);
+        } else {
+            order.set_side(OrderSide::SELL);
+        }
+        Some(order)
+    }
+
+    /// Targets of symbols without a kline yet are dropped.
+    fn convert_target_positions(
+        &self,
+        target_positions: Option<HashMap<String, TargetPosition>>,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        let mut res = HashMap::new();
+        for (symbol, target_position) in target_positions? {
+            if let Some(kline) = self.last_klines.get(&symbol) {
+                if let Some(order) =
+                    Self::convert_target_position(&symbol, &target_position, kline, portfolio)
+                {
+                    res.insert(symbol, order);
+                }
+            }
+        }
+        if res.is_empty() {
+            None
+        } else {
+            Some(res)
+        }
+    }
+}
+
+impl<S: TargetPositionStrategy> BaseStrategy for TargetPositionAdapter<S> {
+    fn on_schedule(
+        &mut self,
+        klines: &HashMap<String, Kline>,
+        context: &MarketContext,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        self.last_klines
+            .extend(klines.iter().map(|(s, k)| (s.clone(), *k)));
+        let target_positions = self.strategy.on_schedule(klines, context, portfolio);
+        self.convert_target_positions(target_positions, portfolio)
+    }
+
+    fn get_strategy_name(&self) -> String {
+        self.strategy.get_strategy_name()
+    }
+
+    fn on_start(&mut self, portfolio: &StrategyPortfolio) {
+        self.strategy.on_start(portfolio);
+    }
+
+    fn on_kline(
+        &mut self,
+        symbol: &str,
+        kline: &Kline,
+        context: &MarketContext,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        self.last_klines.insert(symbol.to_string(), *kline);
+        let target_positions = self.strategy.on_kline(symbol, kline, context, portfolio);
+        self.convert_target_positions(target_positions, portfolio)
+    }
+
+    fn get_intervals(&self) -> Vec<Interval> {
+        self.strategy.get_intervals()
+    }
+
+    fn get_history_size(&self) -> usize {
+        self.strategy.get_history_size()
+    }
+
+    fn on_interval_kline(
+        &mut self,
+        symbol: &str,
+        interval: Interval,
+        kline: &Kline,
+        context: &MarketContext,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        let target_positions = self
+            .strategy
+            .on_interval_kline(symbol, interval, kline, context, portfolio);
+        self.convert_target_positions(target_positions, portfolio)
+    }
+
+    fn on_depth(
+        &mut self,
+        symbol: &str,
+        depth: &Depth,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        let target_positions = self.strategy.on_depth(symbol, depth, portfolio);
+        self.convert_target_positions(target_positions, portfolio)
+    }
+
+    fn on_trade(
+        &mut self,
+        symbol: &str,
+        trade: &Trade,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        let target_positions = self.strategy.on_trade(symbol, trade, portfolio);
+        self.convert_target_positions(target_positions, portfolio)
+    }
+
+    fn on_order_update(
+        &mut self,
+        order: &Order,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        let target_positions = self.strategy.on_order_update(order, portfolio);
+        self.convert_target_positions(target_positions, portfolio)
+    }
+
+    fn on_timer(
+        &mut self,
+        timestamp: i64,
+        portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, Order>> {
+        let target_positions = self.strategy.on_timer(timestamp, portfolio);
+        self.convert_target_positions(target_positions, portfolio)
+    }
+
+    fn get_timer_interval(&self) -> Option<i64> {
+        self.strategy.get_timer_interval()
+    }
+
+    fn on_stop(&mut self, portfolio: &StrategyPortfolio) {
+        self.strategy.on_stop(portfolio);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedTarget(f64);
+
+    impl TargetPositionStrategy for FixedTarget {
+        fn on_schedule(
+            &mut self,
+            _klines: &HashMap<String, Kline>,
+            _context: &MarketContext,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, TargetPosition>> {
+            Some(HashMap::from([(
+                "btcusdt".to_string(),
+                TargetPosition::new(self.0),
+            )]))
+        }
+    }
+
+    #[test]
+    fn test_target_position_adapter() {
+        let portfolio = StrategyPortfolio::new(1000.0, 1.0, vec!["btcusdt".to_string()]);
+        let context = MarketContext::default();
+        let kline = Kline::new(0, 299999, 100.0, 101.0, 99.0, 100.5, 1.0, 1, 0.0, 0.0);
+        let klines = HashMap::from([("btcusdt".to_string(), kline)]);
+
+        let mut adapter = TargetPositionAdapter::new(FixedTarget(-2.0));
+        let orders = adapter.on_schedule(&klines, &context, &portfolio).unwrap();
+        let order = orders.get("btcusdt").unwrap();
+        assert_eq!(order.get_side(), OrderSide::SELL);
+        assert_eq!(order.get_qty(), 2.0);
+        assert_eq!(order.get_price(), 100.5);
+
+        let mut adapter = TargetPositionAdapter::new(FixedTarget(0.0));
+        assert!(adapter.on_schedule(&klines, &context, &portfolio).is_none());
+    }
+
+    struct TimerTarget;
+
+    impl TargetPositionStrategy for TimerTarget {
+        fn on_schedule(
+            &mut self,
+            _klines: &HashMap<String, Kline>,
+            _context: &MarketContext,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, TargetPosition>> {
+            None
+        }
+
+        fn on_timer(
+            &mut self,
+            _timestamp: i64,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, TargetPosition>> {
+            Some(HashMap::from([(
+                "btcusdt".to_string(),
+                TargetPosition::new(1.0),
+            )]))
+        }
+    }
+
+    #[test]
+    fn test_adapter_callbacks() {
+        let portfolio = StrategyPortfolio::new(1000.0, 1.0, vec!["btcusdt".to_string()]);
+        let context = MarketContext::default();
+        let kline = Kline::new(0, 299999, 100.0, 101.0, 99.0, 100.5, 1.0, 1, 0.0, 0.0);
+
+        let mut adapter = TargetPositionAdapter::new(TimerTarget);
+        // no price to convert at before the first kline
+        assert!(adapter.on_timer(0, &portfolio).is_none());
+        assert!(adapter
+            .on_kline("btcusdt", &kline, &context, &portfolio)
+            .is_none());
+        let orders = adapter.on_timer(300000, &portfolio).unwrap();
+        let order = orders.get("btcusdt").unwrap();
+        assert_eq!(order.get_side(), OrderSide::BUY);
+        assert_eq!(order.get_price(), 100.5);
+    }
+}
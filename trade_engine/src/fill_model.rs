@@ -0,0 +1,272 @@
+use public::base_enum::order_enums::{OrderSide, OrderType};
+use public::base_model::market_model::depth_model::Depth;
+use public::base_model::market_model::kline_model::Kline;
+use public::base_model::trade_model::order_model::Order;
+
+/// Quantity and average price filled on one kline, and whether it rested on the book
+/// and pays the maker fee.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fill {
+    qty: f64,
+    price: f64,
+    is_maker: bool,
+}
+
+impl Fill {
+    /// A taker fill.
+    pub fn new(qty: f64, price: f64) -> Self {
+        Self {
+            qty,
+            price,
+            is_maker: false,
+        }
+    }
+
+    pub fn new_maker(qty: f64, price: f64) -> Self {
+        Self {
+            qty,
+            price,
+            is_maker: true,
+        }
+    }
+
+    pub fn get_qty(&self) -> f64 {
+        self.qty
+    }
+
+    pub fn get_price(&self) -> f64 {
+        self.price
+    }
+
+    pub fn is_maker(&self) -> bool {
+        self.is_maker
+    }
+}
+
+/// Decides how much of a pending backtest order fills on a kline and at what price.
+/// `order.get_qty()` is the quantity still unfilled; whatever is not filled stays
+/// pending for the next kline. `depth` is the latest recorded book at the kline open,
+/// if any was set on the engine.
+pub trait FillModel: Send + Sync {
+    fn fill(&self, order: &Order, kline: &Kline, depth: Option<&Depth>) -> Option<Fill>;
+}
+
+/// Fills the whole order at the kline open.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NextOpenFillModel;
+
+impl FillModel for NextOpenFillModel {
+    fn fill(&self, order: &Order, kline: &Kline, _depth: Option<&Depth>) -> Option<Fill> {
+        Some(Fill::new(order.get_qty(), kline.get_open()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slippage {
+    /// Absolute price offset.
+    Fixed(f64),
+    /// Fraction of the price, e.g. 0.0005 for 5 bps.
+    Percentage(f64),
+}
+
+impl Slippage {
+    /// Moves `price` against the order side.
+    pub fn apply(&self, side: OrderSide, price: f64) -> f64 {
+        let offset = match self {
+            Slippage::Fixed(offset) => *offset,
+            Slippage::Percentage(rate) => price * rate,
+        };
+        match side {
+            OrderSide::BUY => price + offset,
+            OrderSide::SELL => price - offset,
+        }
+    }
+}
+
+/// Fills the whole order at the kline open moved by the slippage.
+#[derive(Debug, Clone, Copy)]
+pub struct SlippageFillModel {
+    slippage: Slippage,
+}
+
+impl SlippageFillModel {
+    pub fn new(slippage: Slippage) -> Self {
+        Self { slippage }
+    }
+}
+
+impl FillModel for SlippageFillModel {
+    fn fill(&self, order: &Order, kline: &Kline, _depth: Option<&Depth>) -> Option<Fill> {
+        let price = self.slippage.apply(order.get_side(), kline.get_open());
+        Some(Fill::new(order.get_qty(), price))
+    }
+}
+
+/// Caps each fill at a fraction of the kline volume, at the open moved by the slippage.
+#[derive(Debug, Clone, Copy)]
+pub struct VolumeParticipationFillModel {
+    participation_rate: f64,
+    slippage: Slippage,
+}
+
+impl VolumeParticipationFillModel {
+    pub fn new(participation_rate: f64, slippage: Slippage) -> Self {
+        Self {
+            participation_rate,
+            slippage,
+        }
+    }
+}
+
+impl FillModel for VolumeParticipationFillModel {
+    fn fill(&self, order: &Order, kline: &Kline, _depth: Option<&Depth>) -> Option<Fill> {
+        let qty = order
+            .get_qty()
+            .min(kline.get_volume() * self.participation_rate);
+        if qty <= 0.0 {
+            return None;
+        }
+        let price = self.slippage.apply(order.get_side(), kline.get_open());
+        Some(Fill::new(qty, price))
+    }
+}
+
+/// Sweeps the recorded book from the best level, buys against asks and sells against
+/// bids. Limit orders stop at their price. Falls back to the kline open when no book
+/// is recorded for the kline.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DepthFillModel;
+
+impl FillModel for DepthFillModel {
+    fn fill(&self, order: &Order, kline: &Kline, depth: Option<&Depth>) -> Option<Fill> {
+        let Some(depth) = depth else {
+            return Some(Fill::new(order.get_qty(), kline.get_open()));
+        };
+        let mut levels = match order.get_side() {
+            OrderSide::BUY => depth.get_asks().clone(),
+            OrderSide::SELL => depth.get_bids().clone(),
+        };
+        match order.get_side() {
+            OrderSide::BUY => levels.sort_by(|a, b| a.get_price().total_cmp(&b.get_price())),
+            OrderSide::SELL => levels.sort_by(|a, b| b.get_price().total_cmp(&a.get_price())),
+        }
+        let is_limit = matches!(order.get_order_type(), OrderType::Limit);
+
+        let (mut qty, mut notional) = (0.0, 0.0);
+        for level in levels {
+            let crossed = match order.get_side() {
+                OrderSide::BUY => level.get_price() <= order.get_price(),
+                OrderSide::SELL => level.get_price() >= order.get_price(),
+            };
+            if qty >= order.get_qty() || (is_limit && !crossed) {
+                break;
+            }
+            let level_qty = level.get_quantity().min(order.get_qty() - qty);
+            qty += level_qty;
+            notional += level_qty * level.get_price();
+        }
+        if qty <= 0.0 {
+            return None;
+        }
+        Some(Fill::new(qty, notional / qty))
+    }
+}
+
+/// Limit orders only fill when the kline trades through their price: at the open as
+/// taker if it already crossed, otherwise at the limit price as maker. Other order
+/// types fill at the open.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LimitCrossFillModel;
+
+impl FillModel for LimitCrossFillModel {
+    fn fill(&self, order: &Order, kline: &Kline, _depth: Option<&Depth>) -> Option<Fill> {
+        if !matches!(order.get_order_type(), OrderType::Limit) {
+            return Some(Fill::new(order.get_qty(), kline.get_open()));
+        }
+        let price = order.get_price();
+        match order.get_side() {
+            OrderSide::BUY if kline.get_open() <= price => {
+                Some(Fill::new(order.get_qty(), kline.get_open()))
+            }
+            OrderSide::BUY if kline.get_low() < price => {
+                Some(Fill::new_maker(order.get_qty(), price))
+            }
+            OrderSide::SELL if kline.get_open() >= price => {
+                Some(Fill::new(order.get_qty(), kline.get_open()))
+            }
+            OrderSide::SELL if kline.get_high() > price => {
+                Some(Fill::new_maker(order.get_qty(), price))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use public::base_model::market_model::depth_model::PriceLevel;
+
+    fn test_kline() -> Kline {
+        Kline::new(0, 299999, 100.0, 105.0, 95.0, 102.0, 10.0, 1, 0.0, 0.0)
+    }
+
+    fn test_order(side: OrderSide, order_type: OrderType, price: f64, qty: f64) -> Order {
+        let mut order = Order::default();
+        order.set_symbol("btcusdt");
+        order.set_side(side);
+        order.set_order_type(order_type);
+        order.set_price(price);
+        order.set_qty(qty);
+        order
+    }
+
+    #[test]
+    fn test_slippage_fill() {
+        let kline = test_kline();
+        let order = test_order(OrderSide::SELL, OrderType::Market, 0.0, 2.0);
+        let fill = SlippageFillModel::new(Slippage::Percentage(0.01))
+            .fill(&order, &kline, None)
+            .unwrap();
+        assert_eq!(fill, Fill::new(2.0, 99.0));
+
+        let fill = VolumeParticipationFillModel::new(0.1, Slippage::Fixed(0.5))
+            .fill(&order, &kline, None)
+            .unwrap();
+        assert_eq!(fill, Fill::new(1.0, 99.5));
+    }
+
+    #[test]
+    fn test_depth_fill() {
+        let kline = test_kline();
+        let depth = Depth::new(
+            vec![PriceLevel::new(101.0, 1.0), PriceLevel::new(100.5, 1.0)],
+            vec![PriceLevel::new(99.5, 1.0), PriceLevel::new(99.0, 1.0)],
+        );
+        let order = test_order(OrderSide::BUY, OrderType::Market, 0.0, 1.5);
+        let fill = DepthFillModel.fill(&order, &kline, Some(&depth)).unwrap();
+        assert_eq!(fill.get_qty(), 1.5);
+        assert!((fill.get_price() - (100.5 + 0.5 * 101.0) / 1.5).abs() < 1e-9);
+
+        let order = test_order(OrderSide::SELL, OrderType::Limit, 99.5, 3.0);
+        let fill = DepthFillModel.fill(&order, &kline, Some(&depth)).unwrap();
+        assert_eq!(fill, Fill::new(1.0, 99.5));
+    }
+
+    #[test]
+    fn test_limit_cross_fill() {
+        let kline = test_kline();
+        let order = test_order(OrderSide::BUY, OrderType::Limit, 96.0, 1.0);
+        assert_eq!(
+            LimitCrossFillModel.fill(&order, &kline, None),
+            Some(Fill::new_maker(1.0, 96.0))
+        );
+        let order = test_order(OrderSide::BUY, OrderType::Limit, 94.0, 1.0);
+        assert!(LimitCrossFillModel.fill(&order, &kline, None).is_none());
+        let order = test_order(OrderSide::SELL, OrderType::Limit, 98.0, 1.0);
+        assert_eq!(
+            LimitCrossFillModel.fill(&order, &kline, None),
+            Some(Fill::new(1.0, 100.0))
+        );
+    }
+}
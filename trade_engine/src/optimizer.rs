@@ -0,0 +1,528 @@
+use crate::base_strategy::BaseStrategy;
+use crate::fill_model::{FillModel, NextOpenFillModel};
+use crate::strategy_engine::{StrategyEngine, TradeMode};
+use public::base_enum::market_enums::{Interval, PriceType, SymbolIntervals};
+use public::base_model::info_model::SymbolInfo;
+use public::base_model::market_model::funding_rate_model::FundingRate;
+use public::base_model::market_model::kline_model::Kline;
+use public::strategy_model::backtest_report::BacktestReport;
+use public::strategy_model::fee_schedule::FeeSchedule;
+use public::strategy_model::margin_model::{MarginBrackets, MarginMode};
+use public::strategy_model::strategy_portfolio::StrategyPortfolio;
+use services::mongo_engine::MongoEngine;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Candidate values of one strategy parameter.
+#[derive(Debug, Clone)]
+pub struct ParameterRange {
+    name: String,
+    values: Vec<f64>,
+}
+
+impl ParameterRange {
+    pub fn new(name: &str, values: Vec<f64>) -> Self {
+        Self {
+            name: name.to_string(),
+            values,
+        }
+    }
+
+    /// Values from `start` to `end` inclusive, spaced by `step`.
+    pub fn from_step(name: &str, start: f64, end: f64, step: f64) -> Self {
+        let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+        let values = (0..count).map(|i| start + step * i as f64).collect();
+        Self::new(name, values)
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_values(&self) -> &Vec<f64> {
+        &self.values
+    }
+}
+
+/// Cartesian product of all parameter ranges.
+pub fn parameter_grid(ranges: &[ParameterRange]) -> Vec<HashMap<String, f64>> {
+    let mut res = vec![HashMap::new()];
+    for range in ranges {
+        res = res
+            .into_iter()
+            .flat_map(|params| {
+                range.values.iter().map(move |v| {
+                    let mut params = params.clone();
+                    params.insert(range.name.clone(), *v);
+                    params
+                })
+            })
+            .collect();
+    }
+    res
+}
+
+/// Backtest statistic used to rank parameter sets, higher is better.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    TotalReturn,
+    AnnualReturn,
+    SharpeRatio,
+    SortinoRatio,
+    CalmarRatio,
+    MaxDrawdown,
+    WinRate,
+    ProfitFactor,
+}
+
+impl Objective {
+    pub fn score(&self, report: &BacktestReport) -> f64 {
+        match self {
+            Objective::TotalReturn => report.get_total_return(),
+            Objective::AnnualReturn => report.get_annual_return(),
+            Objective::SharpeRatio => report.get_sharpe_ratio(),
+            Objective::SortinoRatio => report.get_sortino_ratio(),
+            Objective::CalmarRatio => report.get_calmar_ratio(),
+            Objective::MaxDrawdown => -report.get_max_drawdown(),
+            Objective::WinRate => report.get_win_rate(),
+            Objective::ProfitFactor => report.get_profit_factor(),
+        }
+    }
+
+    pub fn string(&self) -> String {
+        match self {
+            Objective::TotalReturn => "total_return".to_string(),
+            Objective::AnnualReturn => "annual_return".to_string(),
+            Objective::SharpeRatio => "sharpe_ratio".to_string(),
+            Objective::SortinoRatio => "sortino_ratio".to_string(),
+            Objective::CalmarRatio => "calmar_ratio".to_string(),
+            Objective::MaxDrawdown => "max_drawdown".to_string(),
+            Objective::WinRate => "win_rate".to_string(),
+            Objective::ProfitFactor => "profit_factor".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OptimizationResult {
+    params: HashMap<String, f64>,
+    score: f64,
+    report: BacktestReport,
+}
+
+impl OptimizationResult {
+    pub fn get_params(&self) -> &HashMap<String, f64> {
+        &self.params
+    }
+
+    pub fn get_score(&self) -> f64 {
+        self.score
+    }
+
+    pub fn get_report(&self) -> &BacktestReport {
+        &self.report
+    }
+}
+
+/// Grid search over strategy parameters. Klines and symbol infos are loaded once
+/// and shared by every backtest, which run in parallel on a thread pool.
+pub struct Optimizer {
+    symbols: Vec<String>,
+    starting_cash: f64,
+    leverage_rate: f64,
+    start_date: i64,
+    parameter_ranges: Vec<ParameterRange>,
+    objective: Objective,
+    threads: usize,
+    fill_model: Arc<dyn FillModel>,
+    fee_schedule: FeeSchedule,
+    margin_mode: MarginMode,
+    mongo_client: MongoEngine,
+    kline_data: Arc<HashMap<String, Vec<Kline>>>,
+    symbol_infos: HashMap<String, SymbolInfo>,
+    funding_rates: Arc<HashMap<String, Vec<FundingRate>>>,
+    mark_price_klines: Arc<HashMap<String, Vec<Kline>>>,
+    margin_brackets: HashMap<String, MarginBrackets>,
+    symbol_intervals: SymbolIntervals,
+}
+
+impl Optimizer {
+    pub fn new(
+        symbols: Vec<String>,
+        starting_cash: f64,
+        leverage_rate: f64,
+        start_date: i64,
+        parameter_ranges: Vec<ParameterRange>,
+        objective: Objective,
+    ) -> Self {
+        let threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self {
+            symbols,
+            starting_cash,
+            leverage_rate,
+            start_date,
+            parameter_ranges,
+            objective,
+            threads,
+            fill_model: Arc::new(NextOpenFillModel),
+            fee_schedule: FeeSchedule::default(),
+            margin_mode: MarginMode::Cross,
+            mongo_client: MongoEngine::default(),
+            kline_data: Arc::new(HashMap::new()),
+            symbol_infos: HashMap::new(),
+            funding_rates: Arc::new(HashMap::new()),
+            mark_price_klines: Arc::new(HashMap::new()),
+            margin_brackets: HashMap::new(),
+            symbol_intervals: SymbolIntervals::default(),
+        }
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    pub fn set_fill_model(&mut self, fill_model: Arc<dyn FillModel>) {
+        self.fill_model = fill_model;
+    }
+
+    pub fn set_fee_schedule(&mut self, fee_schedule: FeeSchedule) {
+        self.fee_schedule = fee_schedule;
+    }
+
+    pub fn set_margin_mode(&mut self, margin_mode: MarginMode) {
+        self.margin_mode = margin_mode;
+    }
+
+    /// Base kline interval loaded for the symbol, 5m unless set. Symbols traded
+    /// together must share the same base interval.
+    pub fn set_symbol_interval(&mut self, symbol: &str, interval: Interval) {
+        self.symbol_intervals.set_interval(symbol, interval);
+    }
+
+    pub fn get_symbol_interval(&self, symbol: &str) -> Interval {
+        self.symbol_intervals.get_interval(symbol)
+    }
+
+    pub fn set_funding_rates(&mut self, funding_rates: HashMap<String, Vec<FundingRate>>) {
+        self.funding_rates = Arc::new(funding_rates);
+    }
+
+    /// Mark price klines checked for liquidations, see `StrategyEngine`.
+    pub fn set_mark_price_klines(&mut self, mark_price_klines: HashMap<String, Vec<Kline>>) {
+        self.mark_price_klines = Arc::new(mark_price_klines);
+    }
+
+    /// Maintenance margin brackets per symbol, the BTCUSDT ones otherwise.
+    pub fn set_margin_brackets(&mut self, margin_brackets: HashMap<String, MarginBrackets>) {
+        self.margin_brackets = margin_brackets;
+    }
+
+    /// Use preloaded data instead of fetching it from Mongo.
+    pub fn set_data(
+        &mut self,
+        kline_data: HashMap<String, Vec<Kline>>,
+        symbol_infos: HashMap<String, SymbolInfo>,
+    ) {
+        self.kline_data = Arc::new(kline_data);
+        self.symbol_infos = symbol_infos;
+    }
+
+    pub async fn load_data(&mut self) {
+        match self.mongo_client.get_exchange_info().await {
+            Some(exchange_info) => {
+                self.symbol_infos = exchange_info.get_symbol_info_map(&self.symbols);
+            }
+            None => {
+                tracing::error!("Failed to get exchange info");
+            }
+        }
+        for symbol in &self.symbols {
+            match self
+                .mongo_client
+                .fetch_klines(symbol, &self.get_symbol_interval(symbol), self.start_date)
+                .await
+            {
+                Ok(Some(klines)) => {
+                    tracing::info!("Get {} klines for symbol: {}", klines.len(), symbol);
+                    Arc::make_mut(&mut self.kline_data).insert(symbol.clone(), klines);
+                }
+                Ok(None) => {
+                    tracing::info!("No klines for symbol: {}", symbol);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to get klines for symbol: {}, error: {}", symbol, e);
+                }
+            }
+            match self
+                .mongo_client
+                .fetch_funding_rates(symbol, self.start_date)
+                .await
+            {
+                Ok(Some(funding_rates)) => {
+                    Arc::make_mut(&mut self.funding_rates).insert(symbol.clone(), funding_rates);
+                }
+                Ok(None) => {
+                    tracing::info!("No funding rates for symbol: {}", symbol);
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to get funding rates for symbol: {}, error: {}",
+                        symbol,
+                        e
+                    );
+                }
+            }
+            match self
+                .mongo_client
+                .fetch_price_klines(
+                    PriceType::Mark,
+                    symbol,
+                    &self.get_symbol_interval(symbol),
+                    self.start_date,
+                )
+                .await
+            {
+                Ok(Some(klines)) => {
+                    Arc::make_mut(&mut self.mark_price_klines).insert(symbol.clone(), klines);
+                }
+                Ok(None) => {
+                    tracing::warn!(
+                        "No mark price klines for symbol: {}, liquidations use last prices",
+                        symbol
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to get mark price klines for symbol: {}, error: {}",
+                        symbol,
+                        e
+                    );
+                }
+            }
+            match self.mongo_client.fetch_margin_brackets(symbol).await {
+                Ok(Some(margin_brackets)) => {
+                    self.margin_brackets.insert(symbol.clone(), margin_brackets);
+                }
+                Ok(None) => {
+                    tracing::warn!(
+                        "No margin brackets for symbol: {}, using the BTCUSDT ones",
+                        symbol
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to get margin brackets for symbol: {}, error: {}",
+                        symbol,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Loads the data from Mongo and runs the grid search.
+    pub async fn optimize<F>(&mut self, factory: F) -> Vec<OptimizationResult>
+    where
+        F: Fn(&HashMap<String, f64>) -> Box<dyn BaseStrategy> + Sync,
+    {
+        self.load_data().await;
+        self.run(factory)
+    }
+
+    /// Backtests every parameter set on the loaded data and returns the results
+    /// sorted by the objective, best first.
+    pub fn run<F>(&self, factory: F) -> Vec<OptimizationResult>
+    where
+        F: Fn(&HashMap<String, f64>) -> Box<dyn BaseStrategy> + Sync,
+    {
+        if !self.has_data() {
+            return vec![];
+        }
+        self.run_on(&self.kline_data, None, self.starting_cash, &factory)
+    }
+
+    pub(crate) fn has_data(&self) -> bool {
+        for symbol in &self.symbols {
+            if !self.kline_data.contains_key(symbol) || !self.symbol_infos.contains_key(symbol) {
+                tracing::error!("Missing klines or symbol info for symbol: {}", symbol);
+                return false;
+            }
+        }
+        true
+    }
+
+    pub(crate) fn get_symbols(&self) -> &Vec<String> {
+        &self.symbols
+    }
+
+    pub(crate) fn get_starting_cash(&self) -> f64 {
+        self.starting_cash
+    }
+
+    pub(crate) fn get_kline_data(&self) -> &HashMap<String, Vec<Kline>> {
+        &self.kline_data
+    }
+
+    /// Grid search on the given klines, trading from `trade_start` if set. Every
+    /// backtest shares the klines instead of copying them.
+    pub(crate) fn run_on<F>(
+        &self,
+        kline_data: &Arc<HashMap<String, Vec<Kline>>>,
+        trade_start: Option<i64>,
+        starting_cash: f64,
+        factory: &F,
+    ) -> Vec<OptimizationResult>
+    where
+        F: Fn(&HashMap<String, f64>) -> Box<dyn BaseStrategy> + Sync,
+    {
+        let grid = parameter_grid(&self.parameter_ranges);
+        tracing::info!(
+            "Optimize {} parameter sets on {} threads by {}",
+            grid.len(),
+            self.threads,
+            self.objective.string()
+        );
+
+        let next_idx = AtomicUsize::new(0);
+        let results = Mutex::new(Vec::with_capacity(grid.len()));
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads.min(grid.len()) {
+                scope.spawn(|| loop {
+                    let idx = next_idx.fetch_add(1, Ordering::Relaxed);
+                    let Some(params) = grid.get(idx) else {
+                        break;
+                    };
+                    let report =
+                        self.back_test(kline_data, trade_start, starting_cash, factory(params));
+                    results.lock().unwrap().push(OptimizationResult {
+                        params: params.clone(),
+                        score: self.objective.score(&report),
+                        report,
+                    });
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results
+    }
+
+    pub(crate) fn back_test(
+        &self,
+        kline_data: &Arc<HashMap<String, Vec<Kline>>>,
+        trade_start: Option<i64>,
+        starting_cash: f64,
+        strategy: Box<dyn BaseStrategy>,
+    ) -> BacktestReport {
+        let mut portfolio =
+            StrategyPortfolio::new(starting_cash, self.leverage_rate, self.symbols.clone());
+        portfolio.set_symbol_infos(self.symbol_infos.clone());
+        portfolio.set_fee_schedule(self.fee_schedule.clone());
+        portfolio.set_margin_mode(self.margin_mode);
+        for (symbol, margin_brackets) in &self.margin_brackets {
+            portfolio.set_margin_brackets(symbol, margin_brackets.clone());
+        }
+        let mut engine = StrategyEngine::new(
+            self.symbols.clone(),
+            portfolio,
+            HashMap::new(),
+            self.start_date,
+            strategy,
+            TradeMode::BackTest,
+        );
+        engine.set_kline_data(kline_data.clone());
+        engine.set_fill_model(self.fill_model.clone());
+        engine.set_funding_rates(self.funding_rates.clone());
+        engine.set_mark_price_klines(self.mark_price_klines.clone());
+        engine.set_symbol_intervals(self.symbol_intervals.clone());
+        if let Some(trade_start) = trade_start {
+            engine.set_trade_start(trade_start);
+        }
+        engine.back_test()
+    }
+
+    /// Writes one row per parameter set: the rank, the parameters in range order,
+    /// the objective score and the report summary.
+    pub fn write_csv(&self, results: &[OptimizationResult], path: &str) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        let param_names = self
+            .parameter_ranges
+            .iter()
+            .map(|r| r.get_name())
+            .collect::<Vec<&str>>();
+        writeln!(
+            file,
+            "rank,{},score,{}",
+            param_names.join(","),
+            BacktestReport::summary_csv_header()
+        )?;
+        for (rank, result) in results.iter().enumerate() {
+            let params = param_names
+                .iter()
+                .map(|name| result.params.get(*name).unwrap_or(&f64::NAN).to_string())
+                .collect::<Vec<String>>();
+            writeln!(
+                file,
+                "{},{},{},{}",
+                rank + 1,
+                params.join(","),
+                result.score,
+                result.report.summary_csv_row()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_strategy::TargetPositionAdapter;
+    use crate::test_utils::{btcusdt_symbol_info, make_klines, HoldStrategy};
+
+    #[test]
+    fn test_parameter_grid() {
+        let grid = parameter_grid(&[
+            ParameterRange::from_step("period", 10.0, 30.0, 10.0),
+            ParameterRange::new("multiplier", vec![1.5, 2.0]),
+        ]);
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[0].get("period"), Some(&10.0));
+        assert_eq!(grid[5].get("period"), Some(&30.0));
+        assert_eq!(grid[5].get("multiplier"), Some(&2.0));
+    }
+
+    #[test]
+    fn test_optimizer_run() {
+        let symbols = vec!["btcusdt".to_string()];
+        let klines = make_klines(50, 100.0, 1.0);
+        let mut optimizer = Optimizer::new(
+            symbols,
+            10000.0,
+            10.0,
+            0,
+            vec![ParameterRange::new("qty", vec![1.0, 3.0, 2.0])],
+            Objective::TotalReturn,
+        );
+        optimizer.set_threads(2);
+        optimizer.set_data(
+            HashMap::from([("btcusdt".to_string(), klines)]),
+            HashMap::from([("btcusdt".to_string(), btcusdt_symbol_info())]),
+        );
+        let results = optimizer.run(|params| {
+            Box::new(TargetPositionAdapter::new(HoldStrategy(
+                *params.get("qty").unwrap(),
+            )))
+        });
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].get_params().get("qty"), Some(&3.0));
+        assert_eq!(results[2].get_params().get("qty"), Some(&1.0));
+        assert!(results[0].get_score() > results[1].get_score());
+    }
+}
@@ -0,0 +1,1149 @@
+use crate::base_strategy::BaseStrategy;
+use crate::fill_model::{FillModel, NextOpenFillModel};
+use public::base_enum::market_enums::{Interval, PriceType, SymbolIntervals};
+use public::base_enum::order_enums::OrderStatus;
+use public::base_model::api_model::MarketData;
+use public::base_model::market_model::depth_model::Depth;
+use public::base_model::market_model::funding_rate_model::FundingRate;
+use public::base_model::market_model::kline_model::{CombineKline, Kline};
+use public::base_model::market_model::trade_model::Trade;
+use public::base_model::trade_model::order_model::Order;
+use public::strategy_model::backtest_report::BacktestReport;
+use public::strategy_model::market_context::MarketContext;
+use public::strategy_model::strategy_portfolio::StrategyPortfolio;
+use public::tools::time_tools;
+use services::mongo_engine::MongoEngine;
+use services::order_manager::order_client::order_service::MakeOrderRequest;
+use services::order_manager::order_client::{
+    convert_reply_into_order, convert_reply_into_position, GeneralOrderClient,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+/// Recorded market data and timers replayed between backtest klines.
+enum BackTestEvent<'a> {
+    Depth(&'a str, &'a Depth),
+    Trade(&'a str, &'a Trade),
+    Timer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TradeMode {
+    /// Replay stored klines, filling orders at the next kline open.
+    BackTest,
+    /// Live klines with orders filled locally on the next kline, as in backtests.
+    PaperTrade,
+    /// Live klines with orders routed to the order service.
+    RealTrade,
+}
+
+pub struct StrategyEngine {
+    symbols: Vec<String>,
+    mongo_client: MongoEngine,
+    portfolio: StrategyPortfolio,
+    kline_data: Arc<HashMap<String, Vec<Kline>>>,
+    start_date: i64,
+    strategy: Box<dyn BaseStrategy>,
+    trade_mode: TradeMode,
+    order_client: GeneralOrderClient,
+    market_receiver: Option<Receiver<MarketData>>,
+    order_receiver: Option<Receiver<Order>>,
+    live_klines: HashMap<String, Kline>,
+    trade_start: Option<i64>,
+    fill_model: Arc<dyn FillModel>,
+    depth_data: HashMap<String, BTreeMap<i64, Depth>>,
+    trade_data: HashMap<String, Vec<Trade>>,
+    funding_rates: Arc<HashMap<String, Vec<FundingRate>>>,
+    mark_price_klines: Arc<HashMap<String, Vec<Kline>>>,
+    symbol_intervals: SymbolIntervals,
+    paper_orders: HashMap<String, Order>,
+    combine_klines: HashMap<String, Vec<CombineKline>>,
+    market_context: MarketContext,
+}
+
+impl StrategyEngine {
+    pub fn new(
+        symbols: Vec<String>,
+        portfolio: StrategyPortfolio,
+        kline_data: HashMap<String, Vec<Kline>>,
+        start_date: i64,
+        strategy: Box<dyn BaseStrategy>,
+        trade_mode: TradeMode,
+    ) -> Self {
+        let mongo_client = MongoEngine::default();
+        StrategyEngine {
+            symbols,
+            mongo_client,
+            portfolio,
+            kline_data: Arc::new(kline_data),
+            start_date,
+            strategy,
+            trade_mode,
+            order_client: GeneralOrderClient::default(),
+            market_receiver: None,
+            order_receiver: None,
+            live_klines: HashMap::new(),
+            trade_start: None,
+            fill_model: Arc::new(NextOpenFillModel),
+            depth_data: HashMap::new(),
+            trade_data: HashMap::new(),
+            funding_rates: Arc::new(HashMap::new()),
+            mark_price_klines: Arc::new(HashMap::new()),
+            symbol_intervals: SymbolIntervals::default(),
+            paper_orders: HashMap::new(),
+            combine_klines: HashMap::new(),
+            market_context: MarketContext::default(),
+        }
+    }
+
+    /// Paper and real trading consume klines broadcast by a MarketDataEngine.
+    pub fn set_market_receiver(&mut self, market_receiver: Receiver<MarketData>) {
+        self.market_receiver = Some(market_receiver);
+    }
+
+    /// Real trading reconciles fills from order updates broadcast by an OrderListener
+    /// or a SimulatedOrderService.
+    pub fn set_order_receiver(&mut self, order_receiver: Receiver<Order>) {
+        self.order_receiver = Some(order_receiver);
+    }
+
+    /// Backtests only trade from `trade_start`; earlier klines just warm up the strategy.
+    pub fn set_trade_start(&mut self, trade_start: i64) {
+        self.trade_start = Some(trade_start);
+    }
+
+    /// Klines shared with other backtests, e.g. by the Optimizer, replacing those
+    /// passed to `new`.
+    pub fn set_kline_data(&mut self, kline_data: Arc<HashMap<String, Vec<Kline>>>) {
+        self.kline_data = kline_data;
+    }
+
+    /// Backtest orders fill at the next kline open by default.
+    pub fn set_fill_model(&mut self, fill_model: Arc<dyn FillModel>) {
+        self.fill_model = fill_model;
+    }
+
+    /// Recorded order books keyed by timestamp, used by depth-based fill models.
+    pub fn set_depth_data(&mut self, depth_data: HashMap<String, BTreeMap<i64, Depth>>) {
+        self.depth_data = depth_data;
+    }
+
+    /// Recorded trades sorted by time, replayed through `on_trade` in backtests.
+    pub fn set_trade_data(&mut self, trade_data: HashMap<String, Vec<Trade>>) {
+        self.trade_data = trade_data;
+    }
+
+    /// Base kline interval loaded for the symbol, 5m unless set. Symbols traded
+    /// together must share the same base interval.
+    pub fn set_symbol_interval(&mut self, symbol: &str, interval: Interval) {
+        self.symbol_intervals.set_interval(symbol, interval);
+    }
+
+    pub fn set_symbol_intervals(&mut self, symbol_intervals: SymbolIntervals) {
+        self.symbol_intervals = symbol_intervals;
+    }
+
+    pub fn get_symbol_interval(&self, symbol: &str) -> Interval {
+        self.symbol_intervals.get_interval(symbol)
+    }
+
+    /// Funding rates settled on open positions during backtests, sorted by time. Shared
+    /// with other backtests like `set_kline_data`.
+    pub fn set_funding_rates(&mut self, funding_rates: Arc<HashMap<String, Vec<FundingRate>>>) {
+        self.funding_rates = funding_rates;
+    }
+
+    /// Mark price klines sorted by time, checked for backtest liquidations. Symbols
+    /// without them are checked against their trade klines.
+    pub fn set_mark_price_klines(&mut self, mark_price_klines: Arc<HashMap<String, Vec<Kline>>>) {
+        self.mark_price_klines = mark_price_klines;
+    }
+
+    async fn prepare_data(&mut self) {
+        self.load_symbol_infos().await;
+        self.load_history_klines().await;
+        if self.trade_mode == TradeMode::BackTest {
+            self.load_funding_rates().await;
+            self.load_mark_price_klines().await;
+            self.load_margin_brackets().await;
+        }
+    }
+
+    async fn load_mark_price_klines(&mut self) {
+        for symbol in &self.symbols {
+            match self
+                .mongo_client
+                .fetch_price_klines(
+                    PriceType::Mark,
+                    symbol,
+                    &self.get_symbol_interval(symbol),
+                    self.start_date,
+                )
+                .await
+            {
+                Ok(Some(klines)) => {
+                    Arc::make_mut(&mut self.mark_price_klines).insert(symbol.clone(), klines);
+                    tracing::info!("Get mark price klines for symbol: {}", symbol);
+                }
+                Ok(None) => {
+                    tracing::warn!(
+                        "No mark price klines for symbol: {}, liquidations use last prices",
+                        symbol
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to get mark price klines for symbol: {}, error: {}",
+                        symbol,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    async fn load_margin_brackets(&mut self) {
+        for symbol in &self.symbols {
+            match self.mongo_client.fetch_margin_brackets(symbol).await {
+                Ok(Some(margin_brackets)) => {
+                    self.portfolio.set_margin_brackets(symbol, margin_brackets);
+                    tracing::info!("Get margin brackets for symbol: {}", symbol);
+                }
+                Ok(None) => {
+                    tracing::warn!(
+                        "No margin brackets for symbol: {}, using the BTCUSDT ones",
+                        symbol
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to get margin brackets for symbol: {}, error: {}",
+                        symbol,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    async fn load_funding_rates(&mut self) {
+        for symbol in &self.symbols {
+            match self
+                .mongo_client
+                .fetch_funding_rates(symbol, self.start_date)
+                .await
+            {
+                Ok(Some(funding_rates)) => {
+                    Arc::make_mut(&mut self.funding_rates).insert(symbol.clone(), funding_rates);
+                    tracing::info!("Get funding rates for symbol: {}", symbol);
+                }
+                Ok(None) => {
+                    tracing::info!("No funding rates for symbol: {}", symbol);
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to get funding rates for symbol: {}, error: {}",
+                        symbol,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    async fn load_symbol_infos(&mut self) {
+        match self.mongo_client.get_exchange_info().await {
+            Some(exchange_info) => {
+                let symbol_infos = exchange_info.get_symbol_info_map(&self.symbols);
+                self.portfolio.set_symbol_infos(symbol_infos);
+                tracing::info!("Get symbol info from exchange info");
+            }
+            None => {
+                tracing::error!("Failed to get exchange info");
+            }
+        }
+    }
+
+    async fn load_history_klines(&mut self) {
+        for symbol in &self.symbols {
+            match self
+                .mongo_client
+                .fetch_klines(symbol, &self.get_symbol_interval(symbol), self.start_date)
+                .await
+            {
+                Ok(stored_klines) => match stored_klines {
+                    Some(klines) => {
+                        Arc::make_mut(&mut self.kline_data).insert(symbol.clone(), klines);
+                        tracing::info!("Get klines for symbol: {}", symbol);
+                    }
+                    None => {
+                        tracing::info!("No klines for symbol: {}", symbol);
+                    }
+                },
+                Err(e) => {
+                    tracing::error!("Failed to get klines for symbol: {}, error: {}", symbol, e);
+                }
+            }
+        }
+    }
+
+    fn format_his_klines(&self) -> Vec<HashMap<String, Kline>> {
+        let mut res: Vec<HashMap<String, Kline>> = Vec::new();
+        let len = self.kline_data.get(&self.symbols[0]).unwrap().len();
+        for i in 0..len {
+            let mut klines: HashMap<String, Kline> = HashMap::new();
+            for symbol in &self.symbols {
+                klines.insert(symbol.clone(), self.kline_data.get(symbol).unwrap()[i]);
+            }
+            res.push(klines);
+        }
+        res
+    }
+
+    /// Mark price klines opened together with `klines`.
+    fn get_mark_price_klines(&self, klines: &HashMap<String, Kline>) -> HashMap<String, Kline> {
+        let mut res = HashMap::new();
+        for (symbol, kline) in klines {
+            let Some(mark_klines) = self.mark_price_klines.get(symbol) else {
+                continue;
+            };
+            if let Ok(idx) =
+                mark_klines.binary_search_by_key(&kline.get_open_time(), |k| k.get_open_time())
+            {
+                res.insert(symbol.clone(), mark_klines[idx]);
+            }
+        }
+        res
+    }
+
+    /// Symbols without kline data to backtest on.
+    fn get_missing_kline_symbols(&self) -> Vec<String> {
+        self.symbols
+            .iter()
+            .filter(|s| !self.kline_data.contains_key(*s))
+            .cloned()
+            .collect()
+    }
+
+    /// Runs the engine in its trade mode. Backtests return their report once the
+    /// kline data is exhausted, or `None` if a symbol has no klines; live modes
+    /// return `None` when the market feed closes.
+    pub async fn run(&mut self) -> Option<BacktestReport> {
+        self.prepare_data().await;
+        match self.trade_mode {
+            TradeMode::BackTest => {
+                let missing_symbols = self.get_missing_kline_symbols();
+                if !missing_symbols.is_empty() {
+                    tracing::error!("No klines to backtest for symbols: {:?}", missing_symbols);
+                    return None;
+                }
+                Some(self.back_test())
+            }
+            TradeMode::PaperTrade | TradeMode::RealTrade => {
+                self.live_trade().await;
+                None
+            }
+        }
+    }
+
+    fn format_order(&self, orders: HashMap<String, Order>) -> HashMap<String, Order> {
+        let mut res: HashMap<String, Order> = HashMap::new();
+        for (s, order) in orders {
+            let symbol_info = self.portfolio.get_symbol_infos().get(&s).unwrap();
+            let price_prec = symbol_info.get_price_precision();
+            let qty_prec = symbol_info.get_quantity_precision();
+            let mut order = order.clone();
+            order.format_order(price_prec, qty_prec);
+            res.insert(s, order);
+        }
+        res
+    }
+
+    /// Backtests on the kline data passed to `new`, without touching Mongo. The
+    /// portfolio must already hold the symbol infos of every symbol.
+    pub fn back_test(&mut self) -> BacktestReport {
+        let format_klines = self.format_his_klines();
+        let mut pending_orders: HashMap<String, Order> = HashMap::new();
+        let mut funding_idx: HashMap<String, usize> = HashMap::new();
+        let mut last_time: Option<i64> = None;
+        let mut next_timer: Option<i64> = None;
+        self.init_kline_state();
+        self.strategy.on_start(&self.portfolio);
+        for klines in format_klines {
+            let open_time = klines.get(&self.symbols[0]).unwrap().get_open_time();
+            let close_time = klines.get(&self.symbols[0]).unwrap().get_close_time();
+            if self.trade_start.is_some_and(|t| open_time < t) {
+                // orders generated during warm up are discarded
+                self.on_back_test_klines(&klines);
+                self.settle_funding(&mut funding_idx, &klines, false);
+                last_time = Some(close_time);
+                continue;
+            }
+            let mut new_orders: HashMap<String, Order> = HashMap::new();
+            // funding settles on the position held before this kline's fills
+            self.settle_funding(&mut funding_idx, &klines, true);
+            let mut updates = self.fill_pending_orders(&mut pending_orders, &klines);
+            let mark_price_klines = self.get_mark_price_klines(&klines);
+            for order in self
+                .portfolio
+                .check_back_test_liquidation(&klines, &mark_price_klines)
+            {
+                pending_orders.remove(order.get_symbol());
+                updates.push(order);
+            }
+            for order in updates {
+                if let Some(orders) = self.strategy.on_order_update(&order, &self.portfolio) {
+                    new_orders.extend(orders);
+                }
+            }
+            new_orders.extend(self.on_back_test_events(last_time, &mut next_timer, close_time));
+            new_orders.extend(self.on_back_test_klines(&klines));
+            last_time = Some(close_time);
+
+            let mut is_valid = true;
+            for (s, order) in self.format_order(new_orders) {
+                // a new order replaces whatever is still pending for the symbol
+                match self.portfolio.check_live_order(&s, &order) {
+                    Ok(_) => {
+                        pending_orders.insert(s, order);
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to make back test order: {}", e);
+                        is_valid = false;
+                    }
+                }
+            }
+            if !is_valid {
+                break;
+            }
+            self.portfolio.update_back_test_market_price(&klines);
+            match self.portfolio.update_back_test_value() {
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("Failed to update back test value: {}", e);
+                    break;
+                }
+            }
+            self.portfolio.update_pnl_records(open_time)
+        }
+        self.strategy.on_stop(&self.portfolio);
+        let report = BacktestReport::new(&self.strategy.get_strategy_name(), &self.portfolio);
+        report.show_summary();
+        report
+    }
+
+    /// Sizes the market context and combines each symbol's base klines into the
+    /// strategy's higher timeframes.
+    fn init_kline_state(&mut self) {
+        self.market_context = MarketContext::new(self.strategy.get_history_size());
+        self.combine_klines.clear();
+        let intervals = self.strategy.get_intervals();
+        for symbol in &self.symbols {
+            let base_interval = self.get_symbol_interval(symbol);
+            let mut combine_klines = Vec::new();
+            for interval in &intervals {
+                // bars are built from whole base klines
+                if interval.get_millis() <= base_interval.get_millis()
+                    || interval.get_millis() % base_interval.get_millis() != 0
+                {
+                    tracing::warn!(
+                        "Skip interval {} not a multiple of the {} base interval of {}",
+                        interval.get_interval_string(),
+                        base_interval.get_interval_string(),
+                        symbol
+                    );
+                    continue;
+                }
+                let mut combine_kline = CombineKline::new(vec![], *interval);
+                combine_kline.set_base_interval(base_interval);
+                combine_klines.push(combine_kline);
+            }
+            self.combine_klines.insert(symbol.clone(), combine_klines);
+        }
+    }
+
+    /// Runs `on_kline` for a closed base kline, then `on_interval_kline` for every
+    /// higher timeframe bar it closes.
+    fn on_closed_kline(&mut self, symbol: &str, kline: &Kline) -> HashMap<String, Order> {
+        let mut res = HashMap::new();
+        self.market_context.add_kline(symbol, *kline);
+        if let Some(orders) =
+            self.strategy
+                .on_kline(symbol, kline, &self.market_context, &self.portfolio)
+        {
+            res.extend(orders);
+        }
+        let Some(combine_klines) = self.combine_klines.get_mut(symbol) else {
+            return res;
+        };
+        let mut bars = Vec::new();
+        for combine_kline in combine_klines.iter_mut() {
+            combine_kline.add(*kline);
+            if let Some(bar) = combine_kline.get_kline() {
+                bars.push((combine_kline.get_interval(), bar));
+            }
+        }
+        for (interval, bar) in bars {
+            if let Some(orders) = self.strategy.on_interval_kline(
+                symbol,
+                interval,
+                &bar,
+                &self.market_context,
+                &self.portfolio,
+            ) {
+                res.extend(orders);
+            }
+        }
+        res
+    }
+
+    /// Runs `on_closed_kline` for every symbol, then `on_schedule`. Later orders
+    /// replace earlier ones of the same symbol.
+    fn on_back_test_klines(&mut self, klines: &HashMap<String, Kline>) -> HashMap<String, Order> {
+        let mut res = HashMap::new();
+        for symbol in self.symbols.clone() {
+            if let Some(kline) = klines.get(&symbol) {
+                res.extend(self.on_closed_kline(&symbol, kline));
+            }
+        }
+        if let Some(orders) =
+            self.strategy
+                .on_schedule(klines, &self.market_context, &self.portfolio)
+        {
+            res.extend(orders);
+        }
+        res
+    }
+
+    /// Replays recorded books and trades after `last_time` and up to `timestamp`
+    /// through `on_depth` and `on_trade`, and fires `on_timer` for every timer boundary
+    /// in between, on kline time. Events run in time order, books first on ties.
+    fn on_back_test_events(
+        &mut self,
+        last_time: Option<i64>,
+        next_timer: &mut Option<i64>,
+        timestamp: i64,
+    ) -> HashMap<String, Order> {
+        let mut events: Vec<(i64, BackTestEvent)> = Vec::new();
+        let start = match last_time {
+            Some(t) => t + 1,
+            None => i64::MIN,
+        };
+        for symbol in &self.symbols {
+            if let Some(depths) = self.depth_data.get(symbol) {
+                for (t, depth) in depths.range(start..=timestamp) {
+                    events.push((*t, BackTestEvent::Depth(symbol, depth)));
+                }
+            }
+            if let Some(trades) = self.trade_data.get(symbol) {
+                let from = trades.partition_point(|trade| trade.get_timestamp() < start);
+                let to = trades.partition_point(|trade| trade.get_timestamp() <= timestamp);
+                for trade in &trades[from..to] {
+                    events.push((trade.get_timestamp(), BackTestEvent::Trade(symbol, trade)));
+                }
+            }
+        }
+        if let Some(interval) = self.strategy.get_timer_interval().filter(|i| *i > 0) {
+            let next = next_timer.get_or_insert((timestamp / interval + 1) * interval);
+            while *next <= timestamp {
+                events.push((*next, BackTestEvent::Timer));
+                *next += interval;
+            }
+        }
+        events.sort_by_key(|(t, _)| *t);
+
+        let mut res = HashMap::new();
+        for (t, event) in events {
+            let orders = match event {
+                BackTestEvent::Depth(symbol, depth) => {
+                    self.strategy.on_depth(symbol, depth, &self.portfolio)
+                }
+                BackTestEvent::Trade(symbol, trade) => {
+                    self.strategy.on_trade(symbol, trade, &self.portfolio)
+                }
+                BackTestEvent::Timer => self.strategy.on_timer(t, &self.portfolio),
+            };
+            if let Some(orders) = orders {
+                res.extend(orders);
+            }
+        }
+        res
+    }
+
+    /// Fills pending orders on the klines through the fill model. Returns the filled
+    /// orders.
+    fn fill_pending_orders(
+        &mut self,
+        pending_orders: &mut HashMap<String, Order>,
+        klines: &HashMap<String, Kline>,
+    ) -> Vec<Order> {
+        let mut filled_orders = Vec::new();
+        for (s, kline) in klines {
+            if let Some(filled_order) = self.fill_pending_order(pending_orders, s, kline) {
+                filled_orders.push(filled_order);
+            }
+        }
+        filled_orders
+    }
+
+    /// Fills the pending order of the symbol on the kline through the fill model. An
+    /// unfilled remainder stays pending unless it falls below the symbol's minimum
+    /// quantity or notional.
+    fn fill_pending_order(
+        &mut self,
+        pending_orders: &mut HashMap<String, Order>,
+        s: &str,
+        cur_kline: &Kline,
+    ) -> Option<Order> {
+        let order = pending_orders.get_mut(s)?;
+        let depth = self.get_depth(s, cur_kline.get_open_time());
+        let fill = self.fill_model.fill(order, cur_kline, depth)?;
+        let filled_qty = fill.get_qty().min(order.get_qty());
+        let mut filled_order = order.clone();
+        filled_order.set_filled_qty(filled_qty);
+        filled_order.set_avg_price(fill.get_price());
+        filled_order.set_timestamp(cur_kline.get_open_time());
+        self.portfolio
+            .fill_back_test_order(s, &filled_order, fill.is_maker());
+
+        // market orders have no price, value the remainder at the fill price
+        let remain_qty = order.get_qty() - filled_qty;
+        let symbol_info = self.portfolio.get_symbol_infos().get(s).unwrap();
+        if remain_qty < symbol_info.get_min_quantity()
+            || remain_qty * fill.get_price() < symbol_info.get_min_notional()
+        {
+            filled_order.set_status(OrderStatus::Filled);
+            pending_orders.remove(s);
+        } else {
+            filled_order.set_status(OrderStatus::PartiallyFilled);
+            order.set_qty(remain_qty);
+        }
+        Some(filled_order)
+    }
+
+    /// Settles funding events up to each kline open, at the reported mark price or the
+    /// open when there is none. Events are only skipped when `apply` is false.
+    fn settle_funding(
+        &mut self,
+        funding_idx: &mut HashMap<String, usize>,
+        klines: &HashMap<String, Kline>,
+        apply: bool,
+    ) {
+        for (s, kline) in klines {
+            let Some(funding_rates) = self.funding_rates.get(s) else {
+                continue;
+            };
+            let idx = funding_idx.entry(s.clone()).or_insert(0);
+            while *idx < funding_rates.len()
+                && funding_rates[*idx].get_funding_time() <= kline.get_open_time()
+            {
+                let funding_rate = &funding_rates[*idx];
+                if apply {
+                    let mark_price = if funding_rate.get_mark_price() > 0.0 {
+                        funding_rate.get_mark_price()
+                    } else {
+                        kline.get_open()
+                    };
+                    self.portfolio.apply_funding(s, funding_rate, mark_price);
+                }
+                *idx += 1;
+            }
+        }
+    }
+
+    /// Latest recorded book at or before `timestamp`.
+    fn get_depth(&self, symbol: &str, timestamp: i64) -> Option<&Depth> {
+        self.depth_data
+            .get(symbol)?
+            .range(..=timestamp)
+            .next_back()
+            .map(|(_, depth)| depth)
+    }
+
+    fn warm_up(&mut self) {
+        if self.kline_data.len() != self.symbols.len() {
+            return;
+        }
+        let format_klines = self.format_his_klines();
+        tracing::info!("Warm up strategy with {} klines", format_klines.len());
+        for klines in format_klines {
+            // orders generated on history are discarded
+            self.on_back_test_klines(&klines);
+        }
+    }
+
+    async fn live_trade(&mut self) {
+        let mut market_receiver = match self.market_receiver.take() {
+            Some(market_receiver) => market_receiver,
+            None => {
+                tracing::error!("Market receiver is not set");
+                return;
+            }
+        };
+        // paper trading fills locally and never receives order updates
+        let mut order_receiver = match self.trade_mode {
+            TradeMode::RealTrade => match self.order_receiver.take() {
+                Some(order_receiver) => Some(order_receiver),
+                None => {
+                    tracing::error!("Order receiver is not set");
+                    return;
+                }
+            },
+            _ => None,
+        };
+        self.init_kline_state();
+        self.strategy.on_start(&self.portfolio);
+        self.warm_up();
+        if self.trade_mode == TradeMode::RealTrade {
+            self.order_client.connect().await;
+            self.sync_live_account().await;
+        }
+        tracing::info!(
+            "Start {:?}: {}",
+            self.trade_mode,
+            self.strategy.get_strategy_name()
+        );
+
+        let mut timer = self
+            .strategy
+            .get_timer_interval()
+            .filter(|i| *i > 0)
+            .map(|i| tokio::time::interval(std::time::Duration::from_millis(i as u64)));
+        loop {
+            tokio::select! {
+                market_data = market_receiver.recv() => match market_data {
+                    Ok(market_data) => self.on_market_data(market_data).await,
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("Market data receiver lagged by {} messages", n);
+                    }
+                    Err(RecvError::Closed) => {
+                        tracing::error!("Market data channel closed");
+                        break;
+                    }
+                },
+                order = Self::recv_order(&mut order_receiver) => match order {
+                    Ok(order) => {
+                        self.portfolio.update_live_order(&order);
+                        let orders = self.strategy.on_order_update(&order, &self.portfolio);
+                        self.dispatch_live_orders(orders).await;
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("Order receiver lagged by {} messages", n);
+                    }
+                    Err(RecvError::Closed) => {
+                        tracing::error!("Order channel closed");
+                        break;
+                    }
+                },
+                _ = Self::tick(&mut timer) => {
+                    let timestamp = time_tools::get_now_timestamp();
+                    let orders = self.strategy.on_timer(timestamp, &self.portfolio);
+                    self.dispatch_live_orders(orders).await;
+                }
+            }
+        }
+        self.strategy.on_stop(&self.portfolio);
+    }
+
+    /// Seeds the portfolio with the account balance and positions, and adopts the open
+    /// orders of the strategy left by a previous session so their fills are applied.
+    async fn sync_live_account(&mut self) {
+        let balance = match self.order_client.get_balance().await {
+            Some(balance) => balance.balance,
+            None => {
+                tracing::warn!("Fetch balance failed, keep the starting cash");
+                self.portfolio.get_starting_cash()
+            }
+        };
+        let positions = match self.order_client.get_positions(String::new()).await {
+            Some(positions) => positions
+                .positions
+                .iter()
+                .map(convert_reply_into_position)
+                .collect(),
+            None => {
+                tracing::warn!("Fetch positions failed, start flat");
+                Vec::new()
+            }
+        };
+        self.portfolio.sync_live_account(balance, positions);
+
+        let Some(open_orders) = self.order_client.list_open_orders(String::new()).await else {
+            tracing::warn!("Fetch open orders failed, none adopted");
+            return;
+        };
+        let strategy_name = self.strategy.get_strategy_name();
+        for order in open_orders.orders.iter().map(convert_reply_into_order) {
+            if order.get_strategy_name() == strategy_name
+                && self.symbols.iter().any(|s| s == order.get_symbol())
+            {
+                self.portfolio.add_live_order(order);
+            }
+        }
+    }
+
+    async fn tick(timer: &mut Option<tokio::time::Interval>) {
+        match timer {
+            Some(timer) => {
+                timer.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    async fn recv_order(order_receiver: &mut Option<Receiver<Order>>) -> Result<Order, RecvError> {
+        match order_receiver {
+            Some(order_receiver) => order_receiver.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    async fn on_market_data(&mut self, market_data: MarketData) {
+        let symbol = market_data.get_symbol().clone();
+        if !self.symbols.contains(&symbol) {
+            return;
+        }
+        if let Some(kline) = market_data.get_kline() {
+            self.fill_paper_order(&symbol, &kline).await;
+            let orders = self.on_closed_kline(&symbol, &kline);
+            self.dispatch_live_orders(Some(orders).filter(|o| !o.is_empty()))
+                .await;
+            self.live_klines.insert(symbol, kline);
+            // schedule once every symbol has closed the same bar
+            if self.live_klines.len() == self.symbols.len() {
+                let klines = std::mem::take(&mut self.live_klines);
+                self.on_live_klines(klines).await;
+            }
+        } else if let Some(depth) = market_data.get_depth() {
+            let orders = self.strategy.on_depth(&symbol, &depth, &self.portfolio);
+            self.dispatch_live_orders(orders).await;
+        } else if let Some(trade) = market_data.get_trade() {
+            let orders = self.strategy.on_trade(&symbol, &trade, &self.portfolio);
+            self.dispatch_live_orders(orders).await;
+        }
+    }
+
+    /// Fills the pending paper order of the symbol on its next kline before the kline
+    /// callbacks run, as backtests do. Orders placed on the fill wait for the next kline.
+    async fn fill_paper_order(&mut self, symbol: &str, kline: &Kline) {
+        let mut paper_orders = std::mem::take(&mut self.paper_orders);
+        let filled_order = self.fill_pending_order(&mut paper_orders, symbol, kline);
+        self.paper_orders = paper_orders;
+        if let Some(order) = filled_order {
+            let orders = self.strategy.on_order_update(&order, &self.portfolio);
+            self.dispatch_live_orders(orders).await;
+        }
+    }
+
+    async fn on_live_klines(&mut self, klines: HashMap<String, Kline>) {
+        self.portfolio.update_back_test_market_price(&klines);
+        if let Err(e) = self.portfolio.update_back_test_value() {
+            tracing::error!("Failed to update live value: {}", e);
+        }
+        self.portfolio
+            .update_pnl_records(klines.get(&self.symbols[0]).unwrap().get_close_time());
+
+        let orders = self
+            .strategy
+            .on_schedule(&klines, &self.market_context, &self.portfolio);
+        self.dispatch_live_orders(orders).await;
+    }
+
+    async fn dispatch_live_orders(&mut self, orders: Option<HashMap<String, Order>>) {
+        let Some(orders) = orders else {
+            return;
+        };
+        let orders = self.format_order(orders);
+        match self.trade_mode {
+            TradeMode::RealTrade => self.submit_live_orders(orders).await,
+            _ => self.make_paper_orders(orders),
+        }
+    }
+
+    /// Paper orders stay pending until the next kline of their symbol, a new order
+    /// replaces the pending one.
+    fn make_paper_orders(&mut self, orders: HashMap<String, Order>) {
+        for (symbol, order) in orders {
+            match self.portfolio.check_live_order(&symbol, &order) {
+                Ok(_) => {
+                    self.paper_orders.insert(symbol, order);
+                }
+                Err(e) => tracing::error!("Failed to make paper order: {}", e),
+            }
+        }
+    }
+
+    async fn submit_live_orders(&mut self, orders: HashMap<String, Order>) {
+        let strategy_name = self.strategy.get_strategy_name();
+        for (symbol, mut order) in orders {
+            // a new signal replaces any order still resting for the symbol
+            let stale_cids = self
+                .portfolio
+                .get_live_orders(&symbol)
+                .iter()
+                .map(|o| o.get_cid().to_string())
+                .collect::<Vec<String>>();
+            for cid in stale_cids {
+                self.order_client
+                    .cancel_order(symbol.to_uppercase(), cid)
+                    .await;
+            }
+
+            if let Err(e) = self.portfolio.check_live_order(&symbol, &order) {
+                tracing::error!("Skip live order: {}", e);
+                continue;
+            }
+            match self
+                .order_client
+                .make_order_request(MakeOrderRequest {
+                    symbol: symbol.to_uppercase(),
+                    side: order.get_side().string(),
+                    price: order.get_price(),
+                    quantity: order.get_qty(),
+                    strategy: strategy_name.clone(),
+                    order_type: order.get_order_type().string(),
+                    ..Default::default()
+                })
+                .await
+            {
+                Some(reply) => {
+                    order.set_symbol(&symbol);
+                    order.set_cid(&reply.order_cid);
+                    self.portfolio.add_live_order(order);
+                }
+                None => {
+                    tracing::error!("Failed to place live order: {}", order.string_order());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fill_model::{Slippage, VolumeParticipationFillModel};
+    use crate::test_utils::{btcusdt_symbol_info, make_klines};
+    use public::base_enum::order_enums::{OrderSide, OrderType};
+    use public::base_model::market_model::depth_model::PriceLevel;
+    use public::strategy_model::market_context::MarketContext;
+    use std::sync::Mutex;
+
+    /// Records its callbacks and buys once on the first schedule.
+    struct EventLogStrategy {
+        events: Arc<Mutex<Vec<String>>>,
+        ordered: bool,
+        intervals: Vec<Interval>,
+    }
+
+    impl EventLogStrategy {
+        fn log(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl BaseStrategy for EventLogStrategy {
+        fn on_schedule(
+            &mut self,
+            klines: &HashMap<String, Kline>,
+            _context: &MarketContext,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, Order>> {
+            let kline = klines.get("btcusdt").unwrap();
+            self.log(format!("schedule {}", kline.get_open_time()));
+            if self.ordered {
+                return None;
+            }
+            self.ordered = true;
+            let mut order = Order::default();
+            order.set_symbol("btcusdt");
+            order.set_price(kline.get_close());
+            order.set_qty(1.0);
+            order.set_side(OrderSide::BUY);
+            Some(HashMap::from([("btcusdt".to_string(), order)]))
+        }
+
+        fn on_depth(
+            &mut self,
+            _symbol: &str,
+            _depth: &Depth,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, Order>> {
+            self.log("depth".to_string());
+            None
+        }
+
+        fn on_trade(
+            &mut self,
+            _symbol: &str,
+            trade: &Trade,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, Order>> {
+            self.log(format!("trade {}", trade.get_timestamp()));
+            None
+        }
+
+        fn on_order_update(
+            &mut self,
+            order: &Order,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, Order>> {
+            self.log(format!("order {}", order.get_timestamp()));
+            None
+        }
+
+        fn on_timer(
+            &mut self,
+            timestamp: i64,
+            _portfolio: &StrategyPortfolio,
+        ) -> Option<HashMap<String, Order>> {
+            self.log(format!("timer {}", timestamp));
+            None
+        }
+
+        fn get_timer_interval(&self) -> Option<i64> {
+            Some(300_000)
+        }
+
+        fn get_intervals(&self) -> Vec<Interval> {
+            self.intervals.clone()
+        }
+    }
+
+    #[test]
+    fn test_back_test_event_order() {
+        let symbols = vec!["btcusdt".to_string()];
+        let klines = make_klines(2, 100.0, 0.0);
+        let mut portfolio = StrategyPortfolio::new(10000.0, 1.0, symbols.clone());
+        portfolio.set_symbol_infos(HashMap::from([(
+            "btcusdt".to_string(),
+            btcusdt_symbol_info(),
+        )]));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let strategy = EventLogStrategy {
+            events: events.clone(),
+            ordered: false,
+            intervals: vec![],
+        };
+        let mut engine = StrategyEngine::new(
+            symbols,
+            portfolio,
+            HashMap::from([("btcusdt".to_string(), klines)]),
+            0,
+            Box::new(strategy),
+            TradeMode::BackTest,
+        );
+        let depth = Depth::new(
+            vec![PriceLevel::new(100.1, 1.0)],
+            vec![PriceLevel::new(99.9, 1.0)],
+        );
+        engine.set_depth_data(HashMap::from([(
+            "btcusdt".to_string(),
+            BTreeMap::from([(100_000, depth.clone()), (400_000, depth)]),
+        )]));
+        engine.set_trade_data(HashMap::from([(
+            "btcusdt".to_string(),
+            vec![
+                Trade::new(1, 100.0, 1.0, 200_000, false),
+                Trade::new(2, 100.0, 1.0, 500_000, true),
+            ],
+        )]));
+        engine.back_test();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "depth",
+                "trade 200000",
+                "schedule 0",
+                "order 300000",
+                "timer 300000",
+                "depth",
+                "trade 500000",
+                "schedule 300000",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fill_pending_market_order() {
+        let symbols = vec!["btcusdt".to_string()];
+        let mut portfolio = StrategyPortfolio::new(10000.0, 1.0, symbols.clone());
+        portfolio.set_symbol_infos(HashMap::from([(
+            "btcusdt".to_string(),
+            btcusdt_symbol_info(),
+        )]));
+        let strategy = EventLogStrategy {
+            events: Arc::new(Mutex::new(Vec::new())),
+            ordered: false,
+            intervals: vec![],
+        };
+        let mut engine = StrategyEngine::new(
+            symbols,
+            portfolio,
+            HashMap::new(),
+            0,
+            Box::new(strategy),
+            TradeMode::BackTest,
+        );
+        engine.set_fill_model(Arc::new(VolumeParticipationFillModel::new(
+            0.2,
+            Slippage::Fixed(0.0),
+        )));
+        let order = Order::new(
+            "btcusdt",
+            0.0,
+            0.5,
+            OrderSide::BUY,
+            OrderType::Market,
+            0.0,
+            0.0,
+            "",
+            "",
+            OrderStatus::New,
+            0,
+        );
+        let mut pending_orders = HashMap::from([("btcusdt".to_string(), order)]);
+        let kline = &make_klines(1, 100.0, 0.0)[0];
+
+        // 0.2 of the 1.0 kline volume fills, the 0.3 left is worth 30 at the fill price
+        let filled = engine
+            .fill_pending_order(&mut pending_orders, "btcusdt", kline)
+            .unwrap();
+        assert!(matches!(filled.get_status(), OrderStatus::PartiallyFilled));
+        assert!((filled.get_filled_qty() - 0.2).abs() < 1e-9);
+        assert!((pending_orders["btcusdt"].get_qty() - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_init_kline_state() {
+        let symbols = vec!["btcusdt".to_string()];
+        let strategy = EventLogStrategy {
+            events: Arc::new(Mutex::new(Vec::new())),
+            ordered: false,
+            intervals: vec![
+                Interval::Min1,
+                Interval::Min3,
+                Interval::Min5,
+                Interval::Min15,
+                Interval::Hour1,
+            ],
+        };
+        let mut engine = StrategyEngine::new(
+            symbols.clone(),
+            StrategyPortfolio::new(10000.0, 1.0, symbols),
+            HashMap::new(),
+            0,
+            Box::new(strategy),
+            TradeMode::BackTest,
+        );
+        engine.set_symbol_interval("btcusdt", Interval::Min3);
+        engine.init_kline_state();
+        // 1m and 3m are not above the 3m base, 5m does not split into 3m klines
+        let intervals = engine.combine_klines["btcusdt"]
+            .iter()
+            .map(|c| c.get_interval())
+            .collect::<Vec<Interval>>();
+        assert_eq!(intervals, vec![Interval::Min15, Interval::Hour1]);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::base_strategy::TargetPositionStrategy;
+use public::base_model::info_model::SymbolInfo;
+use public::base_model::market_model::kline_model::Kline;
+use public::strategy_model::market_context::MarketContext;
+use public::strategy_model::strategy_portfolio::StrategyPortfolio;
+use public::strategy_model::target_position::TargetPosition;
+use std::collections::HashMap;
+
+/// Holds a btcusdt long of `qty` from the first kline on.
+pub(crate) struct HoldStrategy(pub f64);
+
+impl TargetPositionStrategy for HoldStrategy {
+    fn on_schedule(
+        &mut self,
+        _klines: &HashMap<String, Kline>,
+        _context: &MarketContext,
+        _portfolio: &StrategyPortfolio,
+    ) -> Option<HashMap<String, TargetPosition>> {
+        Some(HashMap::from([(
+            "btcusdt".to_string(),
+            TargetPosition::new(self.0),
+        )]))
+    }
+}
+
+/// `count` 5m klines from time 0. Each opens `step` above the previous one and closes
+/// at the next open, with the high and low 1 away from the open.
+pub(crate) fn make_klines(count: i64, start_price: f64, step: f64) -> Vec<Kline> {
+    (0..count)
+        .map(|i| {
+            let open_time = i * 300_000;
+            let px = start_price + step * i as f64;
+            Kline::new(
+                open_time,
+                open_time + 299_999,
+                px,
+                px + 1.0,
+                px - 1.0,
+                px + step,
+                1.0,
+                1,
+                0.0,
+                0.0,
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn btcusdt_symbol_info() -> SymbolInfo {
+    SymbolInfo::new("btcusdt".to_string(), 2, 3, 5.0, 0.001, 1000.0)
+}
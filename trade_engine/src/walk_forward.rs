@@ -0,0 +1,318 @@
+use crate::base_strategy::BaseStrategy;
+use crate::optimizer::Optimizer;
+use public::base_model::market_model::kline_model::Kline;
+use public::strategy_model::backtest_report::BacktestReport;
+use public::strategy_model::strategy_portfolio::PnlRecord;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Kline index bounds of one walk-forward step. Both samples are preceded by
+/// `warmup_size` klines that only warm up the strategy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WalkForwardWindow {
+    pub in_sample_start: usize,
+    pub out_sample_start: usize,
+    pub out_sample_end: usize,
+}
+
+/// Rolling windows over `len` klines: each in-sample window of `in_sample_size` klines
+/// is followed by an out-of-sample window of up to `out_sample_size` klines, and the
+/// next step shifts both by `out_sample_size` so out-of-sample windows do not overlap.
+pub fn walk_forward_windows(
+    len: usize,
+    in_sample_size: usize,
+    out_sample_size: usize,
+    warmup_size: usize,
+) -> Vec<WalkForwardWindow> {
+    let mut res = Vec::new();
+    if in_sample_size == 0 || out_sample_size == 0 {
+        return res;
+    }
+    let mut in_sample_start = warmup_size;
+    while in_sample_start + in_sample_size < len {
+        let out_sample_start = in_sample_start + in_sample_size;
+        res.push(WalkForwardWindow {
+            in_sample_start,
+            out_sample_start,
+            out_sample_end: (out_sample_start + out_sample_size).min(len),
+        });
+        in_sample_start += out_sample_size;
+    }
+    res
+}
+
+#[derive(Debug, Clone)]
+pub struct WalkForwardStep {
+    window: WalkForwardWindow,
+    params: HashMap<String, f64>,
+    in_sample_score: f64,
+    out_sample_report: BacktestReport,
+}
+
+impl WalkForwardStep {
+    pub fn get_window(&self) -> WalkForwardWindow {
+        self.window
+    }
+
+    /// Best parameters on the in-sample window.
+    pub fn get_params(&self) -> &HashMap<String, f64> {
+        &self.params
+    }
+
+    pub fn get_in_sample_score(&self) -> f64 {
+        self.in_sample_score
+    }
+
+    pub fn get_out_sample_report(&self) -> &BacktestReport {
+        &self.out_sample_report
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WalkForwardResult {
+    steps: Vec<WalkForwardStep>,
+    report: BacktestReport,
+}
+
+impl WalkForwardResult {
+    pub fn get_steps(&self) -> &Vec<WalkForwardStep> {
+        &self.steps
+    }
+
+    /// Report over the stitched out-of-sample equity curve.
+    pub fn get_report(&self) -> &BacktestReport {
+        &self.report
+    }
+
+    pub fn show_summary(&self) {
+        for step in &self.steps {
+            tracing::info!(
+                "Walk forward step {}..{} | params: {:?} | in sample score: {:.4} | out of sample return: {:.4}",
+                step.window.out_sample_start,
+                step.window.out_sample_end,
+                step.params,
+                step.in_sample_score,
+                step.out_sample_report.get_total_return()
+            );
+        }
+        self.report.show_summary();
+    }
+}
+
+/// Walk-forward analysis on top of `Optimizer`: parameters are picked on each
+/// in-sample window by the optimizer objective and evaluated on the following
+/// out-of-sample window. Every out-of-sample run starts flat with the net value the
+/// previous one ended with, so their equity curves chain into one.
+pub struct WalkForward {
+    optimizer: Optimizer,
+    in_sample_size: usize,
+    out_sample_size: usize,
+    warmup_size: usize,
+}
+
+impl WalkForward {
+    pub fn new(optimizer: Optimizer, in_sample_size: usize, out_sample_size: usize) -> Self {
+        Self {
+            optimizer,
+            in_sample_size,
+            out_sample_size,
+            warmup_size: 0,
+        }
+    }
+
+    /// Klines fed to the strategy before each sample without trading.
+    pub fn set_warmup_size(&mut self, warmup_size: usize) {
+        self.warmup_size = warmup_size;
+    }
+
+    pub fn get_optimizer_mut(&mut self) -> &mut Optimizer {
+        &mut self.optimizer
+    }
+
+    /// Loads the data from Mongo and runs the walk-forward analysis.
+    pub async fn optimize<F>(&mut self, factory: F) -> Option<WalkForwardResult>
+    where
+        F: Fn(&HashMap<String, f64>) -> Box<dyn BaseStrategy> + Sync,
+    {
+        self.optimizer.load_data().await;
+        self.run(factory)
+    }
+
+    pub fn run<F>(&self, factory: F) -> Option<WalkForwardResult>
+    where
+        F: Fn(&HashMap<String, f64>) -> Box<dyn BaseStrategy> + Sync,
+    {
+        if !self.optimizer.has_data() {
+            return None;
+        }
+        let symbols = self.optimizer.get_symbols();
+        let kline_data = self.optimizer.get_kline_data();
+        let len = symbols
+            .iter()
+            .map(|s| kline_data.get(s).unwrap().len())
+            .min()
+            .unwrap_or(0);
+        let windows = walk_forward_windows(
+            len,
+            self.in_sample_size,
+            self.out_sample_size,
+            self.warmup_size,
+        );
+        tracing::info!(
+            "Walk forward over {} klines in {} steps",
+            len,
+            windows.len()
+        );
+
+        let starting_cash = self.optimizer.get_starting_cash();
+        let mut cash = starting_cash;
+        let mut steps = Vec::new();
+        for window in windows {
+            let in_sample = self.slice_klines(window.in_sample_start, window.out_sample_start);
+            let trade_start = self.get_open_time(window.in_sample_start);
+            let best = self
+                .optimizer
+                .run_on(&in_sample, Some(trade_start), cash, &factory)
+                .into_iter()
+                .next()?;
+
+            let out_sample = self.slice_klines(window.out_sample_start, window.out_sample_end);
+            let trade_start = self.get_open_time(window.out_sample_start);
+            let report = self.optimizer.back_test(
+                &out_sample,
+                Some(trade_start),
+                cash,
+                factory(best.get_params()),
+            );
+            cash = report.get_final_value();
+            steps.push(WalkForwardStep {
+                window,
+                params: best.get_params().clone(),
+                in_sample_score: best.get_score(),
+                out_sample_report: report,
+            });
+        }
+        if steps.is_empty() {
+            return None;
+        }
+
+        let report = Self::stitch_reports(&steps, starting_cash);
+        Some(WalkForwardResult { steps, report })
+    }
+
+    /// Klines in `start..end` preceded by the warm up klines.
+    fn slice_klines(&self, start: usize, end: usize) -> Arc<HashMap<String, Vec<Kline>>> {
+        let start = start.saturating_sub(self.warmup_size);
+        Arc::new(
+            self.optimizer
+                .get_kline_data()
+                .iter()
+                .map(|(s, klines)| (s.clone(), klines[start..end].to_vec()))
+                .collect(),
+        )
+    }
+
+    fn get_open_time(&self, idx: usize) -> i64 {
+        let symbol = &self.optimizer.get_symbols()[0];
+        self.optimizer.get_kline_data().get(symbol).unwrap()[idx].get_open_time()
+    }
+
+    fn stitch_reports(steps: &[WalkForwardStep], starting_cash: f64) -> BacktestReport {
+        let mut equity_curve = Vec::new();
+        let mut round_trips = Vec::new();
+        let mut traded_notional = 0.0;
+        let mut fees = 0.0;
+        let mut funding = 0.0;
+        for step in steps {
+            let report = &step.out_sample_report;
+            let records = report.get_equity_curve();
+            equity_curve.extend(records.iter().map(|r| {
+                PnlRecord::new(
+                    r.get_timestamp(),
+                    r.get_net_value() - starting_cash,
+                    r.get_net_value(),
+                    r.get_exposure(),
+                    funding + r.get_funding(),
+                )
+            }));
+            funding += report.get_funding();
+            round_trips.extend(report.get_round_trips().iter().cloned());
+            if !records.is_empty() {
+                let average_value =
+                    records.iter().map(|r| r.get_net_value()).sum::<f64>() / records.len() as f64;
+                traded_notional += report.get_turnover() * average_value;
+            }
+            fees += report.get_fees();
+        }
+        BacktestReport::from_records(
+            steps[0].out_sample_report.get_strategy_name(),
+            starting_cash,
+            equity_curve,
+            round_trips,
+            traded_notional,
+            fees,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_strategy::TargetPositionAdapter;
+    use crate::optimizer::{Objective, ParameterRange};
+    use crate::test_utils::{btcusdt_symbol_info, make_klines, HoldStrategy};
+
+    #[test]
+    fn test_walk_forward_windows() {
+        let windows = walk_forward_windows(100, 40, 25, 10);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(
+            windows[0],
+            WalkForwardWindow {
+                in_sample_start: 10,
+                out_sample_start: 50,
+                out_sample_end: 75,
+            }
+        );
+        assert_eq!(windows[1].in_sample_start, 35);
+        assert_eq!(windows[1].out_sample_end, 100);
+        assert!(walk_forward_windows(30, 40, 25, 0).is_empty());
+    }
+
+    #[test]
+    fn test_walk_forward_run() {
+        let klines = make_klines(60, 100.0, 1.0);
+        let mut optimizer = Optimizer::new(
+            vec!["btcusdt".to_string()],
+            10000.0,
+            10.0,
+            0,
+            vec![ParameterRange::new("qty", vec![1.0, 2.0])],
+            Objective::TotalReturn,
+        );
+        optimizer.set_data(
+            HashMap::from([("btcusdt".to_string(), klines)]),
+            HashMap::from([("btcusdt".to_string(), btcusdt_symbol_info())]),
+        );
+        let mut walk_forward = WalkForward::new(optimizer, 20, 10);
+        walk_forward.set_warmup_size(5);
+        let result = walk_forward
+            .run(|params| {
+                Box::new(TargetPositionAdapter::new(HoldStrategy(
+                    *params.get("qty").unwrap(),
+                )))
+            })
+            .unwrap();
+
+        assert_eq!(result.get_steps().len(), 4);
+        assert_eq!(result.get_steps()[0].get_params().get("qty"), Some(&2.0));
+        let equity_curve = result.get_report().get_equity_curve();
+        assert_eq!(equity_curve.len(), 35);
+        assert_eq!(equity_curve[0].get_timestamp(), 25 * 300_000);
+        // each out-of-sample run starts from the previous final value
+        let first = result.get_steps()[0].get_out_sample_report();
+        let second = result.get_steps()[1].get_out_sample_report();
+        assert_eq!(second.get_starting_cash(), first.get_final_value());
+        assert!(result.get_report().get_final_value() > 10000.0);
+    }
+}